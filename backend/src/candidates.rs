@@ -0,0 +1,323 @@
+use std::fmt;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Sub};
+
+use crate::GridSize;
+use crate::file_parser::digit_char;
+
+/// A set of sudoku digits stored as a bitmask, where bit `d - 1` is set if digit `d` is in the set.
+/// Digits go up to 16, for the largest grids; `GridSize::digits` gives every digit of a grid.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct DigitSet(u16);
+
+impl DigitSet {
+    pub const EMPTY: DigitSet = DigitSet(0);
+
+    /// Creates a set containing only `digit`.
+    pub fn single(digit: u8) -> Self {
+        DigitSet(1 << (digit - 1))
+    }
+
+    /// Creates a set from a slice of digits. Zeros (empty cells) are ignored.
+    pub fn from_digits(digits: &[u8]) -> Self {
+        digits.iter().copied().collect()
+    }
+
+    /// Creates a set from a raw bitmask, where bit `d - 1` represents digit `d`.
+    pub fn from_bits(bits: u16) -> Self {
+        DigitSet(bits)
+    }
+
+    pub fn bits(self) -> u16 {
+        self.0
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn contains(self, digit: u8) -> bool {
+        (1..=16).contains(&digit) && self.0 & (1 << (digit - 1)) != 0
+    }
+
+    pub fn insert(&mut self, digit: u8) {
+        if digit != 0 {
+            self.0 |= 1 << (digit - 1);
+        }
+    }
+
+    /// Removes `digit` from the set, returning `true` if it was present.
+    pub fn remove(&mut self, digit: u8) -> bool {
+        let present = self.contains(digit);
+        if present {
+            self.0 &= !(1 << (digit - 1));
+        }
+        present
+    }
+
+    pub fn is_subset(self, other: DigitSet) -> bool {
+        self.0 & !other.0 == 0
+    }
+
+    /// Returns the only digit in the set, or `None` if the set does not hold exactly one digit.
+    pub fn single_digit(self) -> Option<u8> {
+        (self.len() == 1).then(|| self.0.trailing_zeros() as u8 + 1)
+    }
+
+    pub fn min(self) -> Option<u8> {
+        (!self.is_empty()).then(|| self.0.trailing_zeros() as u8 + 1)
+    }
+
+    pub fn max(self) -> Option<u8> {
+        (!self.is_empty()).then(|| 16 - self.0.leading_zeros() as u8)
+    }
+
+    /// Iterates over the digits in the set in ascending order.
+    pub fn iter(self) -> DigitIter {
+        DigitIter(self.0)
+    }
+
+    pub fn to_vec(self) -> Vec<u8> {
+        self.iter().collect()
+    }
+}
+
+impl FromIterator<u8> for DigitSet {
+    fn from_iter<I: IntoIterator<Item = u8>>(iter: I) -> Self {
+        let mut set = DigitSet::EMPTY;
+        for digit in iter {
+            set.insert(digit);
+        }
+        set
+    }
+}
+
+impl IntoIterator for DigitSet {
+    type Item = u8;
+    type IntoIter = DigitIter;
+
+    fn into_iter(self) -> DigitIter {
+        self.iter()
+    }
+}
+
+impl BitOr for DigitSet {
+    type Output = DigitSet;
+
+    fn bitor(self, rhs: DigitSet) -> DigitSet {
+        DigitSet(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for DigitSet {
+    fn bitor_assign(&mut self, rhs: DigitSet) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for DigitSet {
+    type Output = DigitSet;
+
+    fn bitand(self, rhs: DigitSet) -> DigitSet {
+        DigitSet(self.0 & rhs.0)
+    }
+}
+
+impl BitAndAssign for DigitSet {
+    fn bitand_assign(&mut self, rhs: DigitSet) {
+        self.0 &= rhs.0;
+    }
+}
+
+impl Sub for DigitSet {
+    type Output = DigitSet;
+
+    fn sub(self, rhs: DigitSet) -> DigitSet {
+        DigitSet(self.0 & !rhs.0)
+    }
+}
+
+impl fmt::Debug for DigitSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl fmt::Display for DigitSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        write!(f, "{{{digits}}}")
+    }
+}
+
+/// Iterator over the digits of a `DigitSet`, in ascending order.
+pub struct DigitIter(u16);
+
+impl Iterator for DigitIter {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.0 == 0 {
+            return None;
+        }
+        let digit = self.0.trailing_zeros() as u8 + 1;
+        self.0 &= self.0 - 1;
+        Some(digit)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.0.count_ones() as usize;
+        (n, Some(n))
+    }
+}
+
+impl ExactSizeIterator for DigitIter {}
+
+/// A single candidate: a digit in a cell, as `((row, col), digit)`.
+pub type Candidate = ((usize, usize), u8);
+
+const MAX_SIDE: usize = GridSize::MAX_SIDE;
+
+/// Dense candidate store for a grid of any size, holding one `DigitSet` per cell.
+///
+/// Filled cells hold an empty set, so the grid alone decides whether an empty set is a
+/// contradiction. The store has room for the largest grid whatever its size, so it stays `Copy`,
+/// which keeps branching in the solver cheap.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct CandidateGrid {
+    size: GridSize,
+    cells: [DigitSet; MAX_SIDE * MAX_SIDE],
+}
+
+impl CandidateGrid {
+    /// An empty store for a classic 9x9 grid.
+    pub fn new() -> Self {
+        CandidateGrid::with_size(GridSize::CLASSIC)
+    }
+
+    pub fn with_size(size: GridSize) -> Self {
+        CandidateGrid {
+            size,
+            cells: [DigitSet::EMPTY; MAX_SIDE * MAX_SIDE],
+        }
+    }
+
+    pub fn size(&self) -> GridSize {
+        self.size
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> DigitSet {
+        self.cells[row * MAX_SIDE + col]
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, digits: DigitSet) {
+        self.cells[row * MAX_SIDE + col] = digits;
+    }
+
+    /// Removes `digit` from the cell's candidates, returning `true` if it was present.
+    pub fn remove(&mut self, row: usize, col: usize, digit: u8) -> bool {
+        self.cells[row * MAX_SIDE + col].remove(digit)
+    }
+
+    /// Intersects the cell's candidates with `allowed`, returning `true` if anything was removed.
+    pub fn restrict(&mut self, row: usize, col: usize, allowed: DigitSet) -> bool {
+        let cell = &mut self.cells[row * MAX_SIDE + col];
+        let before = *cell;
+        *cell &= allowed;
+        before != *cell
+    }
+
+    /// Total number of candidates left across the grid.
+    pub fn count(&self) -> usize {
+        self.cells.iter().map(|set| set.len()).sum()
+    }
+}
+
+impl Default for CandidateGrid {
+    fn default() -> Self {
+        CandidateGrid::new()
+    }
+}
+
+impl fmt::Debug for CandidateGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = self.size.side();
+        for row in self.cells.chunks(MAX_SIDE).take(side) {
+            let line = row[..side]
+                .iter()
+                .map(|set| format!("{:<side$}", set.iter().map(digit_char).collect::<String>()))
+                .collect::<Vec<_>>()
+                .join(" ");
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digit_set_basic_operations() {
+        let mut set = DigitSet::from_digits(&[1, 5, 9]);
+        assert_eq!(set.len(), 3);
+        assert!(set.contains(5));
+        assert!(!set.contains(4));
+        assert!(!set.contains(0));
+        assert!(set.remove(5));
+        assert!(!set.remove(5));
+        set.insert(2);
+        assert_eq!(set.to_vec(), vec![1, 2, 9]);
+        assert_eq!(set.min(), Some(1));
+        assert_eq!(set.max(), Some(9));
+        assert_eq!(DigitSet::single(7).single_digit(), Some(7));
+        assert_eq!(set.single_digit(), None);
+        assert_eq!(format!("{set}"), "{1,2,9}");
+    }
+
+    #[test]
+    fn digit_set_operators() {
+        let a = DigitSet::from_digits(&[1, 2, 3]);
+        let b = DigitSet::from_digits(&[3, 4]);
+        assert_eq!((a | b).to_vec(), vec![1, 2, 3, 4]);
+        assert_eq!((a & b).to_vec(), vec![3]);
+        assert_eq!((a - b).to_vec(), vec![1, 2]);
+        assert_eq!(
+            (GridSize::CLASSIC.digits() - a).to_vec(),
+            vec![4, 5, 6, 7, 8, 9]
+        );
+        assert!(DigitSet::single(3).is_subset(a));
+        assert!(!b.is_subset(a));
+    }
+
+    #[test]
+    fn candidate_grid_restrict() {
+        let mut grid = CandidateGrid::new();
+        grid.set(4, 4, GridSize::CLASSIC.digits());
+        assert!(grid.restrict(4, 4, DigitSet::from_digits(&[2, 4, 6])));
+        assert!(!grid.restrict(4, 4, GridSize::CLASSIC.digits()));
+        assert_eq!(grid.get(4, 4).to_vec(), vec![2, 4, 6]);
+        assert!(grid.remove(4, 4, 4));
+        assert_eq!(grid.count(), 2);
+    }
+
+    #[test]
+    fn candidate_grid_holds_digits_above_nine() {
+        let size = GridSize::for_side(16).unwrap();
+        let mut grid = CandidateGrid::with_size(size);
+        grid.set(15, 15, size.digits());
+        assert!(grid.remove(15, 15, 16));
+        assert!(grid.restrict(15, 15, DigitSet::from_digits(&[9, 10, 15])));
+        assert_eq!(grid.get(15, 15).to_vec(), vec![9, 10, 15]);
+        assert_eq!(grid.count(), 3);
+        assert!(format!("{grid:?}").ends_with("9AF\n"));
+    }
+}
@@ -0,0 +1,282 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::solver::cell_list;
+use crate::variant::repeats;
+use crate::{SudokuGrid, SudokuVariant, Unit};
+
+/// The rule a `Violation` breaks. Indices are 0-based.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Rule {
+    Row(usize),
+    Column(usize),
+    Box(usize),
+    Variant(SudokuVariant),
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rule::Row(r) => write!(f, "Row {}", r + 1),
+            Rule::Column(c) => write!(f, "Column {}", c + 1),
+            Rule::Box(b) => write!(f, "Box {}", b + 1),
+            Rule::Variant(variant) => write!(f, "{variant}"),
+        }
+    }
+}
+
+/// One way a grid breaks the rules, as found by `check`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Violation {
+    pub rule: Rule,
+    /// The cells at fault, e.g. both cells holding a repeated digit.
+    pub cells: Vec<(usize, usize)>,
+    /// What's wrong, e.g. "Killer cage sums to 14, expected 11".
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Lists everything wrong with a filled-in grid: empty cells and repeated digits in each row,
+/// column and box, then each broken part of every variant. A correct solution has none.
+///
+/// Variants only report on the cells that are filled, so a partly filled grid is checked for
+/// the mistakes already made.
+pub fn check(grid: &SudokuGrid) -> Vec<Violation> {
+    let size = grid.size();
+    let mut violations = Vec::new();
+    for row in 0..size.side() {
+        let empty: Vec<(usize, usize)> = (0..size.side())
+            .map(|col| (row, col))
+            .filter(|&(r, c)| grid.get_cell(r, c) == 0)
+            .collect();
+        if !empty.is_empty() {
+            violations.push(Violation {
+                rule: Rule::Row(row),
+                message: format!("Row {} has empty cells at {}", row + 1, cell_list(&empty)),
+                cells: empty,
+            });
+        }
+    }
+    for unit in Unit::all(size) {
+        let rule = match unit {
+            Unit::Row(r) => Rule::Row(r),
+            Unit::Column(c) => Rule::Column(c),
+            Unit::Box(b) => Rule::Box(b),
+            Unit::House { .. } | Unit::Group { .. } => continue,
+        };
+        for (cells, message) in repeats(grid, &unit.cells(size), &rule.to_string()) {
+            violations.push(Violation {
+                rule: rule.clone(),
+                cells,
+                message,
+            });
+        }
+    }
+    for variant in grid.variants() {
+        for (cells, message) in variant.violations(grid) {
+            violations.push(Violation {
+                rule: Rule::Variant(variant.clone()),
+                cells,
+                message,
+            });
+        }
+    }
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Solver;
+
+    fn with_variant(mut grid: SudokuGrid, line: &str) -> SudokuGrid {
+        grid.add_variant(SudokuVariant::parse(line).unwrap());
+        grid
+    }
+
+    fn messages(grid: &SudokuGrid) -> Vec<String> {
+        check(grid).into_iter().map(|v| v.message).collect()
+    }
+
+    mod classic {
+        use super::*;
+        use crate::test_support::{example, pattern};
+
+        #[test]
+        fn test_solution_has_no_violations() {
+            let mut grid = example("draft_day.txt");
+            Solver::new(&mut grid).unwrap().solve();
+            assert_eq!(check(&grid), vec![]);
+            assert_eq!(check(&pattern()), vec![]);
+        }
+
+        #[test]
+        fn test_empty_cells_are_reported_by_row() {
+            let mut grid = pattern();
+            grid.set_cell(2, 3, 0);
+            grid.set_cell(2, 7, 0);
+            let violations = check(&grid);
+            assert_eq!(violations.len(), 1);
+            assert_eq!(violations[0].rule, Rule::Row(2));
+            assert_eq!(violations[0].cells, vec![(2, 3), (2, 7)]);
+            assert_eq!(
+                violations[0].message,
+                "Row 3 has empty cells at r3c4 and r3c8"
+            );
+        }
+
+        #[test]
+        fn test_repeated_digit_names_row_column_and_box() {
+            let mut grid = pattern();
+            // 1 is already at (0, 0) in the row and box, and at (8, 1) in the column
+            grid.set_cell(0, 1, 1);
+            let violations = check(&grid);
+            let rules: Vec<&Rule> = violations.iter().map(|v| &v.rule).collect();
+            assert_eq!(rules, vec![&Rule::Row(0), &Rule::Column(1), &Rule::Box(0)]);
+            assert_eq!(violations[0].message, "Row 1 repeats 1 at r1c1 and r1c2");
+            assert_eq!(violations[0].cells, vec![(0, 0), (0, 1)]);
+        }
+    }
+
+    mod variants {
+        use super::*;
+        use crate::test_support::pattern;
+
+        #[test]
+        fn test_killer_sum() {
+            // (0, 4) and (0, 8) hold 5 and 9
+            let grid = with_variant(pattern(), "killer: ((0, 4), (0, 8)): 11");
+            let violations = check(&grid);
+            assert_eq!(violations.len(), 1);
+            assert!(matches!(
+                violations[0].rule,
+                Rule::Variant(SudokuVariant::Killer(_))
+            ));
+            assert_eq!(violations[0].cells, vec![(0, 4), (0, 8)]);
+            assert_eq!(violations[0].message, "Killer cage sums to 14, expected 11");
+        }
+
+        #[test]
+        fn test_kropki_holds() {
+            let mut grid = SudokuGrid::empty();
+            grid.set_cell(2, 0, 3);
+            grid.set_cell(2, 1, 7);
+            let grid = with_variant(grid, "kropki: ((2, 0), (2, 1)): white");
+            let variant_messages: Vec<String> = check(&grid)
+                .into_iter()
+                .filter(|v| matches!(v.rule, Rule::Variant(_)))
+                .map(|v| v.message)
+                .collect();
+            assert_eq!(
+                variant_messages,
+                vec!["White Kropki between r3c1 and r3c2 holds 3 and 7"]
+            );
+        }
+
+        #[test]
+        fn test_messages_per_variant() {
+            // Digits in the pattern: row 0 is 1-9, row 1 starts 4 5 6 7
+            let cases = [
+                (
+                    "thermometer: ((0, 2), (0, 1))",
+                    "Thermometer holds 3 at r1c3 then 2 at r1c2",
+                ),
+                (
+                    "arrow: ((0, 3), (0, 0), (0, 1))",
+                    "Arrow from r1c4 sums to 3, but its circle holds 4",
+                ),
+                (
+                    "renban: ((0, 0), (0, 1), (0, 3))",
+                    "Renban line holds 1, 2, 4, which aren't consecutive",
+                ),
+                (
+                    "xv: ((0, 0), (0, 3)): x",
+                    "X between r1c1 and r1c4 sums to 5, expected 10",
+                ),
+                (
+                    "german whisper: ((0, 0), (0, 1))",
+                    "German whisper between r1c1 and r1c2 holds 1 and 2",
+                ),
+                (
+                    "nabner: ((0, 0), (0, 2), (0, 3))",
+                    "Nabner line holds 3 and 4 at r1c3 and r1c4",
+                ),
+                (
+                    "entropic: ((0, 0), (0, 3), (0, 1))",
+                    "Entropic line holds 1 and 2, both low, at r1c1 and r1c2",
+                ),
+                (
+                    "region sum: ((0, 0), (0, 3))",
+                    "Region sum line segments differ: 1 in box 1, 4 in box 2",
+                ),
+                (
+                    "quadruple: ((0, 0), (0, 1), (1, 0), (1, 1)): 3, 5",
+                    "Quadruple circle at r1c1 is missing 3",
+                ),
+                (
+                    "anti quadruple: ((0, 0), (0, 1), (1, 0), (1, 1)): 1, 3",
+                    "Anti-quadruple circle at r1c1 holds a ruled out digit at r1c1",
+                ),
+                (
+                    "shaded: ((0, 0)): square",
+                    "Square at r1c1 holds 1, expected an even digit",
+                ),
+            ];
+            for (line, message) in cases {
+                let grid = with_variant(pattern(), line);
+                assert_eq!(messages(&grid), vec![message], "{line}");
+            }
+
+            let grid = with_variant(pattern(), "diagonal: negative");
+            assert_eq!(
+                messages(&grid),
+                vec![
+                    "Negative Diagonal repeats 4 at r6c6 and r8c8",
+                    "Negative Diagonal repeats 5 at r2c2 and r4c4",
+                    "Negative Diagonal repeats 9 at r3c3, r5c5 and r7c7",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_chess_pairs_are_reported_once() {
+            let mut king = with_variant(SudokuGrid::empty(), "king:");
+            king.set_cell(0, 0, 5);
+            king.set_cell(1, 1, 5);
+            let mut knight = with_variant(SudokuGrid::empty(), "knight:");
+            knight.set_cell(0, 0, 5);
+            knight.set_cell(1, 2, 5);
+            let variant_messages = |grid: &SudokuGrid| -> Vec<String> {
+                check(grid)
+                    .into_iter()
+                    .filter(|v| matches!(v.rule, Rule::Variant(_)))
+                    .map(|v| v.message)
+                    .collect()
+            };
+            assert_eq!(
+                variant_messages(&king),
+                vec!["r1c1 and r2c2 are a king's move apart and both hold 5"]
+            );
+            assert_eq!(
+                variant_messages(&knight),
+                vec!["r1c1 and r2c3 are a knight's move apart and both hold 5"]
+            );
+        }
+
+        #[test]
+        fn test_only_filled_cells_are_checked() {
+            let grid = with_variant(SudokuGrid::empty(), "killer: ((0, 0), (0, 1)): 11");
+            assert!(
+                check(&grid)
+                    .iter()
+                    .all(|v| !matches!(v.rule, Rule::Variant(_)))
+            );
+        }
+    }
+}
@@ -0,0 +1,519 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use itertools::Itertools;
+
+use crate::{GridSize, SudokuGrid, Unit};
+
+/// A sudoku, variants included, as a boolean formula in conjunctive normal form, for handing
+/// to an off-the-shelf SAT solver.
+///
+/// On a grid `n` cells wide, variable `n * n * row + n * col + digit` (1-based digits, so 1 to
+/// 729 on a classic grid) means "(`row`, `col`) holds `digit`"; any further variables are
+/// helpers introduced by the variants' encodings.
+///
+/// ```
+/// use sudoku_solver::{Cnf, SudokuGrid};
+///
+/// let grid = SudokuGrid::empty();
+/// let cnf = Cnf::encode(&grid);
+/// assert!(cnf.to_dimacs().starts_with("p cnf 729 "));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cnf {
+    grid: SudokuGrid,
+    variables: usize,
+    clauses: Vec<Vec<i32>>,
+}
+
+impl Cnf {
+    /// Encodes the givens, the classic rules and every variant of `grid`.
+    ///
+    /// The rules variants share through `seen_cells` and `houses` are encoded here, and each
+    /// variant adds the rest of its rules with `Variant::encode`.
+    pub fn encode(grid: &SudokuGrid) -> Cnf {
+        let size = grid.size();
+        let mut cnf = Cnf {
+            grid: grid.clone(),
+            variables: size.side().pow(3),
+            clauses: Vec::new(),
+        };
+        for (row, col) in size.cells() {
+            let digits = (1..=size.max_digit()).map(|d| cnf.cell((row, col), d));
+            cnf.exactly_one(&digits.collect_vec());
+            let given = grid.get_cell(row, col);
+            if given != 0 {
+                cnf.add_clause([cnf.cell((row, col), given)]);
+            }
+        }
+        let variant_houses = grid.variants().flat_map(|variant| variant.houses(size));
+        let houses: Vec<Vec<(usize, usize)>> = Unit::all(size)
+            .iter()
+            .map(|unit| unit.cells(size))
+            .chain(variant_houses)
+            .collect();
+        for cells in &houses {
+            for digit in 1..=size.max_digit() {
+                cnf.exactly_one(&cells.iter().map(|&c| cnf.cell(c, digit)).collect_vec());
+            }
+        }
+
+        // Cells a variant forbids from sharing a digit, skipping those already in a house
+        let mut pairs = HashSet::new();
+        for variant in grid.variants() {
+            for a in variant.constrained_cells(size) {
+                for b in variant.seen_cells(size, a.0, a.1) {
+                    let shares_house = houses
+                        .iter()
+                        .any(|cells| cells.contains(&a) && cells.contains(&b));
+                    if a != b && !shares_house {
+                        pairs.insert((a.min(b), a.max(b)));
+                    }
+                }
+            }
+        }
+        for (a, b) in pairs.into_iter().sorted() {
+            cnf.forbid_pairs(a, b, |d1, d2| d1 != d2);
+        }
+
+        for variant in grid.variants() {
+            variant.encode(&mut cnf);
+        }
+        cnf
+    }
+
+    pub fn variables(&self) -> usize {
+        self.variables
+    }
+
+    pub fn clauses(&self) -> &[Vec<i32>] {
+        &self.clauses
+    }
+
+    /// The formula in the DIMACS format read by SAT solvers.
+    pub fn to_dimacs(&self) -> String {
+        let mut dimacs = format!("p cnf {} {}\n", self.variables, self.clauses.len());
+        for clause in &self.clauses {
+            for literal in clause {
+                dimacs.push_str(&literal.to_string());
+                dimacs.push(' ');
+            }
+            dimacs.push_str("0\n");
+        }
+        dimacs
+    }
+
+    pub fn write_dimacs(&self, path: &Path) -> std::io::Result<()> {
+        fs::write(path, self.to_dimacs())
+    }
+
+    /// Rebuilds the solved grid from a SAT solver's output, e.g. `s SATISFIABLE` followed by
+    /// `v` lines of literals as in the SAT competition format, or MiniSat's `SAT` followed by
+    /// the literals. Lines starting with `c` are ignored.
+    pub fn decode_model(&self, model: &str) -> Result<SudokuGrid, ModelError> {
+        let mut grid = self.grid.clone();
+        let side = self.size().side();
+        let mut assigned = vec![vec![false; side]; side];
+        for line in model.lines().map(str::trim) {
+            let literals = match line.split_whitespace().next() {
+                None | Some("c") => continue,
+                Some("s") | Some("SAT") | Some("UNSAT") | Some("INDET") => {
+                    if line.contains("UNSAT") {
+                        return Err(ModelError::Unsatisfiable);
+                    }
+                    if line.contains("INDET") || line.contains("UNKNOWN") {
+                        return Err(ModelError::Unknown);
+                    }
+                    continue;
+                }
+                Some("v") => &line[1..],
+                Some(_) => line,
+            };
+            for token in literals.split_whitespace() {
+                let literal: i32 = token
+                    .parse()
+                    .map_err(|_| ModelError::Malformed(token.to_string()))?;
+                if literal <= 0 || literal as usize > side.pow(3) {
+                    continue;
+                }
+                let index = literal as usize - 1;
+                let (row, col) = (index / (side * side), index / side % side);
+                let digit = (index % side) as u8 + 1;
+                if assigned[row][col] {
+                    return Err(ModelError::Conflict((row, col)));
+                }
+                assigned[row][col] = true;
+                grid.set_cell(row, col, digit);
+            }
+        }
+        match self.size().cells().find(|&(row, col)| !assigned[row][col]) {
+            Some(cell) => Err(ModelError::Unassigned(cell)),
+            None => Ok(grid),
+        }
+    }
+
+    pub fn decode_model_file(&self, path: &Path) -> Result<SudokuGrid, ModelError> {
+        let model = fs::read_to_string(path).map_err(|e| ModelError::Malformed(e.to_string()))?;
+        self.decode_model(&model)
+    }
+
+    /// The size of the grid being encoded.
+    pub(crate) fn size(&self) -> GridSize {
+        self.grid.size()
+    }
+
+    /// The literal for "`cell` holds `digit`".
+    pub(crate) fn cell(&self, (row, col): (usize, usize), digit: u8) -> i32 {
+        let side = self.size().side();
+        (row * side * side + col * side + digit as usize) as i32
+    }
+
+    pub(crate) fn new_variable(&mut self) -> i32 {
+        self.variables += 1;
+        self.variables as i32
+    }
+
+    pub(crate) fn add_clause(&mut self, clause: impl IntoIterator<Item = i32>) {
+        self.clauses.push(clause.into_iter().collect());
+    }
+
+    /// Pairwise at-most-one, plus at-least-one.
+    pub(crate) fn exactly_one(&mut self, literals: &[i32]) {
+        self.add_clause(literals.iter().copied());
+        for (a, b) in literals.iter().tuple_combinations() {
+            self.add_clause([-a, -b]);
+        }
+    }
+
+    /// Forbids every pair of digits in cells `a` and `b` that `allowed` rejects.
+    pub(crate) fn forbid_pairs(
+        &mut self,
+        a: (usize, usize),
+        b: (usize, usize),
+        allowed: impl Fn(u8, u8) -> bool,
+    ) {
+        let max_digit = self.size().max_digit();
+        for d1 in 1..=max_digit {
+            for d2 in 1..=max_digit {
+                if !allowed(d1, d2) {
+                    self.add_clause([-self.cell(a, d1), -self.cell(b, d2)]);
+                }
+            }
+        }
+    }
+
+    /// Encodes the sum of `cells` with a chain of partial sums (a unary adder), returning the
+    /// literal for "the cells sum to `v`" at index `v`, or `None` for sums they can't reach.
+    /// Sums above `max` are forbidden outright, and exactly one of the literals is true.
+    pub(crate) fn sum(&mut self, cells: &[(usize, usize)], max: usize) -> Vec<Option<i32>> {
+        let mut sums: Vec<Option<i32>> = vec![None; max + 1];
+        let Some((&first, rest)) = cells.split_first() else {
+            return sums;
+        };
+        let max_digit = self.size().max_digit();
+        for digit in 1..=max_digit {
+            match sums.get_mut(digit as usize) {
+                Some(sum) => *sum = Some(self.cell(first, digit)),
+                None => self.add_clause([-self.cell(first, digit)]),
+            }
+        }
+        for &cell in rest {
+            let mut next: Vec<Option<i32>> = vec![None; max + 1];
+            for (before, literal) in sums.iter().enumerate() {
+                let Some(literal) = *literal else { continue };
+                for digit in 1..=max_digit {
+                    let digit_literal = self.cell(cell, digit);
+                    let Some(slot) = next.get_mut(before + digit as usize) else {
+                        self.add_clause([-literal, -digit_literal]);
+                        continue;
+                    };
+                    let sum = match *slot {
+                        Some(sum) => sum,
+                        None => *slot.insert(self.new_variable()),
+                    };
+                    self.add_clause([-literal, -digit_literal, sum]);
+                }
+            }
+            sums = next;
+        }
+        let reachable = sums.iter().flatten().copied().collect_vec();
+        for (a, b) in reachable.iter().tuple_combinations() {
+            self.add_clause([-a, -b]);
+        }
+        sums
+    }
+}
+
+/// Why a SAT solver's output couldn't be turned into a grid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModelError {
+    /// The solver proved there is no solution.
+    Unsatisfiable,
+    /// The solver gave up without an answer.
+    Unknown,
+    /// Something that isn't a literal, or a model file that couldn't be read.
+    Malformed(String),
+    /// The model puts two digits in the cell.
+    Conflict((usize, usize)),
+    /// The model puts no digit in the cell.
+    Unassigned((usize, usize)),
+}
+
+impl std::error::Error for ModelError {}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelError::Unsatisfiable => write!(f, "The puzzle has no solution"),
+            ModelError::Unknown => write!(f, "The SAT solver gave up"),
+            ModelError::Malformed(reason) => write!(f, "Malformed model: {reason}"),
+            ModelError::Conflict((row, col)) => {
+                write!(f, "More than one digit at ({row}, {col})")
+            }
+            ModelError::Unassigned((row, col)) => write!(f, "No digit at ({row}, {col})"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SolveOutcome, Solver, SudokuVariant};
+
+    fn solved(grid: &SudokuGrid) -> SudokuGrid {
+        let mut grid = grid.clone();
+        assert_eq!(
+            Solver::new(&mut grid).unwrap().solve(),
+            SolveOutcome::Solved
+        );
+        grid
+    }
+
+    fn with_variant(line: &str) -> SudokuGrid {
+        let mut grid = SudokuGrid::empty();
+        grid.add_variant(SudokuVariant::parse(line).unwrap());
+        grid
+    }
+
+    /// Whether the digits of `solution` extend to a model of `cnf`: the cell variables are
+    /// fixed from the grid, helpers are set by unit propagation and any left over are false.
+    fn satisfies(cnf: &Cnf, solution: &SudokuGrid) -> bool {
+        let mut values: Vec<Option<bool>> = vec![None; cnf.variables() + 1];
+        for row in 0..9 {
+            for col in 0..9 {
+                for digit in 1..=9 {
+                    let value = solution.get_cell(row, col) == digit;
+                    values[cnf.cell((row, col), digit) as usize] = Some(value);
+                }
+            }
+        }
+        let value = |values: &[Option<bool>], literal: i32| {
+            values[literal.unsigned_abs() as usize].map(|v| v == (literal > 0))
+        };
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for clause in cnf.clauses() {
+                if clause.iter().any(|&l| value(&values, l) == Some(true)) {
+                    continue;
+                }
+                let open = clause
+                    .iter()
+                    .filter(|&&l| value(&values, l).is_none())
+                    .collect_vec();
+                match open[..] {
+                    [] => return false,
+                    [&literal] => {
+                        values[literal.unsigned_abs() as usize] = Some(literal > 0);
+                        changed = true;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        cnf.clauses()
+            .iter()
+            .all(|clause| clause.iter().any(|&l| value(&values, l).unwrap_or(l < 0)))
+    }
+
+    /// The model a SAT solver would print for `solution`, cell variables only.
+    fn model(solution: &SudokuGrid) -> String {
+        let mut model = String::from("s SATISFIABLE\nv");
+        for literal in 1..=729 {
+            let index = literal as usize - 1;
+            let digit = solution.get_cell(index / 81, index / 9 % 9);
+            let sign = if digit as usize == index % 9 + 1 {
+                ""
+            } else {
+                "-"
+            };
+            model.push_str(&format!(" {sign}{literal}"));
+        }
+        model.push_str(" 0\n");
+        model
+    }
+
+    mod encode {
+        use super::*;
+        use crate::test_support::{example, pattern};
+
+        #[test]
+        fn test_solution_satisfies_classic_rules() {
+            let grid = example("sudoku.txt");
+            let cnf = Cnf::encode(&grid);
+            assert!(satisfies(&cnf, &solved(&grid)));
+            assert!(satisfies(&Cnf::encode(&SudokuGrid::empty()), &pattern()));
+        }
+
+        #[test]
+        fn test_solution_satisfies_variants() {
+            for name in ["draft_day.txt", "pelican.txt"] {
+                let grid = example(name);
+                assert!(satisfies(&Cnf::encode(&grid), &solved(&grid)), "{name}");
+            }
+        }
+
+        #[test]
+        fn test_wrong_digit_violates_givens() {
+            let grid = example("sudoku.txt");
+            let mut solution = solved(&grid);
+            // (0, 2) is a given 9
+            solution.set_cell(0, 2, 8);
+            assert!(!satisfies(&Cnf::encode(&grid), &solution));
+        }
+
+        #[test]
+        fn test_each_variant_accepts_and_rejects() {
+            // Digits in the pattern: row 0 is 1-9, row 1 starts 4 5 6 7
+            let cases = [
+                (
+                    "thermometer: ((0, 0), (0, 1), (0, 2))",
+                    "thermometer: ((0, 2), (0, 1), (0, 0))",
+                ),
+                ("killer: ((0, 0), (0, 1)): 3", "killer: ((0, 0), (0, 1)): 4"),
+                (
+                    "arrow: ((0, 2), (0, 0), (0, 1))",
+                    "arrow: ((0, 3), (0, 0), (0, 1))",
+                ),
+                (
+                    "renban: ((0, 2), (0, 0), (0, 1))",
+                    "renban: ((0, 0), (0, 1), (0, 3))",
+                ),
+                (
+                    "kropki: ((0, 0), (0, 1)): black",
+                    "kropki: ((0, 1), (0, 3)): white",
+                ),
+                ("xv: ((0, 0), (0, 3)): v", "xv: ((0, 0), (0, 3)): x"),
+                (
+                    "german whisper: ((0, 0), (0, 5))",
+                    "german whisper: ((0, 0), (0, 1))",
+                ),
+                (
+                    "nabner: ((0, 0), (0, 2), (0, 4))",
+                    "nabner: ((0, 0), (0, 2), (0, 3))",
+                ),
+                (
+                    "entropic: ((0, 0), (0, 3), (0, 6))",
+                    "entropic: ((0, 0), (0, 1), (0, 3))",
+                ),
+                (
+                    "region sum: ((1, 0), (0, 3))",
+                    "region sum: ((0, 0), (0, 3))",
+                ),
+                (
+                    "quadruple: ((0, 0), (0, 1), (1, 0), (1, 1)): 1, 5",
+                    "quadruple: ((0, 0), (0, 1), (1, 0), (1, 1)): 3",
+                ),
+                (
+                    "anti quadruple: ((0, 0), (0, 1), (1, 0), (1, 1)): 3",
+                    "anti quadruple: ((0, 0), (0, 1), (1, 0), (1, 1)): 1",
+                ),
+                ("shaded: ((0, 0)): circle", "shaded: ((0, 0)): square"),
+            ];
+            for (accepted, rejected) in cases {
+                let cnf = Cnf::encode(&with_variant(accepted));
+                assert!(satisfies(&cnf, &pattern()), "{accepted}");
+                let cnf = Cnf::encode(&with_variant(rejected));
+                assert!(!satisfies(&cnf, &pattern()), "{rejected}");
+            }
+        }
+
+        #[test]
+        fn test_diagonal_is_a_house() {
+            // The pattern repeats 5 on the main diagonal
+            let cnf = Cnf::encode(&with_variant("diagonal: positive"));
+            assert!(!satisfies(&cnf, &pattern()));
+        }
+    }
+
+    mod dimacs {
+        use super::*;
+
+        #[test]
+        fn test_header_counts_variables_and_clauses() {
+            let cnf = Cnf::encode(&with_variant("killer: ((0, 0), (0, 1)): 3"));
+            assert!(cnf.variables() > 729);
+            let dimacs = cnf.to_dimacs();
+            let mut lines = dimacs.lines();
+            let header = format!("p cnf {} {}", cnf.variables(), cnf.clauses().len());
+            assert_eq!(lines.next(), Some(header.as_str()));
+            assert_eq!(lines.count(), cnf.clauses().len());
+            assert!(dimacs.lines().skip(1).all(|line| line.ends_with(" 0")));
+        }
+    }
+
+    mod decode {
+        use super::*;
+        use crate::test_support::example;
+
+        #[test]
+        fn test_model_round_trips() {
+            let grid = example("sudoku.txt");
+            let solution = solved(&grid);
+            let cnf = Cnf::encode(&grid);
+            let decoded = cnf.decode_model(&model(&solution)).unwrap();
+            assert_eq!(decoded.get_cells(), solution.get_cells());
+        }
+
+        #[test]
+        fn test_minisat_output_with_helpers() {
+            let grid = example("sudoku.txt");
+            let solution = solved(&grid);
+            let cnf = Cnf::encode(&grid);
+            let minisat = model(&solution)
+                .replace("s SATISFIABLE\nv", "c solved\nSAT\n")
+                .replace(" 0\n", " 730 -731 0\n");
+            let decoded = cnf.decode_model(&minisat).unwrap();
+            assert_eq!(decoded.get_cells(), solution.get_cells());
+        }
+
+        #[test]
+        fn test_unsatisfiable() {
+            let cnf = Cnf::encode(&SudokuGrid::empty());
+            assert_eq!(
+                cnf.decode_model("s UNSATISFIABLE\n"),
+                Err(ModelError::Unsatisfiable)
+            );
+            assert_eq!(cnf.decode_model("INDET\n"), Err(ModelError::Unknown));
+        }
+
+        #[test]
+        fn test_bad_models() {
+            let cnf = Cnf::encode(&SudokuGrid::empty());
+            assert_eq!(
+                cnf.decode_model("v 1 -2 0\n"),
+                Err(ModelError::Unassigned((0, 1)))
+            );
+            assert_eq!(
+                cnf.decode_model("v 1 2 0\n"),
+                Err(ModelError::Conflict((0, 0)))
+            );
+            assert_eq!(
+                cnf.decode_model("v 1 x 0\n"),
+                Err(ModelError::Malformed("x".to_string()))
+            );
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{SudokuGrid, SudokuVariant};
+use crate::{Entropic, GridSize, SudokuGrid, SudokuVariant};
 use std::{
     env,
     fs::File,
@@ -6,41 +6,55 @@ use std::{
     path::Path,
 };
 
+/// Reads a puzzle: the grid one row per line, followed by any variants, one per line.
+///
+/// The first row sets the size of the grid, which takes the squarest boxes that fit (see
+/// `GridSize::for_side`) unless a `boxes: 3x2` line gives their rows and columns. Each row is
+/// either one character per cell, with digits above 9 written as letters from `A` (10) to `G`
+/// (16), or numbers separated by spaces. Empty cells are `.` or `0`.
 pub fn parse_file(path: &Path) -> Result<SudokuGrid, Error> {
     let file = File::open(path)?;
     let reader = BufReader::new(file);
     let mut lines = reader.lines();
 
-    let mut sudoku_grid = SudokuGrid::default();
-
-    for row in 0..9 {
+    let mut next_row = || -> Result<Vec<u8>, Error> {
         let line = lines.next().ok_or_else(|| {
             Error::new(
                 ErrorKind::UnexpectedEof,
                 "Unexpected end of file while reading grid",
             )
         })??;
-
-        let chars: Vec<char> = line.chars().collect();
-        for (col, ch) in chars.iter().enumerate() {
-            if let Some(num) = ch.to_digit(10) {
-                sudoku_grid.set_cell(row, col, num as u8);
-            } else if *ch != '.' {
-                return Err(Error::new(
-                    ErrorKind::InvalidData,
-                    "Invalid character in input",
-                ));
-            }
-        }
+        parse_row(&line)
+    };
+    let first = next_row()?;
+    let mut size = GridSize::for_side(first.len()).ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("Unsupported grid width: {}", first.len()),
+        )
+    })?;
+    let mut rows = vec![first];
+    for _ in 1..size.side() {
+        rows.push(next_row()?);
     }
 
     // Process any variants in the file
+    let mut variants = Vec::new();
     for line in lines {
         let line = line?.trim().to_string();
         if let Some(variant) = SudokuVariant::parse(&line) {
-            sudoku_grid.add_variant(variant);
+            variants.push(variant);
         } else if line.eq_ignore_ascii_case("solution:") {
             break;
+        } else if let Some(boxes) = parse_boxes(&line) {
+            size = boxes
+                .filter(|boxes| boxes.side() == size.side())
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Boxes don't tile the grid: {line}"),
+                    )
+                })?;
         } else if !line.is_empty() {
             return Err(Error::new(
                 ErrorKind::InvalidData,
@@ -48,9 +62,85 @@ pub fn parse_file(path: &Path) -> Result<SudokuGrid, Error> {
             ));
         }
     }
+
+    let entropic = variants
+        .iter()
+        .any(|variant| matches!(variant, SudokuVariant::Entropic(_)));
+    if entropic && !Entropic::supports(size) {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Entropic lines don't fit a {size} grid"),
+        ));
+    }
+
+    let mut sudoku_grid = SudokuGrid::with_size(size);
+    for (row, values) in rows.into_iter().enumerate() {
+        if values.len() != size.side() || values.iter().any(|&v| v > size.max_digit()) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Row {} doesn't fit a {size} grid", row + 1),
+            ));
+        }
+        for (col, value) in values.into_iter().enumerate() {
+            sudoku_grid.set_cell(row, col, value);
+        }
+    }
+    for variant in variants {
+        sudoku_grid.add_variant(variant);
+    }
     Ok(sudoku_grid)
 }
 
+/// The digits of a row of the grid, 0 for an empty cell.
+fn parse_row(line: &str) -> Result<Vec<u8>, Error> {
+    let line = line.trim();
+    let values: Option<Vec<u8>> = if line.contains(char::is_whitespace) {
+        line.split_whitespace()
+            .map(|token| match token.parse() {
+                Ok(value) => Some(value),
+                Err(_) => token.parse().ok().and_then(char_digit),
+            })
+            .collect()
+    } else {
+        line.chars().map(char_digit).collect()
+    };
+    values.ok_or_else(|| Error::new(ErrorKind::InvalidData, "Invalid character in input"))
+}
+
+/// Parses a `boxes: RxC` line, giving `Some(None)` if the shape is out of range.
+fn parse_boxes(line: &str) -> Option<Option<GridSize>> {
+    let (key, shape) = line.split_once(':')?;
+    if !key.trim().eq_ignore_ascii_case("boxes") {
+        return None;
+    }
+    let (rows, cols) = shape.trim().split_once(['x', 'X'])?;
+    Some(GridSize::new(
+        rows.trim().parse().ok()?,
+        cols.trim().parse().ok()?,
+    ))
+}
+
+/// The digit a grid character stands for: `1`-`9`, then `A`-`G` for 10 to 16, and 0 for an
+/// empty cell written `.` or `0`.
+fn char_digit(ch: char) -> Option<u8> {
+    match ch.to_ascii_uppercase() {
+        '.' => Some(0),
+        ch @ '0'..='9' => Some(ch as u8 - b'0'),
+        ch @ 'A'..='G' => Some(ch as u8 - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// How a digit is written in the grid, the reverse of how it's read: `.` for an empty cell and
+/// letters for digits above 9.
+pub(crate) fn digit_char(digit: u8) -> char {
+    match digit {
+        0 => '.',
+        1..=9 => (b'0' + digit) as char,
+        _ => (b'A' + digit - 10) as char,
+    }
+}
+
 pub fn parse_positions(data: &str) -> Result<Vec<(usize, usize)>, Error> {
     let mut positions = Vec::new();
     let re = regex::Regex::new(r"\((\d+),\s*(\d+)\)").unwrap();
@@ -80,9 +170,11 @@ pub fn get_examples_path() -> String {
 mod tests {
     use std::path::PathBuf;
 
-    use crate::{Diagonal, KillerCage, SudokuVariant, Thermometer, file_parser::parse_positions};
+    use crate::{
+        Diagonal, GridSize, KillerCage, SudokuVariant, Thermometer, file_parser::parse_positions,
+    };
 
-    use super::{get_examples_path, parse_file};
+    use super::{get_examples_path, parse_boxes, parse_file, parse_row};
 
     #[test]
     fn test_read_no_variants() {
@@ -188,6 +280,48 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_read_box_shape() {
+        let mut path = PathBuf::from(get_examples_path());
+        path.push("sudoku_6x6_tall.txt");
+        let grid = parse_file(&path).unwrap();
+        assert_eq!(grid.size(), GridSize::new(3, 2).unwrap());
+        assert_eq!(grid.get_cells()[0], vec![0, 0, 0, 3, 4, 0]);
+        assert!(grid.variants().next().is_none());
+    }
+
+    #[test]
+    fn test_read_letters() {
+        let mut path = PathBuf::from(get_examples_path());
+        path.push("sudoku_16x16.txt");
+        let grid = parse_file(&path).unwrap();
+        assert_eq!(grid.size(), GridSize::new(4, 4).unwrap());
+        assert_eq!(grid.get_cell(0, 2), 11);
+        assert_eq!(grid.get_cell(1, 6), 16);
+        assert_eq!(grid.get_cell(0, 15), 4);
+    }
+
+    #[test]
+    fn test_entropic_needs_bands() {
+        let mut path = std::env::temp_dir();
+        path.push("sudoku_solver_entropic_16x16.txt");
+        let grid = format!("{}\n", ".".repeat(16)).repeat(16);
+        std::fs::write(&path, grid + "Entropic: ((0, 0), (0, 1), (0, 2))\n").unwrap();
+        let result = parse_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_parse_row() {
+        assert_eq!(parse_row("..9A g0").ok(), None);
+        assert_eq!(parse_row("..9Ag0").unwrap(), vec![0, 0, 9, 10, 16, 0]);
+        assert_eq!(parse_row("0 12 3 G").unwrap(), vec![0, 12, 3, 16]);
+        assert_eq!(parse_boxes("Boxes: 3x2"), Some(GridSize::new(3, 2)));
+        assert_eq!(parse_boxes("boxes: 0x2"), Some(None));
+        assert_eq!(parse_boxes("(0, 1)"), None);
+    }
+
     #[test]
     fn test_parse_positions_valid_input() {
         let input = "((0, 1), (0, 2))";
@@ -1,12 +1,28 @@
+mod candidates;
+mod check;
+mod cnf;
 mod file_parser;
+mod size;
 mod solver;
 mod sudoku;
+#[cfg(test)]
+mod test_support;
 mod variant;
 
+pub use candidates::{Candidate, CandidateGrid, DigitSet};
+pub use check::{Rule, Violation, check};
+pub use cnf::{Cnf, ModelError};
 pub use file_parser::get_examples_path;
-pub use solver::Solver;
+pub use size::GridSize;
+pub use solver::{
+    Backend, Band, BranchHeuristic, Branching, CancellationToken, Conflict, Deduction,
+    DifficultyReport, FishKind, LogicalSolve, PrintObserver, Progress, SearchState, Solutions,
+    SolveEvent, SolveObserver, SolveOutcome, SolveStats, Solver, SolverConfig, Step, Technique,
+    Tier, TraceRecorder, Uniqueness, Unit, minimal_conflict, rate,
+};
 pub use sudoku::{SudokuGrid, SudokuVariant};
 pub use variant::Arrow;
+pub use variant::CellSum;
 pub use variant::Diagonal;
 pub use variant::Entropic;
 pub use variant::KillerCage;
@@ -8,15 +8,13 @@ use axum::{
     routing::{get, post},
     serve,
 };
+use std::io::{Error, ErrorKind};
 use std::sync::Arc;
 use std::{env, path::PathBuf};
-use std::{
-    io::{Error, ErrorKind},
-    time::Instant,
-};
 use sudoku_solver::{
-    Diagonal, KillerCage, KropkiDot, QuadrupleCircle, Solver, SudokuGrid, SudokuVariant,
-    Thermometer, get_examples_path,
+    Diagonal, KillerCage, KropkiDot, PrintObserver, QuadrupleCircle, SolveOutcome, Solver,
+    SolverConfig, SudokuGrid, SudokuVariant, Thermometer, Violation, check, get_examples_path,
+    minimal_conflict,
 };
 use tokio::net::TcpListener;
 use tokio::sync::RwLock;
@@ -40,13 +38,20 @@ async fn solve_handler(
     let mut grid = state.grid.write().await;
     let mut solver = Solver::new(&mut grid).map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
 
-    if solver.solve(false) {
+    if solver.solve().is_solved() {
         Ok(Json(grid.clone()))
     } else {
         Err(StatusCode::UNPROCESSABLE_ENTITY)
     }
 }
 
+async fn check_handler(
+    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+) -> Json<Vec<Violation>> {
+    let grid = state.grid.read().await;
+    Json(check(&grid))
+}
+
 async fn set_cell_handler(
     axum::extract::State(state): axum::extract::State<Arc<AppState>>,
     Path((row, col, value)): Path<(usize, usize, u8)>,
@@ -78,6 +83,7 @@ async fn set_cell_handler(
 //     let app = Router::new()
 //         .route("/sudoku", get(sudoku_handler))
 //         .route("/solve", post(solve_handler))
+//         .route("/check", get(check_handler))
 //         .route("/cell/{row}/{col}/{value}", post(set_cell_handler))
 //         .with_state(state)
 //         .layer(cors);
@@ -422,10 +428,7 @@ fn triumvirate(do_solve: bool) {
     let mut grid = SudokuGrid::read_from_file(&path).unwrap();
 
     if do_solve {
-        let start = Instant::now();
         run_solve(&mut grid, true, true);
-        let duration = start.elapsed();
-        println!("Time elapsed: {:?}", duration);
     } else {
         grid.display(true);
     }
@@ -470,19 +473,39 @@ fn pointing_pairs(do_solve: bool) {
 fn run_solve(grid: &mut SudokuGrid, show_variants: bool, debug: bool) {
     println!("Sudoku Puzzle::::");
     grid.display(show_variants);
+    let puzzle = grid.clone();
 
     let mut solver = match Solver::new(grid) {
+        Ok(s) if debug => s.with_observer(PrintObserver),
         Ok(s) => s,
         Err(e) => {
             println!("Early contradiction: {}", e);
             println!("\nNo solution found for this Sudoku puzzle");
+            explain_conflict(&puzzle);
             return;
         }
     };
-    if solver.solve(debug) {
-        println!("\n<<<<<<<<<<<<<<<<<Solved Sudoku Puzzle>>>>>>>>>>>>>>>>>>>>");
-        grid.display(false);
-    } else {
-        println!("\nNo solution found for this Sudoku puzzle");
+    let outcome = solver.solve();
+    println!("\nSolver stats: {}", solver.stats());
+    match outcome {
+        SolveOutcome::Solved => {
+            println!("\n<<<<<<<<<<<<<<<<<Solved Sudoku Puzzle>>>>>>>>>>>>>>>>>>>>");
+            grid.display(false);
+        }
+        SolveOutcome::NoSolution => {
+            println!("\nNo solution found for this Sudoku puzzle");
+            explain_conflict(&puzzle);
+        }
+        SolveOutcome::LimitReached => {
+            println!("\nGave up on this Sudoku puzzle after reaching the search limit")
+        }
+        SolveOutcome::TimedOut => println!("\nGave up on this Sudoku puzzle after timing out"),
+        SolveOutcome::Cancelled => println!("\nStopped solving this Sudoku puzzle"),
+    }
+}
+
+fn explain_conflict(puzzle: &SudokuGrid) {
+    if let Some(conflict) = minimal_conflict(puzzle, &SolverConfig::default()) {
+        println!("{conflict}");
     }
 }
@@ -0,0 +1,164 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::candidates::DigitSet;
+
+/// The shape of a grid: how many rows and columns each box spans. A grid is as wide as a box
+/// holds cells, so 3x3 boxes make the classic 9x9 grid and 2x3 boxes a 6x6 one, and its digits
+/// run from 1 to that side.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct GridSize {
+    box_rows: usize,
+    box_cols: usize,
+}
+
+impl GridSize {
+    pub const CLASSIC: GridSize = GridSize {
+        box_rows: 3,
+        box_cols: 3,
+    };
+    /// The widest grid supported, so that every digit fits in a `DigitSet`.
+    pub const MAX_SIDE: usize = 16;
+
+    /// A grid of boxes `box_rows` cells tall and `box_cols` cells wide, or `None` if that makes
+    /// the grid empty or wider than `MAX_SIDE`.
+    pub fn new(box_rows: usize, box_cols: usize) -> Option<Self> {
+        let side = box_rows.checked_mul(box_cols)?;
+        (1..=Self::MAX_SIDE)
+            .contains(&side)
+            .then_some(GridSize { box_rows, box_cols })
+    }
+
+    /// A grid `side` cells wide with the squarest boxes that fit, never taller than they are
+    /// wide: 2x2 for 4, 2x3 for 6, 2x4 for 8, 3x3 for 9, 3x4 for 12 and 4x4 for 16.
+    pub fn for_side(side: usize) -> Option<Self> {
+        let box_rows = (1..=side)
+            .take_while(|rows| rows * rows <= side)
+            .filter(|rows| side.is_multiple_of(*rows))
+            .last()?;
+        GridSize::new(box_rows, side / box_rows)
+    }
+
+    pub fn box_rows(self) -> usize {
+        self.box_rows
+    }
+
+    pub fn box_cols(self) -> usize {
+        self.box_cols
+    }
+
+    /// The number of rows and of columns, which is also the number of boxes and of digits.
+    pub fn side(self) -> usize {
+        self.box_rows * self.box_cols
+    }
+
+    /// The largest digit.
+    pub fn max_digit(self) -> u8 {
+        self.side() as u8
+    }
+
+    /// Every digit, from 1 to the side.
+    pub fn digits(self) -> DigitSet {
+        (1..=self.max_digit()).collect()
+    }
+
+    /// What each row, column and box adds up to, e.g. 45 on a 9x9 grid.
+    pub fn house_sum(self) -> usize {
+        self.side() * (self.side() + 1) / 2
+    }
+
+    /// Whether (`row`, `col`) lies on the grid.
+    pub fn contains(self, (row, col): (usize, usize)) -> bool {
+        row < self.side() && col < self.side()
+    }
+
+    /// Every cell in reading order.
+    pub fn cells(self) -> impl Iterator<Item = (usize, usize)> {
+        let side = self.side();
+        (0..side * side).map(move |i| (i / side, i % side))
+    }
+
+    /// The position of a cell in reading order, for indexing per-cell data.
+    pub fn index(self, (row, col): (usize, usize)) -> usize {
+        row * self.side() + col
+    }
+
+    /// The box holding (`row`, `col`), numbered in reading order.
+    pub fn box_index(self, row: usize, col: usize) -> usize {
+        (row / self.box_rows) * self.boxes_per_band() + col / self.box_cols
+    }
+
+    /// The cells of box `b`, in reading order.
+    pub fn box_cells(self, b: usize) -> Vec<(usize, usize)> {
+        let top = (b / self.boxes_per_band()) * self.box_rows;
+        let left = (b % self.boxes_per_band()) * self.box_cols;
+        (0..self.side())
+            .map(|i| (top + i / self.box_cols, left + i % self.box_cols))
+            .collect()
+    }
+
+    /// How many boxes sit side by side in a band of rows.
+    fn boxes_per_band(self) -> usize {
+        self.side() / self.box_cols
+    }
+}
+
+impl Default for GridSize {
+    fn default() -> Self {
+        GridSize::CLASSIC
+    }
+}
+
+/// Renders the size as e.g. "6x6 (2x3 boxes)".
+impl fmt::Display for GridSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = self.side();
+        write!(
+            f,
+            "{side}x{side} ({}x{} boxes)",
+            self.box_rows, self.box_cols
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_squarest_boxes() {
+        let shapes: Vec<(usize, usize)> = [4, 6, 8, 9, 12, 16]
+            .into_iter()
+            .map(|side| GridSize::for_side(side).unwrap())
+            .map(|size| (size.box_rows(), size.box_cols()))
+            .collect();
+
+        assert_eq!(shapes, [(2, 2), (2, 3), (2, 4), (3, 3), (3, 4), (4, 4)]);
+        assert_eq!(GridSize::for_side(9), Some(GridSize::CLASSIC));
+        assert_eq!(GridSize::for_side(17), None);
+        assert_eq!(GridSize::new(0, 3), None);
+    }
+
+    #[test]
+    fn lays_out_rectangular_boxes() {
+        let size = GridSize::new(2, 3).unwrap();
+
+        assert_eq!(size.side(), 6);
+        assert_eq!(size.box_index(1, 2), 0);
+        assert_eq!(size.box_index(1, 3), 1);
+        assert_eq!(size.box_index(2, 0), 2);
+        assert_eq!(
+            size.box_cells(3),
+            [(2, 3), (2, 4), (2, 5), (3, 3), (3, 4), (3, 5)]
+        );
+        assert!((0..6).all(|b| {
+            size.box_cells(b)
+                .iter()
+                .all(|&(r, c)| size.box_index(r, c) == b)
+        }));
+        assert_eq!(size.digits().to_vec(), [1, 2, 3, 4, 5, 6]);
+        assert_eq!(size.house_sum(), 21);
+        assert_eq!(size.to_string(), "6x6 (2x3 boxes)");
+    }
+}
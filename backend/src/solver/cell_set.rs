@@ -0,0 +1,111 @@
+use std::ops::{BitAnd, BitOr, BitOrAssign, Sub};
+
+use crate::GridSize;
+
+/// Cells per row of the bitmask, so that every supported grid fits.
+const STRIDE: usize = GridSize::MAX_SIDE;
+
+/// A set of cells, as a bitmask with bit `row * 16 + col` for (`row`, `col`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct CellSet([u64; 4]);
+
+impl CellSet {
+    pub(super) const EMPTY: CellSet = CellSet([0; 4]);
+    /// Every cell of the largest grid, to intersect sets from.
+    pub(super) const FULL: CellSet = CellSet([u64::MAX; 4]);
+
+    pub(super) fn insert(&mut self, (row, col): (usize, usize)) {
+        let i = row * STRIDE + col;
+        self.0[i / 64] |= 1 << (i % 64);
+    }
+
+    pub(super) fn contains(self, (row, col): (usize, usize)) -> bool {
+        let i = row * STRIDE + col;
+        self.0[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    pub(super) fn len(self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    pub(super) fn is_empty(self) -> bool {
+        self == CellSet::EMPTY
+    }
+
+    /// The cells in reading order.
+    pub(super) fn iter(self) -> impl Iterator<Item = (usize, usize)> {
+        self.0.into_iter().enumerate().flat_map(|(w, mut bits)| {
+            std::iter::from_fn(move || {
+                (bits != 0).then(|| {
+                    let i = w * 64 + bits.trailing_zeros() as usize;
+                    bits &= bits - 1;
+                    (i / STRIDE, i % STRIDE)
+                })
+            })
+        })
+    }
+}
+
+impl FromIterator<(usize, usize)> for CellSet {
+    fn from_iter<I: IntoIterator<Item = (usize, usize)>>(iter: I) -> Self {
+        let mut set = CellSet::EMPTY;
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<(usize, usize)> for CellSet {
+    fn extend<I: IntoIterator<Item = (usize, usize)>>(&mut self, iter: I) {
+        for cell in iter {
+            self.insert(cell);
+        }
+    }
+}
+
+impl BitOr for CellSet {
+    type Output = CellSet;
+
+    fn bitor(self, rhs: CellSet) -> CellSet {
+        CellSet(std::array::from_fn(|i| self.0[i] | rhs.0[i]))
+    }
+}
+
+impl BitOrAssign for CellSet {
+    fn bitor_assign(&mut self, rhs: CellSet) {
+        *self = *self | rhs;
+    }
+}
+
+impl BitAnd for CellSet {
+    type Output = CellSet;
+
+    fn bitand(self, rhs: CellSet) -> CellSet {
+        CellSet(std::array::from_fn(|i| self.0[i] & rhs.0[i]))
+    }
+}
+
+/// The cells of `self` that aren't in `rhs`.
+impl Sub for CellSet {
+    type Output = CellSet;
+
+    fn sub(self, rhs: CellSet) -> CellSet {
+        CellSet(std::array::from_fn(|i| self.0[i] & !rhs.0[i]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iterates_in_reading_order_across_words() {
+        let cells = [(0, 1), (3, 15), (4, 0), (15, 15)];
+        let set: CellSet = cells.into_iter().rev().collect();
+
+        assert_eq!(set.iter().collect::<Vec<_>>(), cells);
+        assert_eq!(set.len(), 4);
+        assert!(set.contains((4, 0)) && !set.contains((0, 4)));
+        assert_eq!(set - CellSet::FULL, CellSet::EMPTY);
+        assert_eq!(set & CellSet::FULL, set);
+    }
+}
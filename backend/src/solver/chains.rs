@@ -0,0 +1,249 @@
+use std::collections::{HashMap, VecDeque};
+
+use super::Solver;
+use super::step::{Step, Technique};
+use crate::candidates::{Candidate, DigitSet};
+
+/// Chains longer than this many links aren't searched for.
+const MAX_CHAIN_LINKS: usize = 15;
+
+/// The candidates of the grid with the strong and weak links between them.
+///
+/// A strong link means at least one of the two candidates is true, a weak link means at most
+/// one of them is.
+pub(super) struct LinkGraph {
+    nodes: Vec<Candidate>,
+    strong: Vec<Vec<usize>>,
+    weak: Vec<Vec<usize>>,
+}
+
+impl LinkGraph {
+    fn index(&self) -> HashMap<Candidate, usize> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .map(|(i, &n)| (n, i))
+            .collect()
+    }
+}
+
+impl Solver<'_> {
+    /// Builds the link graph from the current candidates.
+    ///
+    /// Strong links come from bivalue cells, from digits with only two places left in a row,
+    /// column or box, and from variants. Weak links join the candidates of a cell, the same
+    /// digit in cells that see each other, and candidates a variant rules out together.
+    pub(super) fn link_graph(&self) -> LinkGraph {
+        let nodes: Vec<Candidate> = self
+            .size()
+            .cells()
+            .flat_map(|(r, c)| self.candidates.get(r, c).iter().map(move |d| ((r, c), d)))
+            .collect();
+        let mut graph = LinkGraph {
+            strong: vec![Vec::new(); nodes.len()],
+            weak: vec![Vec::new(); nodes.len()],
+            nodes,
+        };
+        let index = graph.index();
+        let mut strong = Vec::new();
+        let mut weak = Vec::new();
+
+        for (i, &((r, c), d)) in graph.nodes.iter().enumerate() {
+            let cell_digits = self.candidates.get(r, c);
+            if cell_digits.len() == 2 {
+                let other = (cell_digits - DigitSet::single(d)).min().unwrap();
+                strong.push((i, index[&((r, c), other)]));
+            }
+            for other in cell_digits - DigitSet::single(d) {
+                weak.push((i, index[&((r, c), other)]));
+            }
+            for cell in self.seen_by((r, c)).iter() {
+                if self.candidates.get(cell.0, cell.1).contains(d) {
+                    weak.push((i, index[&(cell, d)]));
+                }
+            }
+        }
+        for digit in 1..=self.size().max_digit() {
+            for (a, b) in self.conjugate_pairs(digit) {
+                strong.push((index[&(a, digit)], index[&(b, digit)]));
+            }
+        }
+        for variant in &self.variants {
+            let known = |links: Vec<(Candidate, Candidate)>| {
+                links
+                    .into_iter()
+                    .filter_map(|(a, b)| Some((*index.get(&a)?, *index.get(&b)?)))
+                    .collect::<Vec<_>>()
+            };
+            strong.extend(known(variant.strong_links(&self.candidates)));
+            weak.extend(known(variant.weak_links(&self.candidates)));
+        }
+
+        for (links, adjacency) in [(strong, &mut graph.strong), (weak, &mut graph.weak)] {
+            for (a, b) in links {
+                adjacency[a].push(b);
+                adjacency[b].push(a);
+            }
+            for neighbours in adjacency.iter_mut() {
+                neighbours.sort_unstable();
+                neighbours.dedup();
+            }
+        }
+        graph
+    }
+
+    /// Finds the shortest alternating inference chain that removes a candidate.
+    ///
+    /// A chain starts and ends with a strong link and alternates strong and weak links, so one
+    /// of its two ends must be true. Any candidate weakly linked to both ends can be removed.
+    ///
+    /// https://www.sudokuwiki.org/Alternating_Inference_Chains
+    pub(super) fn aic_step(&self) -> Option<Step> {
+        let graph = self.link_graph();
+        let mut best: Option<(Vec<usize>, Vec<Candidate>)> = None;
+        for start in 0..graph.nodes.len() {
+            let limit = best
+                .as_ref()
+                .map_or(MAX_CHAIN_LINKS, |(chain, _)| chain.len() - 2);
+            if let Some(found) = shortest_eliminating_chain(&graph, start, limit) {
+                best = Some(found);
+            }
+        }
+
+        let (chain, eliminations) = best?;
+        let nodes: Vec<Candidate> = chain.iter().map(|&i| graph.nodes[i]).collect();
+        let mut cells: Vec<(usize, usize)> = nodes.iter().map(|&(cell, _)| cell).collect();
+        cells.dedup();
+        Some(Step {
+            technique: Technique::Aic,
+            unit: None,
+            digits: nodes.iter().map(|&(_, d)| d).collect(),
+            cells,
+            placements: Vec::new(),
+            eliminations,
+            chains: Vec::new(),
+        })
+    }
+}
+
+/// Breadth-first search for the shortest chain from `start` with at most `max_links` links whose
+/// ends share a weakly linked candidate. Returns the chain's nodes and the candidates it removes.
+fn shortest_eliminating_chain(
+    graph: &LinkGraph,
+    start: usize,
+    max_links: usize,
+) -> Option<(Vec<usize>, Vec<Candidate>)> {
+    // A state is a node plus whether the link that reached it was strong, packed as
+    // `node * 2 + via_strong`. Each state records the state it was reached from and its depth.
+    let mut reached: Vec<Option<(usize, usize)>> = vec![None; graph.nodes.len() * 2];
+    reached[start * 2] = Some((start * 2, 0));
+    let mut queue = VecDeque::from([start * 2]);
+
+    while let Some(state) = queue.pop_front() {
+        let (node, via_strong) = (state / 2, state % 2 == 1);
+        let links = reached[state].map_or(0, |(_, depth)| depth);
+        // Chains of a single strong link are just pointing or subset logic
+        if via_strong && links >= 3 && node != start {
+            let eliminations: Vec<Candidate> = graph.weak[start]
+                .iter()
+                .filter(|&&z| z != node && graph.weak[node].binary_search(&z).is_ok())
+                .map(|&z| graph.nodes[z])
+                .collect();
+            if !eliminations.is_empty() {
+                let mut chain = vec![node];
+                let mut current = state;
+                while current != start * 2 {
+                    current = reached[current].map_or(start * 2, |(previous, _)| previous);
+                    chain.push(current / 2);
+                }
+                chain.reverse();
+                return Some((chain, eliminations));
+            }
+        }
+        if links >= max_links {
+            continue;
+        }
+        // Links must alternate, starting with a strong one
+        let next = if via_strong {
+            &graph.weak[node]
+        } else {
+            &graph.strong[node]
+        };
+        for &n in next {
+            let next_state = n * 2 + usize::from(!via_strong);
+            if reached[next_state].is_none() {
+                reached[next_state] = Some((state, links + 1));
+                queue.push_back(next_state);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::variant::GermanWhisper;
+    use crate::{KillerCage, SudokuGrid, SudokuVariant};
+
+    fn linked(graph: &LinkGraph, links: &[Vec<usize>], a: Candidate, b: Candidate) -> bool {
+        let index = graph.index();
+        links[index[&a]].contains(&index[&b])
+    }
+
+    #[test]
+    fn x_chain_of_conjugate_pairs() {
+        let mut grid = SudokuGrid::empty();
+        let mut solver = Solver::new(&mut grid).unwrap();
+        // 5 only has two places in column 1, row 8 and column 6:
+        // (5)r2c1=(5)r8c1-(5)r8c6=(5)r2c6
+        for i in 0..9 {
+            if i != 1 && i != 7 {
+                solver.candidates.remove(i, 0, 5);
+                solver.candidates.remove(i, 5, 5);
+            }
+            if i != 0 && i != 5 {
+                solver.candidates.remove(7, i, 5);
+            }
+        }
+
+        let step = solver.aic_step().unwrap();
+
+        assert_eq!(step.technique, Technique::Aic);
+        assert_eq!(step.digits, DigitSet::single(5));
+        assert_eq!(step.cells.len(), 4);
+        let expected: Vec<Candidate> = [1, 2, 3, 4, 6, 7, 8].iter().map(|&c| ((1, c), 5)).collect();
+        assert_eq!(step.eliminations, expected);
+    }
+
+    #[test]
+    fn two_cell_cage_with_one_combination_gives_strong_links() {
+        let mut grid = SudokuGrid::empty();
+        grid.add_variant(SudokuVariant::Killer(KillerCage::new(
+            vec![(0, 0), (1, 1)],
+            17,
+        )));
+        let solver = Solver::new(&mut grid).unwrap();
+
+        let graph = solver.link_graph();
+
+        assert!(linked(&graph, &graph.strong, ((0, 0), 8), ((1, 1), 8)));
+        assert!(linked(&graph, &graph.strong, ((0, 0), 9), ((1, 1), 9)));
+    }
+
+    #[test]
+    fn german_whisper_gives_weak_links() {
+        let mut grid = SudokuGrid::empty();
+        grid.add_variant(SudokuVariant::GermanWhisper(GermanWhisper::new(
+            vec![(0, 0), (4, 4)],
+            false,
+        )));
+        let solver = Solver::new(&mut grid).unwrap();
+
+        let graph = solver.link_graph();
+
+        assert!(linked(&graph, &graph.weak, ((0, 0), 1), ((4, 4), 4)));
+        assert!(linked(&graph, &graph.weak, ((0, 0), 9), ((4, 4), 6)));
+        assert!(!linked(&graph, &graph.weak, ((0, 0), 1), ((4, 4), 6)));
+    }
+}
@@ -0,0 +1,224 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use super::heuristic::{BranchHeuristic, Heuristic};
+
+/// Limits and options for a `Solver`'s search, built up from `SolverConfig::default()`.
+///
+/// ```
+/// use std::time::Duration;
+/// use sudoku_solver::{Deduction, SolveOutcome, Solver, SolverConfig, SudokuGrid};
+///
+/// let config = SolverConfig::default()
+///     .max_nodes(50_000)
+///     .time_limit(Duration::from_secs(2))
+///     .enable(Deduction::Fish, false);
+/// let mut grid = SudokuGrid::empty();
+/// let mut solver = Solver::new(&mut grid).unwrap().with_config(config);
+/// assert_eq!(solver.solve(), SolveOutcome::Solved);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolverConfig {
+    pub(super) max_nodes: usize,
+    pub(super) time_limit: Option<Duration>,
+    pub(super) disabled: Vec<Deduction>,
+    pub(super) heuristic: Heuristic,
+    pub(super) cancellation: Option<CancellationToken>,
+    pub(super) backend: Backend,
+    pub(super) threads: usize,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        SolverConfig {
+            max_nodes: 1_000_000,
+            time_limit: None,
+            disabled: vec![Deduction::ForcingChains],
+            heuristic: Heuristic::Builtin(Branching::MostConstrained),
+            cancellation: None,
+            backend: Backend::Search,
+            threads: 1,
+        }
+    }
+}
+
+impl SolverConfig {
+    /// The number of search nodes (cells branched on) after which the search gives up.
+    pub fn max_nodes(mut self, max_nodes: usize) -> Self {
+        self.max_nodes = max_nodes;
+        self
+    }
+
+    /// Wall-clock time after which the search gives up, measured from the start of each search.
+    pub fn time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    /// Turns one of the deductions on or off. All but `ForcingChains` are on by default.
+    pub fn enable(mut self, deduction: Deduction, enabled: bool) -> Self {
+        self.disabled.retain(|&d| d != deduction);
+        if !enabled {
+            self.disabled.push(deduction);
+        }
+        self
+    }
+
+    /// Branches with one of the built-in heuristics, `MostConstrained` by default.
+    pub fn branching(mut self, branching: Branching) -> Self {
+        self.heuristic = Heuristic::Builtin(branching);
+        self
+    }
+
+    /// Branches with a heuristic of one's own. Configs are only equal when they share the same
+    /// instance, so clone the config rather than setting it again to compare them.
+    pub fn heuristic(mut self, heuristic: impl BranchHeuristic + 'static) -> Self {
+        self.heuristic = Heuristic::Custom(Arc::new(heuristic));
+        self
+    }
+
+    /// Stops the search once `token` is cancelled, e.g. from another thread.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Which search `solve`, `count_solutions` and `uniqueness` use.
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Splits the search over this many threads when above 1, e.g.
+    /// `std::thread::available_parallelism()`. The first few branching cells are expanded into
+    /// separate pieces of the search tree, which the threads take in turn. The node limit is
+    /// shared between them, and they all stop once a solution (for `solve`) or enough solutions
+    /// (for `count_solutions` and `uniqueness`) are found.
+    ///
+    /// The progress callback and observer aren't called from the threads, and `solutions`
+    /// always searches on the calling thread.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    pub(super) fn is_enabled(&self, deduction: Deduction) -> bool {
+        !self.disabled.contains(&deduction)
+    }
+
+    pub(super) fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+}
+
+/// A flag shared between a running search and whoever may want to stop it. Clones share the
+/// same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks every search using this token to stop at its next node.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Tokens are equal when they share the same flag.
+impl PartialEq for CancellationToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// A snapshot of a running search, passed to the callback set with `Solver::on_progress`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// Search nodes visited so far.
+    pub nodes: usize,
+    /// Number of cells currently branched on.
+    pub depth: usize,
+    /// Number of filled cells in the grid, givens included.
+    pub cells_filled: usize,
+}
+
+/// The deductions the solver uses on the candidates. The search runs all but `Wings`,
+/// `SimpleColoring` and `Chains` after every placement, while `Solver::next_step` and
+/// `Solver::solve_logically` use every one that is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Deduction {
+    NakedSubsets,
+    PointingPairs,
+    HiddenPairs,
+    Fish,
+    /// XY-Wings, XYZ-Wings and W-Wings.
+    Wings,
+    SimpleColoring,
+    /// Alternating inference chains, including the links variants provide.
+    Chains,
+    /// Assumes each candidate in turn and removes those leading to a contradiction, and keeps
+    /// only the digit a bivalue cell forces either way, before the next guess. Off by default,
+    /// as it costs a propagation per candidate at every node.
+    ForcingChains,
+}
+
+/// The built-in ways for the search to pick the next cell to branch on and order its digits.
+/// Unless stated otherwise, digits are tried in ascending order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Branching {
+    /// The empty cell with the fewest candidates, the first one in reading order on a tie.
+    MostConstrained,
+    /// The first empty cell in reading order.
+    FirstEmpty,
+    /// The empty cell with the fewest candidates, preferring the one constrained by the most
+    /// variants on a tie, then the first in reading order.
+    MostConstrainedDegree,
+    /// The cell `MostConstrained` picks, trying first the digits that rule out the fewest
+    /// candidates from the cells it sees.
+    LeastConstrainingValue,
+}
+
+/// The search behind `Solver::solve`, `Solver::count_solutions` and `Solver::uniqueness`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Backend {
+    /// Depth-first search over the candidates, running the deductions at every node.
+    Search,
+    /// Dancing Links over an exact-cover encoding of the puzzle, which is much faster for
+    /// classic puzzles. Only `Diagonal`, `King`, `Knight`, `Shaded` and `QuadrupleCircle` can
+    /// be encoded; puzzles with other variants use `Search` instead.
+    ///
+    /// The deductions and the branching heuristic don't apply, and `solutions` always uses
+    /// `Search`.
+    DancingLinks,
+}
+
+/// How a call to `Solver::solve` ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SolveOutcome {
+    /// A solution was found and left in the grid.
+    Solved,
+    /// The whole search space was explored without finding a solution.
+    NoSolution,
+    /// The search gave up after visiting the configured number of nodes.
+    LimitReached,
+    /// The search gave up after running out of time.
+    TimedOut,
+    /// The search was stopped through its `CancellationToken`.
+    Cancelled,
+}
+
+impl SolveOutcome {
+    pub fn is_solved(&self) -> bool {
+        *self == SolveOutcome::Solved
+    }
+}
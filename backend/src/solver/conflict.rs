@@ -0,0 +1,215 @@
+use std::fmt;
+
+use super::step::cell_name;
+use super::{SolveOutcome, Solver, SolverConfig, classic_peers};
+use crate::{Candidate, GridSize, SudokuGrid, SudokuVariant};
+
+/// A set of clues that can't all hold at once, as found by `minimal_conflict`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    /// The givens involved, as (cell, digit).
+    pub givens: Vec<Candidate>,
+    pub variants: Vec<SudokuVariant>,
+    /// Whether dropping any one of the clues is known to leave a solvable puzzle. This is false
+    /// when a check hit the solver's limits and the clue was kept without proof it's needed.
+    pub minimal: bool,
+}
+
+impl Conflict {
+    pub fn len(&self) -> usize {
+        self.givens.len() + self.variants.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} clues conflict", self.len())?;
+        if !self.minimal {
+            write!(f, " (possibly not a minimal set)")?;
+        }
+        for &(cell, digit) in &self.givens {
+            write!(f, "\n  Given {digit} in {}", cell_name(cell))?;
+        }
+        for variant in &self.variants {
+            write!(f, "\n  {variant}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Feasibility {
+    Solvable,
+    Unsolvable,
+    /// The solver hit its limits before deciding.
+    Unknown,
+}
+
+/// Explains why a puzzle has no solution by finding a minimal subset of its givens and variants
+/// that is still unsolvable: remove any one of them and the rest can be satisfied.
+///
+/// Clues are dropped one at a time and kept only when the puzzle becomes solvable without them,
+/// so this solves the puzzle once per clue. Each of those solves runs under `config`. Returns
+/// `None` when the puzzle has a solution, or when its own solve gives up.
+pub fn minimal_conflict(grid: &SudokuGrid, config: &SolverConfig) -> Option<Conflict> {
+    let mut givens: Vec<Candidate> = grid
+        .size()
+        .cells()
+        .map(|(row, col)| ((row, col), grid.get_cell(row, col)))
+        .filter(|&(_, digit)| digit != 0)
+        .collect();
+    let mut variants: Vec<SudokuVariant> = grid.variants().cloned().collect();
+    if feasibility(grid.size(), &givens, &variants, config) != Feasibility::Unsolvable {
+        return None;
+    }
+
+    let mut minimal = true;
+    let mut i = 0;
+    while i < variants.len() {
+        let variant = variants.remove(i);
+        match feasibility(grid.size(), &givens, &variants, config) {
+            Feasibility::Unsolvable => {}
+            kept => {
+                minimal &= kept == Feasibility::Solvable;
+                variants.insert(i, variant);
+                i += 1;
+            }
+        }
+    }
+    let mut i = 0;
+    while i < givens.len() {
+        let given = givens.remove(i);
+        match feasibility(grid.size(), &givens, &variants, config) {
+            Feasibility::Unsolvable => {}
+            kept => {
+                minimal &= kept == Feasibility::Solvable;
+                givens.insert(i, given);
+                i += 1;
+            }
+        }
+    }
+    Some(Conflict {
+        givens,
+        variants,
+        minimal,
+    })
+}
+
+fn feasibility(
+    size: GridSize,
+    givens: &[Candidate],
+    variants: &[SudokuVariant],
+    config: &SolverConfig,
+) -> Feasibility {
+    let mut grid = SudokuGrid::with_size(size);
+    for &((row, col), digit) in givens {
+        grid.set_cell(row, col, digit);
+    }
+    for variant in variants {
+        grid.add_variant(variant.clone());
+    }
+    // Givens repeating a digit are only caught once the grid is full, after trying every way of
+    // filling in the rest
+    let repeated = givens.iter().any(|&((row, col), digit)| {
+        classic_peers(size, row, col)
+            .into_iter()
+            .chain(variants.iter().flat_map(|v| v.seen_cells(size, row, col)))
+            .any(|(r, c)| (r, c) != (row, col) && grid.get_cell(r, c) == digit)
+    });
+    if repeated {
+        return Feasibility::Unsolvable;
+    }
+    let Ok(solver) = Solver::new(&mut grid) else {
+        return Feasibility::Unsolvable;
+    };
+    match solver.with_config(config.clone()).solve() {
+        SolveOutcome::Solved => Feasibility::Solvable,
+        SolveOutcome::NoSolution => Feasibility::Unsolvable,
+        SolveOutcome::LimitReached | SolveOutcome::TimedOut | SolveOutcome::Cancelled => {
+            Feasibility::Unknown
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::example;
+    use crate::{KillerCage, Thermometer};
+
+    fn conflict(grid: &SudokuGrid) -> Option<Conflict> {
+        minimal_conflict(grid, &SolverConfig::default())
+    }
+
+    #[test]
+    fn solvable_puzzle_has_no_conflict() {
+        assert_eq!(conflict(&example("sudoku.txt")), None);
+    }
+
+    #[test]
+    fn clashing_givens_are_found_among_the_rest() {
+        let mut grid = example("sudoku.txt");
+        // Clashes with the 4s at (0, 8) and (4, 0), and either pair on its own is minimal
+        grid.set_cell(0, 0, 4);
+
+        let conflict = conflict(&grid).unwrap();
+
+        assert_eq!(conflict.givens, vec![((0, 0), 4), ((4, 0), 4)]);
+        assert!(conflict.variants.is_empty());
+        assert!(conflict.minimal);
+    }
+
+    #[test]
+    fn variant_against_a_given() {
+        let mut grid = SudokuGrid::empty();
+        grid.set_cell(0, 0, 9);
+        grid.set_cell(5, 5, 1);
+        let cage = SudokuVariant::Killer(KillerCage::new(vec![(0, 0), (0, 1)], 10));
+        let therm = SudokuVariant::Thermometer(Thermometer::new(vec![(0, 0), (1, 0), (2, 0)]));
+        grid.add_variant(cage.clone());
+        grid.add_variant(therm.clone());
+
+        let conflict = conflict(&grid).unwrap();
+
+        // The cage needs a 1 beside the 9, which is fine; the 9 can't start a thermometer
+        assert_eq!(conflict.givens, vec![((0, 0), 9)]);
+        assert_eq!(conflict.variants, vec![therm]);
+    }
+
+    #[test]
+    fn conflicting_variants_without_givens() {
+        let mut grid = SudokuGrid::empty();
+        let cells = vec![(4, 4), (4, 5)];
+        let low = SudokuVariant::Killer(KillerCage::new(cells.clone(), 3));
+        let high = SudokuVariant::Killer(KillerCage::new(cells, 17));
+        let unrelated = SudokuVariant::Killer(KillerCage::new(vec![(0, 0), (0, 1)], 5));
+        grid.add_variant(unrelated);
+        grid.add_variant(low.clone());
+        grid.add_variant(high.clone());
+
+        let conflict = conflict(&grid).unwrap();
+
+        assert!(conflict.givens.is_empty());
+        assert_eq!(conflict.variants, vec![low, high]);
+        assert!(conflict.minimal);
+    }
+
+    #[test]
+    fn report_lists_each_clue() {
+        let mut grid = SudokuGrid::empty();
+        grid.set_cell(0, 0, 9);
+        let therm = Thermometer::new(vec![(0, 0), (0, 1)]);
+        grid.add_variant(SudokuVariant::Thermometer(therm.clone()));
+
+        let report = conflict(&grid).unwrap().to_string();
+
+        assert_eq!(
+            report,
+            format!("2 clues conflict\n  Given 9 in r1c1\n  {therm}")
+        );
+    }
+}
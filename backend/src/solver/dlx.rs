@@ -0,0 +1,485 @@
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+use std::time::Instant;
+
+use super::{Progress, SolveEvent, SolveOutcome, Solver};
+use crate::candidates::{Candidate, CandidateGrid, DigitSet};
+use crate::{SudokuGrid, SudokuVariant};
+
+/// A rule a placement satisfies, i.e. a column of the exact-cover matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Column {
+    /// An empty cell (indexed by `GridSize::index`) holds exactly one digit.
+    Cell(usize),
+    /// A complete unit (indexed into `Solver::units`) holds each missing digit exactly once.
+    UnitDigit(usize, u8),
+    /// Two cells that see each other without sharing a complete unit, like a king's move apart,
+    /// hold the digit at most once between them. Only this rule may be left uncovered.
+    PairDigit(usize, usize, u8),
+}
+
+/// What the search reports to its visitor.
+enum Visit<'a> {
+    /// A placement was tried, with `depth` placements made including this one.
+    Choice { choice: Candidate, depth: usize },
+    /// The placement was taken back.
+    Undo(Candidate),
+    /// Every primary column is covered by these placements.
+    Solution(&'a [Candidate]),
+}
+
+const ROOT: usize = 0;
+
+/// Knuth's Algorithm X over a sparse matrix of circular doubly linked nodes ("Dancing Links").
+/// Node 0 is the root, followed by one header per column, followed by the rows' nodes.
+///
+/// https://arxiv.org/abs/cs/0011047
+struct DancingLinks {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    // The header of each node's column
+    column: Vec<usize>,
+    // The number of nodes in each column, indexed by header
+    size: Vec<usize>,
+    // The placement each node's row stands for, indexed by node
+    choice: Vec<Candidate>,
+}
+
+impl DancingLinks {
+    /// Creates a matrix with `primary` columns that must be covered exactly once, followed by
+    /// `secondary` columns that may be covered at most once.
+    fn new(primary: usize, secondary: usize) -> Self {
+        let headers = 1 + primary + secondary;
+        let mut matrix = DancingLinks {
+            left: (0..headers).collect(),
+            right: (0..headers).collect(),
+            up: (0..headers).collect(),
+            down: (0..headers).collect(),
+            column: (0..headers).collect(),
+            size: vec![0; headers],
+            choice: vec![((0, 0), 0); headers],
+        };
+        // Only the primary headers are linked to the root, so only they are ever chosen
+        for header in 1..=primary {
+            matrix.left[header] = header - 1;
+            matrix.right[header - 1] = header;
+        }
+        matrix.right[primary] = ROOT;
+        matrix.left[ROOT] = primary;
+        matrix
+    }
+
+    /// Adds a row for `choice`, with a node in each of `columns` (0-based, primary first).
+    fn add_row(&mut self, choice: Candidate, columns: &[usize]) {
+        let first = self.column.len();
+        for &column in columns {
+            let header = column + 1;
+            let node = self.column.len();
+            self.column.push(header);
+            self.choice.push(choice);
+            self.up.push(self.up[header]);
+            self.down.push(header);
+            let above = self.up[header];
+            self.down[above] = node;
+            self.up[header] = node;
+            self.size[header] += 1;
+            if node == first {
+                self.left.push(node);
+                self.right.push(node);
+            } else {
+                self.left.push(self.left[first]);
+                self.right.push(first);
+                let last = self.left[first];
+                self.right[last] = node;
+                self.left[first] = node;
+            }
+        }
+    }
+
+    fn cover(&mut self, header: usize) {
+        let (l, r) = (self.left[header], self.right[header]);
+        self.right[l] = r;
+        self.left[r] = l;
+        let mut i = self.down[header];
+        while i != header {
+            let mut j = self.right[i];
+            while j != i {
+                let (u, d) = (self.up[j], self.down[j]);
+                self.down[u] = d;
+                self.up[d] = u;
+                self.size[self.column[j]] -= 1;
+                j = self.right[j];
+            }
+            i = self.down[i];
+        }
+    }
+
+    fn uncover(&mut self, header: usize) {
+        let mut i = self.up[header];
+        while i != header {
+            let mut j = self.left[i];
+            while j != i {
+                self.size[self.column[j]] += 1;
+                let (u, d) = (self.up[j], self.down[j]);
+                self.down[u] = j;
+                self.up[d] = j;
+                j = self.left[j];
+            }
+            i = self.up[i];
+        }
+        let (l, r) = (self.left[header], self.right[header]);
+        self.right[l] = header;
+        self.left[r] = header;
+    }
+
+    /// Runs Algorithm X, always branching on the primary column with the fewest rows. Stops
+    /// as soon as `visit` breaks, leaving the matrix half covered.
+    fn search(
+        &mut self,
+        partial: &mut Vec<Candidate>,
+        visit: &mut impl FnMut(Visit) -> ControlFlow<SolveOutcome>,
+    ) -> ControlFlow<SolveOutcome> {
+        if self.right[ROOT] == ROOT {
+            return visit(Visit::Solution(partial));
+        }
+        let mut header = self.right[ROOT];
+        let mut i = self.right[header];
+        while i != ROOT {
+            if self.size[i] < self.size[header] {
+                header = i;
+            }
+            i = self.right[i];
+        }
+        if self.size[header] == 0 {
+            return ControlFlow::Continue(());
+        }
+
+        self.cover(header);
+        let mut row = self.down[header];
+        while row != header {
+            let choice = self.choice[row];
+            partial.push(choice);
+            visit(Visit::Choice {
+                choice,
+                depth: partial.len(),
+            })?;
+            let mut j = self.right[row];
+            while j != row {
+                self.cover(self.column[j]);
+                j = self.right[j];
+            }
+            self.search(partial, visit)?;
+            let mut j = self.left[row];
+            while j != row {
+                self.uncover(self.column[j]);
+                j = self.left[j];
+            }
+            partial.pop();
+            visit(Visit::Undo(choice))?;
+            row = self.down[row];
+        }
+        self.uncover(header);
+        ControlFlow::Continue(())
+    }
+}
+
+impl Solver<'_> {
+    /// Finds up to `limit` solutions with Dancing Links, leaving the grid as it was. Returns
+    /// `None` if a variant can't be encoded as an exact cover.
+    ///
+    /// Rules that don't come down to units and seen cells, like the digits a quadruple circle
+    /// needs, are only encoded through the candidates, so every cover is checked against the
+    /// variants before being counted as a solution. The outcome is `Solved` once `limit`
+    /// solutions are found, `NoSolution` if the search ran out before that, or else why it gave
+    /// up.
+    pub(super) fn exact_cover_solutions(
+        &mut self,
+        limit: usize,
+    ) -> Option<(Vec<SudokuGrid>, SolveOutcome)> {
+        let mut matrix = self.exact_cover_matrix()?;
+        let deadline = self.config.time_limit.map(|limit| Instant::now() + limit);
+        let givens = self
+            .size()
+            .cells()
+            .filter(|&(row, col)| self.sudoku_grid.get_cell(row, col) != 0)
+            .count();
+        let mut solutions = Vec::new();
+
+        let mut visit = |visit: Visit| {
+            match visit {
+                Visit::Choice {
+                    choice: (cell, digit),
+                    depth,
+                } => {
+                    self.stats.nodes += 1;
+                    self.stats.max_depth = self.stats.max_depth.max(depth);
+                    if self.stats.nodes > self.config.max_nodes {
+                        return ControlFlow::Break(SolveOutcome::LimitReached);
+                    }
+                    if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                        return ControlFlow::Break(SolveOutcome::TimedOut);
+                    }
+                    if self.config.is_cancelled() {
+                        return ControlFlow::Break(SolveOutcome::Cancelled);
+                    }
+                    if let Some(progress) = &mut self.progress
+                        && self.stats.nodes.is_multiple_of(progress.interval)
+                    {
+                        (progress.callback)(Progress {
+                            nodes: self.stats.nodes,
+                            depth,
+                            cells_filled: givens + depth,
+                        });
+                    }
+                    self.emit(|| SolveEvent::Guess { cell, digit, depth });
+                }
+                Visit::Undo((cell, digit)) => {
+                    self.stats.backtracks += 1;
+                    self.emit(|| SolveEvent::Backtrack { cell, digit });
+                }
+                Visit::Solution(choices) => {
+                    for &((row, col), digit) in choices {
+                        self.sudoku_grid.set_cell(row, col, digit);
+                    }
+                    if self.validate_solution() {
+                        solutions.push(self.sudoku_grid.clone());
+                    }
+                    for &((row, col), _) in choices {
+                        self.sudoku_grid.set_cell(row, col, 0);
+                    }
+                    if solutions.len() >= limit {
+                        return ControlFlow::Break(SolveOutcome::Solved);
+                    }
+                }
+            }
+            ControlFlow::Continue(())
+        };
+        let outcome = match matrix.search(&mut Vec::new(), &mut visit) {
+            ControlFlow::Break(outcome) => outcome,
+            ControlFlow::Continue(()) => SolveOutcome::NoSolution,
+        };
+        Some((solutions, outcome))
+    }
+
+    /// Solves the puzzle with Dancing Links, leaving the solution in the grid. Returns `None`
+    /// if a variant can't be encoded as an exact cover.
+    pub(super) fn solve_exact_cover(&mut self) -> Option<SolveOutcome> {
+        let (solutions, outcome) = self.exact_cover_solutions(1)?;
+        if let Some(solution) = solutions.first() {
+            for (row, col) in self.size().cells() {
+                self.sudoku_grid
+                    .set_cell(row, col, solution.get_cell(row, col));
+            }
+            self.candidates = CandidateGrid::with_size(self.size());
+        }
+        Some(outcome)
+    }
+
+    /// Encodes the empty cells' candidates as an exact-cover matrix: one row per candidate,
+    /// covering its cell, the digit in each complete unit holding the cell, and the digit in
+    /// each pair of cells it sees outside those units.
+    fn exact_cover_matrix(&self) -> Option<DancingLinks> {
+        let encodable = self.variants.iter().all(|variant| {
+            matches!(
+                variant,
+                SudokuVariant::Diagonal(_)
+                    | SudokuVariant::King(_)
+                    | SudokuVariant::Knight(_)
+                    | SudokuVariant::Shaded(_)
+                    | SudokuVariant::QuadrupleCircles(_)
+            )
+        });
+        if !encodable {
+            return None;
+        }
+
+        let size = self.size();
+        let value = |(row, col): (usize, usize)| self.sudoku_grid.get_cell(row, col);
+        let mut cell_units = vec![Vec::new(); size.side() * size.side()];
+        let mut placed = vec![DigitSet::EMPTY; self.units.len()];
+        for (u, unit) in self.units.iter().enumerate() {
+            if !unit.is_complete() {
+                continue;
+            }
+            for cell in unit.cells(size) {
+                cell_units[size.index(cell)].push(u);
+                if value(cell) != 0 {
+                    placed[u].insert(value(cell));
+                }
+            }
+        }
+
+        // Every missing digit of a complete unit must be covered, even if no candidate can
+        let mut columns: Vec<Column> = Vec::new();
+        for (u, unit) in self.units.iter().enumerate() {
+            if unit.is_complete() {
+                let missing = size.digits() - placed[u];
+                columns.extend(missing.iter().map(|d| Column::UnitDigit(u, d)));
+            }
+        }
+        let mut rows: Vec<(Candidate, Vec<Column>)> = Vec::new();
+        for cell in size.cells().filter(|&cell| value(cell) == 0) {
+            let i = size.index(cell);
+            columns.push(Column::Cell(i));
+            for digit in self.candidates.get(cell.0, cell.1) {
+                if cell_units[i].iter().any(|&u| placed[u].contains(digit)) {
+                    continue;
+                }
+                let mut covers = vec![Column::Cell(i)];
+                covers.extend(cell_units[i].iter().map(|&u| Column::UnitDigit(u, digit)));
+                for other in self.seen_by(cell).iter().filter(|&other| other != cell) {
+                    let j = size.index(other);
+                    let shares_unit = cell_units[i].iter().any(|u| cell_units[j].contains(u));
+                    if value(other) == 0
+                        && !shares_unit
+                        && self.candidates.get(other.0, other.1).contains(digit)
+                    {
+                        covers.push(Column::PairDigit(i.min(j), i.max(j), digit));
+                    }
+                }
+                rows.push(((cell, digit), covers));
+            }
+        }
+
+        // Primary columns first, then the secondary ones in order of first use
+        let primary = columns.len();
+        let mut index: HashMap<Column, usize> = columns
+            .iter()
+            .enumerate()
+            .map(|(i, &column)| (column, i))
+            .collect();
+        for (_, covers) in &rows {
+            for &column in covers {
+                let next = index.len();
+                index.entry(column).or_insert(next);
+            }
+        }
+        let mut matrix = DancingLinks::new(primary, index.len() - primary);
+        for (choice, covers) in rows {
+            let covers: Vec<usize> = covers.iter().map(|column| index[column]).collect();
+            matrix.add_row(choice, &covers);
+        }
+        Some(matrix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{contradictory_grid, example};
+    use crate::variant::{King, Knight, QuadrupleCircle};
+    use crate::{Backend, KillerCage, SolverConfig};
+
+    fn config(backend: Backend) -> SolverConfig {
+        SolverConfig::default().backend(backend)
+    }
+
+    /// Solves a copy of `grid` with `backend`.
+    fn solve(grid: &SudokuGrid, backend: Backend) -> (SolveOutcome, SudokuGrid) {
+        let mut grid = grid.clone();
+        let outcome = Solver::new(&mut grid)
+            .unwrap()
+            .with_config(config(backend))
+            .solve();
+        (outcome, grid)
+    }
+
+    fn count(grid: &SudokuGrid, backend: Backend, limit: usize) -> usize {
+        let mut grid = grid.clone();
+        let (count, _) = Solver::new(&mut grid)
+            .unwrap()
+            .with_config(config(backend))
+            .count_solutions(limit);
+        count
+    }
+
+    #[test]
+    fn matches_search_on_classic_example() {
+        let grid = example("sudoku.txt");
+
+        let (outcome, solution) = solve(&grid, Backend::DancingLinks);
+
+        assert_eq!(outcome, SolveOutcome::Solved);
+        assert_eq!(
+            solution.get_cells(),
+            solve(&grid, Backend::Search).1.get_cells()
+        );
+        assert_eq!(count(&grid, Backend::DancingLinks, 2), 1);
+    }
+
+    #[test]
+    fn counts_like_search_with_chess_constraints() {
+        let mut grid = SudokuGrid::empty();
+        grid.add_variant(SudokuVariant::King(King::new()));
+        grid.add_variant(SudokuVariant::Knight(Knight::new()));
+
+        let (outcome, mut solution) = solve(&grid, Backend::DancingLinks);
+
+        assert_eq!(outcome, SolveOutcome::Solved);
+        assert!(solution.variants().all(|v| v.validate_solution(&solution)));
+        for row in 3..9 {
+            for col in 0..9 {
+                solution.set_cell(row, col, 0);
+            }
+        }
+        assert_eq!(
+            count(&solution, Backend::DancingLinks, 10),
+            count(&solution, Backend::Search, 10)
+        );
+    }
+
+    #[test]
+    fn checks_rules_it_does_not_encode() {
+        let mut grid = SudokuGrid::empty();
+        grid.add_variant(SudokuVariant::QuadrupleCircles(QuadrupleCircle::new(
+            vec![(0, 0), (0, 1), (1, 0), (1, 1)],
+            vec![7, 8],
+            false,
+        )));
+
+        let (outcome, solution) = solve(&grid, Backend::DancingLinks);
+
+        assert_eq!(outcome, SolveOutcome::Solved);
+        assert!(solution.variants().all(|v| v.validate_solution(&solution)));
+    }
+
+    #[test]
+    fn reports_no_solution() {
+        let grid = contradictory_grid();
+
+        assert_eq!(
+            solve(&grid, Backend::DancingLinks).0,
+            SolveOutcome::NoSolution
+        );
+        assert_eq!(count(&grid, Backend::DancingLinks, 2), 0);
+    }
+
+    #[test]
+    fn stops_at_node_limit() {
+        let mut grid = SudokuGrid::empty();
+        let mut solver = Solver::new(&mut grid)
+            .unwrap()
+            .with_config(config(Backend::DancingLinks).max_nodes(5));
+
+        assert_eq!(solver.solve(), SolveOutcome::LimitReached);
+        assert_eq!(solver.stats().nodes, 6);
+    }
+
+    #[test]
+    fn falls_back_for_other_variants() {
+        let mut grid = SudokuGrid::empty();
+        grid.add_variant(SudokuVariant::Killer(KillerCage::new(
+            vec![(0, 0), (0, 1)],
+            3,
+        )));
+        let solver = Solver::new(&mut grid).unwrap();
+        assert!(solver.exact_cover_matrix().is_none());
+
+        let (outcome, solution) = solve(&grid, Backend::DancingLinks);
+
+        assert_eq!(outcome, SolveOutcome::Solved);
+        assert_eq!(solution.get_cell(0, 0) + solution.get_cell(0, 1), 3);
+    }
+}
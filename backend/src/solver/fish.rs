@@ -0,0 +1,336 @@
+use super::Solver;
+use super::step::{FishKind, Step, Technique};
+use crate::GridSize;
+use crate::candidates::DigitSet;
+
+impl Solver<'_> {
+    /// Applies X-Wings, Swordfish and Jellyfish (including finned and sashimi ones) over rows
+    /// and columns.
+    ///
+    /// https://www.sudokuwiki.org/X_Wing_Strategy
+    pub fn apply_fish(&mut self) {
+        for size in 2..=4 {
+            for step in self.fish_steps(size) {
+                self.apply_step(&step);
+            }
+        }
+    }
+
+    /// Finds the fish with `size` base lines that remove at least one candidate, for each digit,
+    /// first with rows as the base lines and then with columns.
+    ///
+    /// If the base candidates of a digit fit in `size` cover lines, the digit can be removed
+    /// from the rest of the cover lines. Base candidates outside the cover lines ("fins") are
+    /// allowed if they share a box, in which case only cover cells in that box lose the digit.
+    pub(super) fn fish_steps(&self, size: usize) -> Vec<Step> {
+        // row_masks[d - 1][row] has bit `col` set if that cell still has `d`, and vice versa
+        let grid_size = self.size();
+        let mut row_masks = [[0u16; GridSize::MAX_SIDE]; GridSize::MAX_SIDE];
+        let mut col_masks = [[0u16; GridSize::MAX_SIDE]; GridSize::MAX_SIDE];
+        for (row, col) in grid_size.cells() {
+            for digit in self.candidates.get(row, col) {
+                row_masks[digit as usize - 1][row] |= 1 << col;
+                col_masks[digit as usize - 1][col] |= 1 << row;
+            }
+        }
+
+        let mut steps = Vec::new();
+        for digit in 1..=grid_size.max_digit() {
+            for rows_as_base in [true, false] {
+                let lines = LineView::new(
+                    grid_size,
+                    rows_as_base,
+                    if rows_as_base {
+                        row_masks[digit as usize - 1]
+                    } else {
+                        col_masks[digit as usize - 1]
+                    },
+                );
+                let available = (0..grid_size.side())
+                    .filter(|&line| lines.masks[line] != 0)
+                    .fold(0u16, |acc, line| acc | (1 << line));
+
+                for base in subsets_of(available, size) {
+                    let union = bits(base).fold(0, |acc, line| acc | lines.masks[line]);
+                    // Too narrow is a contradiction for the search to find
+                    if (union.count_ones() as usize) < size {
+                        continue;
+                    }
+                    for cover in cover_sets(union, size, &lines.position_segments) {
+                        if bits(base).any(|line| lines.masks[line] & cover == 0) {
+                            continue;
+                        }
+                        if let Some(step) = self.fish_step(digit, base, cover, &lines) {
+                            steps.push(step);
+                        }
+                    }
+                }
+            }
+        }
+        steps
+    }
+
+    fn fish_step(&self, digit: u8, base: u16, cover: u16, lines: &LineView) -> Option<Step> {
+        // Fins must share a box: their lines within one band, their positions within one stack
+        let fin_lines = bits(base)
+            .filter(|&line| lines.masks[line] & !cover != 0)
+            .fold(0u16, |acc, line| acc | (1 << line));
+        let fin_positions = bits(base).fold(0, |acc, line| acc | (lines.masks[line] & !cover));
+        let fin_box = if fin_lines == 0 {
+            None
+        } else {
+            let band = lines
+                .line_segments
+                .iter()
+                .copied()
+                .find(|&s| fin_lines & !s == 0)?;
+            let stack = lines
+                .position_segments
+                .iter()
+                .copied()
+                .find(|&s| fin_positions & !s == 0)?;
+            Some((band, stack))
+        };
+
+        // Cover cells outside the base lines (and, with fins, inside the fin box) lose the digit
+        let (target_lines, target_positions) = match fin_box {
+            None => (lines.all_lines & !base, cover),
+            Some((band, stack)) => (band & !base, cover & stack),
+        };
+        let eliminations: Vec<((usize, usize), u8)> = bits(target_positions)
+            .flat_map(|pos| bits(target_lines).map(move |line| lines.cell(line, pos)))
+            .filter(|&(r, c)| self.candidates.get(r, c).contains(digit))
+            .map(|cell| (cell, digit))
+            .collect();
+        if eliminations.is_empty() {
+            return None;
+        }
+
+        let kind = match fin_box {
+            None => FishKind::Basic,
+            Some(_) if bits(base).any(|line| (lines.masks[line] & cover).count_ones() < 2) => {
+                FishKind::Sashimi
+            }
+            Some(_) => FishKind::Finned,
+        };
+        let cells = bits(base)
+            .flat_map(|line| bits(lines.masks[line]).map(move |pos| lines.cell(line, pos)))
+            .collect();
+        Some(Step {
+            technique: Technique::Fish {
+                size: base.count_ones() as usize,
+                kind,
+            },
+            unit: None,
+            digits: DigitSet::single(digit),
+            cells,
+            placements: Vec::new(),
+            eliminations,
+            chains: Vec::new(),
+        })
+    }
+}
+
+/// One digit's candidates seen as parallel lines (rows or columns).
+struct LineView {
+    rows_as_base: bool,
+    masks: [u16; GridSize::MAX_SIDE],
+    /// Every line, as a bitmask.
+    all_lines: u16,
+    /// The lines that pass through the same boxes, e.g. the rows of a band.
+    line_segments: Vec<u16>,
+    /// The positions along a line that fall in the same box.
+    position_segments: Vec<u16>,
+}
+
+impl LineView {
+    fn new(size: GridSize, rows_as_base: bool, masks: [u16; GridSize::MAX_SIDE]) -> Self {
+        // A box spans `box_rows` rows and `box_cols` positions along each of them
+        let (line_len, position_len) = if rows_as_base {
+            (size.box_rows(), size.box_cols())
+        } else {
+            (size.box_cols(), size.box_rows())
+        };
+        LineView {
+            rows_as_base,
+            masks,
+            all_lines: ((1u32 << size.side()) - 1) as u16,
+            line_segments: segments(size.side(), line_len),
+            position_segments: segments(size.side(), position_len),
+        }
+    }
+
+    /// Position `pos` along line `line`, as a (row, col) cell.
+    fn cell(&self, line: usize, pos: usize) -> (usize, usize) {
+        if self.rows_as_base {
+            (line, pos)
+        } else {
+            (pos, line)
+        }
+    }
+}
+
+/// The cover sets of `size` positions worth trying for base candidates at positions `union`.
+///
+/// Any position left out of the cover holds a fin, and fins must share a box, so apart from
+/// the exact cover only the positions of a single box segment may be left out.
+fn cover_sets(union: u16, size: usize, box_segments: &[u16]) -> Vec<u16> {
+    if union.count_ones() as usize == size {
+        return vec![union];
+    }
+    let mut covers = Vec::new();
+    for &segment in box_segments {
+        let outside = union & !segment;
+        let inside = union & segment;
+        let needed = size.checked_sub(outside.count_ones() as usize);
+        match needed {
+            Some(needed) if needed < inside.count_ones() as usize => {
+                covers.extend(subsets_of(inside, needed).map(|extra| outside | extra));
+            }
+            _ => {}
+        }
+    }
+    covers
+}
+
+/// Every subset of `mask` with exactly `size` bits set.
+fn subsets_of(mask: u16, size: usize) -> impl Iterator<Item = u16> {
+    // Walks down through the submasks of `mask`, ending with the empty set
+    let mut next = Some(mask);
+    std::iter::from_fn(move || {
+        let subset = next?;
+        next = (subset != 0).then(|| (subset - 1) & mask);
+        Some(subset)
+    })
+    .filter(move |subset| subset.count_ones() as usize == size)
+}
+
+/// The indices of the bits set in `mask`, in ascending order.
+fn bits(mask: u16) -> impl Iterator<Item = usize> {
+    (0..16).filter(move |&i| mask & (1 << i) != 0)
+}
+
+/// Bitmasks splitting `side` indices into runs of `len`, e.g. the three runs of three along a
+/// classic row.
+fn segments(side: usize, len: usize) -> Vec<u16> {
+    (0..side / len)
+        .map(|i| (((1u32 << len) - 1) << (i * len)) as u16)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::example;
+    use crate::{Deduction, SolveOutcome, SolverConfig, SudokuGrid};
+
+    /// An empty grid where digit 4 is restricted to the given columns in rows 2 and 7.
+    fn solver_with_rows<'a>(
+        grid: &'a mut SudokuGrid,
+        row_1: &[usize],
+        row_6: &[usize],
+    ) -> Solver<'a> {
+        let mut solver = Solver::new(grid).unwrap();
+        for col in 0..9 {
+            if !row_1.contains(&col) {
+                solver.candidates.remove(1, col, 4);
+            }
+            if !row_6.contains(&col) {
+                solver.candidates.remove(6, col, 4);
+            }
+        }
+        solver
+    }
+
+    fn find_fish(solver: &Solver, kind: FishKind) -> Step {
+        solver
+            .fish_steps(2)
+            .into_iter()
+            .find(|step| step.technique == Technique::Fish { size: 2, kind })
+            .unwrap()
+    }
+
+    #[test]
+    fn x_wing() {
+        let mut grid = SudokuGrid::empty();
+        let mut solver = solver_with_rows(&mut grid, &[2, 6], &[2, 6]);
+
+        let step = find_fish(&solver, FishKind::Basic);
+        assert_eq!(step.cells, vec![(1, 2), (1, 6), (6, 2), (6, 6)]);
+        assert_eq!(step.eliminations.len(), 14);
+
+        solver.apply_fish();
+        for row in [0, 2, 3, 4, 5, 7, 8] {
+            assert!(!solver.candidates.get(row, 2).contains(4));
+            assert!(!solver.candidates.get(row, 6).contains(4));
+        }
+        assert!(solver.candidates.get(1, 2).contains(4));
+        assert!(solver.candidates.get(3, 3).contains(4));
+    }
+
+    #[test]
+    fn finned_x_wing() {
+        let mut grid = SudokuGrid::empty();
+        let solver = solver_with_rows(&mut grid, &[2, 6], &[2, 6, 7]);
+
+        let step = find_fish(&solver, FishKind::Finned);
+        assert_eq!(step.eliminations, vec![((7, 6), 4), ((8, 6), 4)]);
+        assert!(
+            step.to_string()
+                .starts_with("Finned X-Wing {4} on r2c3, r2c7, r7c3, r7c7, r7c8 removes")
+        );
+    }
+
+    #[test]
+    fn sashimi_x_wing() {
+        let mut grid = SudokuGrid::empty();
+        let solver = solver_with_rows(&mut grid, &[2, 6], &[2, 7]);
+
+        // Either row can supply the fin, and either choice gives a sashimi X-Wing
+        let sashimi: Vec<Vec<((usize, usize), u8)>> = solver
+            .fish_steps(2)
+            .into_iter()
+            .filter(|step| {
+                step.technique
+                    == Technique::Fish {
+                        size: 2,
+                        kind: FishKind::Sashimi,
+                    }
+            })
+            .map(|step| step.eliminations)
+            .collect();
+        assert!(sashimi.contains(&vec![((7, 6), 4), ((8, 6), 4)]));
+        assert!(sashimi.contains(&vec![((0, 7), 4), ((2, 7), 4)]));
+    }
+
+    #[test]
+    fn no_fish_when_fins_span_boxes() {
+        let mut grid = SudokuGrid::empty();
+        let solver = solver_with_rows(&mut grid, &[2, 6], &[0, 2, 6, 7]);
+
+        // Every choice of cover leaves fins in two different boxes
+        assert!(
+            !solver
+                .fish_steps(2)
+                .iter()
+                .any(|step| { step.cells.contains(&(1, 2)) && step.cells.contains(&(6, 0)) })
+        );
+    }
+
+    #[test]
+    fn fewer_guesses_with_fish() {
+        let puzzle = example("ultraviolet.txt");
+        let search = |fish| {
+            let mut grid = puzzle.clone();
+            let config = SolverConfig::default().enable(Deduction::Fish, fish);
+            let mut solver = Solver::new(&mut grid).unwrap().with_config(config);
+            assert_eq!(solver.solve(), SolveOutcome::Solved);
+            solver.stats().clone()
+        };
+
+        let with_fish = search(true);
+        let without_fish = search(false);
+        assert!(with_fish.nodes < without_fish.nodes);
+        assert!(with_fish.backtracks < without_fish.backtracks);
+    }
+}
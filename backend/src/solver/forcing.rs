@@ -0,0 +1,340 @@
+use std::collections::HashMap;
+
+use super::Solver;
+use super::step::{Step, Technique};
+use crate::candidates::{Candidate, DigitSet};
+use crate::variant::VariantContradiction;
+
+/// The most singles followed from an assumption before it is given up on as inconclusive.
+const MAX_FORCING_DEPTH: usize = 20;
+
+impl Solver<'_> {
+    /// Finds the forcing chain with the shortest chains, preferring Nishio to cell forcing
+    /// chains.
+    pub(super) fn forcing_step(&self) -> Option<Step> {
+        self.forcing_steps()
+            .into_iter()
+            .min_by_key(|step| step.chains.iter().map(Vec::len).sum::<usize>())
+    }
+
+    /// Assumes every candidate in turn and follows it through the variants' propagation and
+    /// any singles that appear, up to `MAX_FORCING_DEPTH` of them. Returns a Nishio step for
+    /// each candidate whose assumption runs into a contradiction, or if there are none, a cell
+    /// forcing chain for each bivalue cell whose two candidates force a common digit.
+    ///
+    /// https://www.sudokuwiki.org/Cell_Forcing_Chains
+    fn forcing_steps(&self) -> Vec<Step> {
+        let mut scratch = self.fork();
+        let mut nishio = Vec::new();
+        let mut followed: HashMap<Candidate, Vec<Candidate>> = HashMap::new();
+        for cell in self.size().cells() {
+            if self.sudoku_grid.get_cell(cell.0, cell.1) != 0 {
+                continue;
+            }
+            let digits = self.candidates.get(cell.0, cell.1);
+            for digit in digits {
+                match scratch.assume(cell, digit) {
+                    Err(chain) => nishio.push(Step {
+                        technique: Technique::Nishio,
+                        unit: None,
+                        digits: DigitSet::EMPTY,
+                        cells: vec![cell],
+                        placements: Vec::new(),
+                        eliminations: vec![(cell, digit)],
+                        chains: vec![chain],
+                    }),
+                    Ok(chain) if digits.len() == 2 => {
+                        followed.insert((cell, digit), chain);
+                    }
+                    Ok(_) => {}
+                }
+            }
+        }
+        if !nishio.is_empty() {
+            return nishio;
+        }
+
+        self.cell_forcing_chains(&followed)
+    }
+
+    /// Builds a cell forcing chain for each bivalue cell whose two candidates, followed as in
+    /// `followed`, force a common digit.
+    fn cell_forcing_chains(&self, followed: &HashMap<Candidate, Vec<Candidate>>) -> Vec<Step> {
+        let mut steps = Vec::new();
+        for cell in self.size().cells() {
+            let digits = self.candidates.get(cell.0, cell.1);
+            let (Some(a), Some(b)) = (
+                digits.min().and_then(|first| followed.get(&(cell, first))),
+                digits
+                    .max()
+                    .and_then(|second| followed.get(&(cell, second))),
+            ) else {
+                continue;
+            };
+            // Cells already down to one candidate only show up because the search leaves them
+            let placements: Vec<Candidate> = a[1..]
+                .iter()
+                .filter(|&&((r, c), digit)| {
+                    self.candidates.get(r, c).len() > 1 && b.contains(&((r, c), digit))
+                })
+                .copied()
+                .collect();
+            if placements.is_empty() {
+                continue;
+            }
+            // Each chain only needs to go as far as the last digit both force
+            let upto = |chain: &[Candidate]| {
+                let end = chain.iter().rposition(|p| placements.contains(p)).unwrap();
+                chain[..=end].to_vec()
+            };
+            steps.push(Step {
+                technique: Technique::CellForcingChain,
+                unit: None,
+                digits,
+                cells: vec![cell],
+                chains: vec![upto(a), upto(b)],
+                placements,
+                eliminations: Vec::new(),
+            });
+        }
+        steps
+    }
+
+    /// Runs forcing chains at a search node until none are left. Digits they force are left as
+    /// their cell's only candidate rather than placed, since backtracking only takes back the
+    /// guessed digit. Fails if a cell is left without candidates.
+    pub(super) fn apply_forcing(&mut self) -> Result<(), VariantContradiction> {
+        loop {
+            if let Some(cell) = self.size().cells().find(|&(r, c)| {
+                self.sudoku_grid.get_cell(r, c) == 0 && self.candidates.get(r, c).is_empty()
+            }) {
+                return Err(VariantContradiction::NoPossibilities {
+                    cell,
+                    variant: "Solver",
+                    reason: "Forcing chains ruled out every candidate".to_string(),
+                });
+            }
+            let steps = self.forcing_steps();
+            if steps.is_empty() {
+                return Ok(());
+            }
+            // Each step holds on its own, so taking them all only removes more
+            for mut step in steps {
+                for ((row, col), digit) in std::mem::take(&mut step.placements) {
+                    let others = self.candidates.get(row, col) - DigitSet::single(digit);
+                    step.eliminations
+                        .extend(others.iter().map(|other| ((row, col), other)));
+                }
+                self.apply_step(&step);
+            }
+        }
+    }
+
+    /// Places `digit` in `cell` and follows the singles that leads to. Returns the digits
+    /// forced in order, starting with the assumption, as an error if they ran into a
+    /// contradiction. The grid and candidates are left as they were.
+    fn assume(
+        &mut self,
+        cell: (usize, usize),
+        digit: u8,
+    ) -> Result<Vec<Candidate>, Vec<Candidate>> {
+        let candidates = self.candidates;
+        let mut chain = vec![(cell, digit)];
+        let outcome = self.follow(&mut chain);
+        for &((row, col), _) in &chain {
+            self.sudoku_grid.set_cell(row, col, 0);
+        }
+        if outcome.is_err() {
+            // Propagation stopped partway, leaving the naked singles it had found unplaced
+            let forced: Vec<Candidate> = self
+                .size()
+                .cells()
+                .filter(|&(r, c)| self.sudoku_grid.get_cell(r, c) == 0)
+                .filter(|&(r, c)| candidates.get(r, c).len() > 1)
+                .filter_map(|(r, c)| Some(((r, c), self.candidates.get(r, c).single_digit()?)))
+                .filter(|single| !chain.contains(single))
+                .collect();
+            chain.extend(forced);
+        }
+        self.candidates = candidates;
+        match outcome {
+            Ok(()) => Ok(chain),
+            Err(_) => Err(chain),
+        }
+    }
+
+    /// Places the last digit of `chain` and propagates it, then appends the next single and
+    /// repeats until there is none or the chain reaches `MAX_FORCING_DEPTH`.
+    fn follow(&mut self, chain: &mut Vec<Candidate>) -> Result<(), VariantContradiction> {
+        loop {
+            let ((row, col), digit) = chain[chain.len() - 1];
+            self.sudoku_grid.set_cell(row, col, digit);
+            self.update_possibilities(row, col)?;
+            if chain.len() > MAX_FORCING_DEPTH {
+                return Ok(());
+            }
+            match self.next_single()? {
+                Some(single) => chain.push(single),
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// The first naked single in reading order, or else the first hidden single. Fails if a
+    /// cell has no candidates left, or a unit has no place left for a digit.
+    fn next_single(&self) -> Result<Option<Candidate>, VariantContradiction> {
+        let empty = |&(r, c): &(usize, usize)| self.sudoku_grid.get_cell(r, c) == 0;
+        for cell in self.size().cells().filter(empty) {
+            let digits = self.candidates.get(cell.0, cell.1);
+            if digits.is_empty() {
+                return Err(VariantContradiction::NoPossibilities {
+                    cell,
+                    variant: "Solver",
+                    reason: "No candidates left".to_string(),
+                });
+            }
+            if let Some(digit) = digits.single_digit() {
+                return Ok(Some((cell, digit)));
+            }
+        }
+        for unit in self.units.iter().filter(|unit| unit.is_complete()) {
+            let cells = unit.cells(self.size());
+            for digit in 1..=self.size().max_digit() {
+                if cells
+                    .iter()
+                    .any(|&(r, c)| self.sudoku_grid.get_cell(r, c) == digit)
+                {
+                    continue;
+                }
+                let mut places = cells.iter().filter(|&&cell| {
+                    empty(&cell) && self.candidates.get(cell.0, cell.1).contains(digit)
+                });
+                match (places.next(), places.next()) {
+                    (None, _) => {
+                        return Err(VariantContradiction::Inconsistent {
+                            variant: "Solver",
+                            reason: format!("No place left for {digit} in {unit}"),
+                        });
+                    }
+                    (Some(&cell), None) => return Ok(Some((cell, digit))),
+                    _ => {}
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::example;
+    use crate::{Deduction, SolveOutcome, SolverConfig, SudokuGrid};
+
+    fn forcing() -> SolverConfig {
+        SolverConfig::default().enable(Deduction::ForcingChains, true)
+    }
+
+    fn restrict(solver: &mut Solver, cell: (usize, usize), digits: &[u8]) {
+        solver
+            .candidates
+            .set(cell.0, cell.1, DigitSet::from_digits(digits));
+    }
+
+    #[test]
+    fn nishio_removes_a_candidate_leading_to_a_contradiction() {
+        let mut grid = SudokuGrid::empty();
+        let mut solver = Solver::new(&mut grid).unwrap();
+        // 1 in r1c1 leaves 2 for both r1c3 and r3c3, which share column 3
+        restrict(&mut solver, (0, 0), &[1, 5]);
+        restrict(&mut solver, (0, 2), &[1, 2]);
+        restrict(&mut solver, (2, 2), &[1, 2]);
+
+        let step = solver.forcing_step().unwrap();
+
+        assert_eq!(step.technique, Technique::Nishio);
+        assert_eq!(step.eliminations, vec![((0, 0), 1)]);
+        assert_eq!(step.chains, vec![vec![((0, 0), 1), ((0, 2), 2)]]);
+        assert_eq!(
+            step.to_string(),
+            "Nishio on r1c1 removes 1 from r1c1: 1 in r1c1 -> 2 in r1c3 -> contradiction"
+        );
+    }
+
+    #[test]
+    fn cell_forcing_chain_places_what_both_candidates_force() {
+        let mut grid = SudokuGrid::empty();
+        let mut solver = Solver::new(&mut grid).unwrap();
+        // 1 in r1c1 makes r1c5 a 3 and r1c4 a 5; 2 makes r2c1 a 3 and r2c2 a 5. Either way
+        // r2c5 loses both and is left with 4.
+        restrict(&mut solver, (0, 0), &[1, 2]);
+        restrict(&mut solver, (0, 4), &[1, 3]);
+        restrict(&mut solver, (0, 3), &[1, 5]);
+        restrict(&mut solver, (1, 0), &[2, 3]);
+        restrict(&mut solver, (1, 1), &[2, 5]);
+        restrict(&mut solver, (1, 4), &[3, 4, 5]);
+        let mut scratch = solver.fork();
+        let followed: HashMap<Candidate, Vec<Candidate>> = [1, 2]
+            .into_iter()
+            .map(|digit| (((0, 0), digit), scratch.assume((0, 0), digit).unwrap()))
+            .collect();
+
+        let steps = solver.cell_forcing_chains(&followed);
+
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].technique, Technique::CellForcingChain);
+        assert_eq!(steps[0].digits, DigitSet::from_digits(&[1, 2]));
+        assert_eq!(steps[0].placements, vec![((1, 4), 4)]);
+        assert!(
+            steps[0]
+                .chains
+                .iter()
+                .all(|chain| chain.ends_with(&[((1, 4), 4)]))
+        );
+        // The scratch solver was put back after each assumption
+        assert_eq!(scratch.candidates, solver.candidates);
+        assert_eq!(*scratch.sudoku_grid, *solver.sudoku_grid);
+    }
+
+    #[test]
+    fn no_forcing_chains_in_an_empty_grid() {
+        let mut grid = SudokuGrid::empty();
+        let solver = Solver::new(&mut grid).unwrap();
+
+        assert_eq!(solver.forcing_step(), None);
+    }
+
+    #[test]
+    fn logical_solve_falls_back_on_forcing_chains() {
+        let mut solution = example("lumber.txt");
+        let mut solver = Solver::new(&mut solution).unwrap().with_config(forcing());
+        assert_eq!(solver.solve(), SolveOutcome::Solved);
+
+        let mut grid = example("lumber.txt");
+        // Forcing chains are off by default, and nothing else applies
+        assert_eq!(Solver::new(&mut grid).unwrap().next_step(), None);
+        let step = Solver::new(&mut grid)
+            .unwrap()
+            .with_config(forcing())
+            .next_step()
+            .unwrap();
+
+        assert_eq!(step.technique, Technique::Nishio);
+        let ((row, col), digit) = step.eliminations[0];
+        assert_ne!(solution.get_cell(row, col), digit);
+    }
+
+    #[test]
+    fn search_runs_forcing_chains_when_enabled() {
+        assert!(!SolverConfig::default().is_enabled(Deduction::ForcingChains));
+
+        let mut grid = example("lumber.txt");
+        let mut solver = Solver::new(&mut grid).unwrap().with_config(forcing());
+
+        assert_eq!(solver.solve(), SolveOutcome::Solved);
+        // Without forcing chains the search backtracks thousands of times
+        assert!(solver.stats().backtracks < 20);
+        assert!(solver.stats().eliminations[&Technique::Nishio] > 0);
+        assert!(grid.is_board_valid());
+    }
+}
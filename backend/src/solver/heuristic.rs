@@ -0,0 +1,314 @@
+use std::cmp::Reverse;
+use std::fmt;
+use std::sync::Arc;
+
+use super::Branching;
+use super::cell_set::CellSet;
+use crate::SudokuGrid;
+use crate::candidates::{CandidateGrid, DigitSet};
+
+/// Decides where the search branches and in which order it tries the digits there, set with
+/// `SolverConfig::heuristic`. `Branching` holds the built-in ones.
+///
+/// Both methods should be deterministic, so that a solve always visits the same nodes and its
+/// trace can be replayed.
+pub trait BranchHeuristic: fmt::Debug + Send + Sync {
+    /// Picks the empty cell to branch on. Only called while some cell is empty and every empty
+    /// cell has at least one candidate. If the cell picked is filled or off the grid, the search
+    /// branches as `Branching::MostConstrained` would instead.
+    fn choose_cell(&self, state: &SearchState) -> (usize, usize);
+
+    /// Orders the candidates of the chosen cell, first to try first. Digits that aren't
+    /// candidates are skipped, and any left out are tried last in ascending order.
+    fn order_digits(
+        &self,
+        _state: &SearchState,
+        _cell: (usize, usize),
+        digits: DigitSet,
+    ) -> Vec<u8> {
+        digits.to_vec()
+    }
+}
+
+/// What a `BranchHeuristic` can see of the search at the node it's choosing for.
+pub struct SearchState<'a> {
+    pub(super) grid: &'a SudokuGrid,
+    pub(super) candidates: &'a CandidateGrid,
+    pub(super) cell_variants: &'a [Vec<usize>],
+    pub(super) sees: &'a [CellSet],
+}
+
+impl SearchState<'_> {
+    pub fn grid(&self) -> &SudokuGrid {
+        self.grid
+    }
+
+    pub fn candidates(&self, row: usize, col: usize) -> DigitSet {
+        self.candidates.get(row, col)
+    }
+
+    /// The empty cells in reading order.
+    pub fn empty_cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.grid
+            .size()
+            .cells()
+            .filter(|&(row, col)| self.grid.get_cell(row, col) == 0)
+    }
+
+    /// How many of the puzzle's variants constrain the cell, counting the cages the solver
+    /// derives from killer cages.
+    pub fn variant_count(&self, row: usize, col: usize) -> usize {
+        self.cell_variants[self.grid.size().index((row, col))].len()
+    }
+
+    /// The cells that can't share the cell's digit: its row, column and box, plus any a variant
+    /// forbids from repeating it.
+    pub fn seen_cells(&self, row: usize, col: usize) -> impl Iterator<Item = (usize, usize)> {
+        self.sees[self.grid.size().index((row, col))].iter()
+    }
+}
+
+impl BranchHeuristic for Branching {
+    fn choose_cell(&self, state: &SearchState) -> (usize, usize) {
+        let mut empty = state.empty_cells();
+        // `min_by_key` keeps the first of equal cells, so ties go to reading order
+        let cell = match self {
+            Branching::FirstEmpty => empty.next(),
+            Branching::MostConstrained | Branching::LeastConstrainingValue => {
+                empty.min_by_key(|&(row, col)| state.candidates(row, col).len())
+            }
+            Branching::MostConstrainedDegree => empty.min_by_key(|&(row, col)| {
+                (
+                    state.candidates(row, col).len(),
+                    Reverse(state.variant_count(row, col)),
+                )
+            }),
+        };
+        cell.expect("the search only branches while a cell is empty")
+    }
+
+    fn order_digits(&self, state: &SearchState, cell: (usize, usize), digits: DigitSet) -> Vec<u8> {
+        let mut order = digits.to_vec();
+        if *self == Branching::LeastConstrainingValue {
+            let (row, col) = cell;
+            // Stable, so digits ruling out as many candidates stay in ascending order
+            order.sort_by_key(|&digit| {
+                state
+                    .seen_cells(row, col)
+                    .filter(|&(r, c)| {
+                        state.grid.get_cell(r, c) == 0 && state.candidates(r, c).contains(digit)
+                    })
+                    .count()
+            });
+        }
+        order
+    }
+}
+
+/// The heuristic a `SolverConfig` holds: a built-in one, or one set with
+/// `SolverConfig::heuristic`.
+#[derive(Debug, Clone)]
+pub(super) enum Heuristic {
+    Builtin(Branching),
+    Custom(Arc<dyn BranchHeuristic>),
+}
+
+impl Heuristic {
+    pub(super) fn get(&self) -> &dyn BranchHeuristic {
+        match self {
+            Heuristic::Builtin(branching) => branching,
+            Heuristic::Custom(heuristic) => heuristic.as_ref(),
+        }
+    }
+}
+
+/// Custom heuristics are equal when they're the same instance, as with `CancellationToken`.
+impl PartialEq for Heuristic {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Heuristic::Builtin(a), Heuristic::Builtin(b)) => a == b,
+            (Heuristic::Custom(a), Heuristic::Custom(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::example;
+    use crate::{
+        Diagonal, KillerCage, SolveEvent, SolveOutcome, Solver, SolverConfig, SudokuVariant,
+        TraceRecorder,
+    };
+
+    use super::super::NextCell;
+
+    /// The cell the search would branch on first and the order of its digits.
+    fn first_branch(grid: &SudokuGrid, config: SolverConfig) -> ((usize, usize), Vec<u8>) {
+        let mut grid = grid.clone();
+        let solver = Solver::new(&mut grid).unwrap().with_config(config);
+        match solver.find_branch_cell() {
+            NextCell::Cell(row, col, digits) => ((row, col), digits),
+            _ => panic!("expected a cell to branch on"),
+        }
+    }
+
+    fn branching(branching: Branching) -> SolverConfig {
+        SolverConfig::default().branching(branching)
+    }
+
+    /// Row 1 holds 1 to 7, leaving 8 and 9 for its last two cells, with a 9 in row 2.
+    fn two_left() -> SudokuGrid {
+        let mut grid = SudokuGrid::empty();
+        for col in 0..7 {
+            grid.set_cell(0, col, col as u8 + 1);
+        }
+        grid.set_cell(1, 3, 9);
+        grid
+    }
+
+    /// Picks the last empty cell and tries its digits from the top.
+    #[derive(Debug)]
+    struct LastEmpty;
+
+    impl BranchHeuristic for LastEmpty {
+        fn choose_cell(&self, state: &SearchState) -> (usize, usize) {
+            state.empty_cells().last().unwrap()
+        }
+
+        fn order_digits(&self, _: &SearchState, _: (usize, usize), digits: DigitSet) -> Vec<u8> {
+            let mut order = digits.to_vec();
+            order.reverse();
+            order
+        }
+    }
+
+    /// Picks the same cell whatever the grid holds.
+    #[derive(Debug)]
+    struct Always((usize, usize));
+
+    impl BranchHeuristic for Always {
+        fn choose_cell(&self, _: &SearchState) -> (usize, usize) {
+            self.0
+        }
+    }
+
+    #[test]
+    fn most_constrained_breaks_ties_in_reading_order() {
+        let grid = two_left();
+
+        let first = first_branch(&grid, SolverConfig::default());
+
+        assert_eq!(first, ((0, 7), vec![8, 9]));
+        assert_eq!(first_branch(&grid, SolverConfig::default()), first);
+        assert_eq!(
+            first_branch(&SudokuGrid::empty(), SolverConfig::default()).0,
+            (0, 0)
+        );
+    }
+
+    #[test]
+    fn degree_prefers_the_cell_with_more_variants() {
+        let mut grid = SudokuGrid::empty();
+        // Both cages leave two candidates in each of their cells, but only the second lies on the
+        // diagonal, and only (4, 4) of it
+        grid.add_variant(SudokuVariant::Killer(KillerCage::new(
+            vec![(0, 1), (0, 2)],
+            3,
+        )));
+        grid.add_variant(SudokuVariant::Killer(KillerCage::new(
+            vec![(4, 4), (4, 5)],
+            17,
+        )));
+        grid.add_variant(SudokuVariant::Diagonal(Diagonal::new(true)));
+
+        assert_eq!(first_branch(&grid, SolverConfig::default()).0, (0, 1));
+        assert_eq!(
+            first_branch(&grid, branching(Branching::MostConstrainedDegree)),
+            ((4, 4), vec![8, 9])
+        );
+    }
+
+    #[test]
+    fn least_constraining_value_tries_the_freer_digit_first() {
+        // The 9 in row 2 already rules 9 out of three cells r1c8 sees, so 9 removes fewer
+        // candidates than 8 does
+        let first = first_branch(&two_left(), branching(Branching::LeastConstrainingValue));
+
+        assert_eq!(first, ((0, 7), vec![9, 8]));
+    }
+
+    #[test]
+    fn unusable_choice_falls_back_to_most_constrained() {
+        for cell in [(0, 0), (9, 3)] {
+            let config = SolverConfig::default().heuristic(Always(cell));
+
+            assert_eq!(first_branch(&two_left(), config), ((0, 7), vec![8, 9]));
+        }
+    }
+
+    #[test]
+    fn custom_heuristic_drives_the_search() {
+        let mut grid = SudokuGrid::empty();
+        let recorder = TraceRecorder::new();
+        let config = SolverConfig::default().heuristic(LastEmpty);
+        let mut solver = Solver::new(&mut grid)
+            .unwrap()
+            .with_config(config)
+            .with_observer(recorder.clone());
+
+        assert_eq!(solver.solve(), SolveOutcome::Solved);
+        assert_eq!(
+            recorder.events()[0],
+            SolveEvent::Guess {
+                cell: (8, 8),
+                digit: 9,
+                depth: 1
+            }
+        );
+        assert_eq!(grid.get_cell(8, 8), 9);
+    }
+
+    #[test]
+    fn every_builtin_solves_with_a_reproducible_trace() {
+        let puzzle = example("sudoku.txt");
+        let mut expected = puzzle.clone();
+        Solver::new(&mut expected).unwrap().solve();
+
+        for builtin in [
+            Branching::MostConstrained,
+            Branching::FirstEmpty,
+            Branching::MostConstrainedDegree,
+            Branching::LeastConstrainingValue,
+        ] {
+            let traces: Vec<Vec<SolveEvent>> = (0..2)
+                .map(|_| {
+                    let mut grid = puzzle.clone();
+                    let recorder = TraceRecorder::new();
+                    let mut solver = Solver::new(&mut grid)
+                        .unwrap()
+                        .with_config(branching(builtin))
+                        .with_observer(recorder.clone());
+                    assert_eq!(solver.solve(), SolveOutcome::Solved, "{builtin:?}");
+                    assert_eq!(grid, expected, "{builtin:?}");
+                    recorder.events()
+                })
+                .collect();
+            assert_eq!(traces[0], traces[1], "{builtin:?}");
+        }
+    }
+
+    #[test]
+    fn configs_compare_heuristics() {
+        let custom = SolverConfig::default().heuristic(LastEmpty);
+
+        assert_eq!(
+            SolverConfig::default(),
+            branching(Branching::MostConstrained)
+        );
+        assert_ne!(SolverConfig::default(), branching(Branching::FirstEmpty));
+        assert_eq!(custom.clone(), custom);
+        assert_ne!(SolverConfig::default().heuristic(LastEmpty), custom);
+    }
+}
@@ -0,0 +1,242 @@
+use std::ops::Range;
+
+use super::cell_set::CellSet;
+use crate::variant::{CellSum, KillerCage};
+use crate::{GridSize, SudokuGrid, SudokuVariant};
+
+/// Leftover regions larger than this aren't worth a virtual cage.
+const MAX_VIRTUAL_CELLS: usize = 5;
+
+/// Derives extra killer cages with the rule of 45: every row, column and box sums to 45 (or
+/// whatever the digits of the grid add up to), so a region made of whole houses sums to a
+/// multiple of that.
+///
+/// Subtracting the cages inside such a region leaves the sum of its uncaged cells (the
+/// "innies"), and subtracting the region from the cages covering it leaves the sum of the cells
+/// poking out (the "outies"). Each small leftover region becomes a `KillerCage` if its cells all
+/// see each other (per `sees`), since a cage can't repeat a digit, and a `CellSum` otherwise.
+///
+/// https://www.sudokuwiki.org/Killer_Sudoku
+pub(super) fn virtual_cages(grid: &SudokuGrid, sees: &[CellSet]) -> Vec<SudokuVariant> {
+    let size = grid.size();
+    let cages: Vec<(CellSet, i32)> = grid
+        .variants()
+        .filter_map(|variant| match variant {
+            SudokuVariant::Killer(cage) => Some((
+                cage.cells().iter().copied().collect(),
+                i32::from(cage.sum()),
+            )),
+            _ => None,
+        })
+        .collect();
+    if cages.is_empty() {
+        return Vec::new();
+    }
+
+    let house_sum = size.house_sum() as i32;
+    let mut found: Vec<(CellSet, i32)> = Vec::new();
+    for (region, houses) in regions(size) {
+        let region_sum = house_sum * houses;
+
+        // Innies: the region minus the cages entirely inside it
+        let (inside, inside_sum) = cages
+            .iter()
+            .filter(|&&(cells, _)| (cells - region).is_empty())
+            .fold((CellSet::EMPTY, 0), |(m, s), &(cells, sum)| {
+                (m | cells, s + sum)
+            });
+        found.push((region - inside, region_sum - inside_sum));
+
+        // Outies: the cages touching the region minus the region, if they cover all of it
+        let (touching, touching_sum) = cages
+            .iter()
+            .filter(|&&(cells, _)| !(cells & region).is_empty())
+            .fold((CellSet::EMPTY, 0), |(m, s), &(cells, sum)| {
+                (m | cells, s + sum)
+            });
+        if (region - touching).is_empty() {
+            found.push((touching - region, touching_sum - region_sum));
+        }
+    }
+
+    let max_digit = i32::from(size.max_digit());
+    let mut virtual_cages: Vec<(CellSet, i32)> = Vec::new();
+    for (cells, sum) in found {
+        let useful = (1..=MAX_VIRTUAL_CELLS).contains(&cells.len())
+            && (1..=cells.len() as i32 * max_digit).contains(&sum)
+            && !cages.contains(&(cells, sum))
+            && !virtual_cages.contains(&(cells, sum));
+        if useful {
+            virtual_cages.push((cells, sum));
+        }
+    }
+    virtual_cages
+        .into_iter()
+        .map(|(cells, sum)| {
+            if all_see_each_other(size, cells, sees) {
+                SudokuVariant::Killer(KillerCage::new(cells.iter().collect(), sum as u8))
+            } else {
+                SudokuVariant::CellSum(CellSum::new(cells.iter().collect(), sum as u8))
+            }
+        })
+        .collect()
+}
+
+/// Regions made of whole houses, with the number of houses in each: every run of consecutive
+/// rows (the last being the whole grid), every run of consecutive columns short of the whole
+/// grid, every box, and every union of two of these that don't overlap, such as two boxes or a
+/// box and a row.
+fn regions(size: GridSize) -> Vec<(CellSet, i32)> {
+    let side = size.side();
+    let rows = |rows: Range<usize>| rows.flat_map(|r| (0..side).map(move |c| (r, c))).collect();
+    let cols = |cols: Range<usize>| cols.flat_map(|c| (0..side).map(move |r| (r, c))).collect();
+    let mut blocks: Vec<(CellSet, i32)> = Vec::new();
+    for len in 1..=side {
+        for start in 0..=side - len {
+            blocks.push((rows(start..start + len), len as i32));
+            if len < side {
+                blocks.push((cols(start..start + len), len as i32));
+            }
+        }
+    }
+    for b in 0..side {
+        blocks.push((size.box_cells(b).into_iter().collect(), 1));
+    }
+
+    let mut regions = blocks.clone();
+    for (i, &(a, a_houses)) in blocks.iter().enumerate() {
+        for &(b, b_houses) in &blocks[i + 1..] {
+            if (a & b).is_empty() {
+                regions.push((a | b, a_houses + b_houses));
+            }
+        }
+    }
+    regions
+}
+
+fn all_see_each_other(size: GridSize, cells: CellSet, sees: &[CellSet]) -> bool {
+    cells.iter().all(|cell| {
+        let seen = sees[size.index(cell)];
+        cells
+            .iter()
+            .all(|other| other == cell || seen.contains(other))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::candidates::DigitSet;
+    use crate::test_support::example;
+    use crate::{SolveOutcome, Solver, Technique};
+
+    fn cage(cells: &[(usize, usize)], sum: u8) -> SudokuVariant {
+        SudokuVariant::Killer(KillerCage::new(cells.to_vec(), sum))
+    }
+
+    fn cell_sum(cells: &[(usize, usize)], sum: u8) -> SudokuVariant {
+        SudokuVariant::CellSum(CellSum::new(cells.to_vec(), sum))
+    }
+
+    fn found(grid: &SudokuGrid) -> Vec<SudokuVariant> {
+        let mut copy = grid.clone();
+        let solver = Solver::new(&mut copy).unwrap();
+        virtual_cages(grid, &solver.sees)
+    }
+
+    /// Two cages covering the first eight cells of row 1.
+    fn nearly_caged_row() -> SudokuGrid {
+        let mut grid = SudokuGrid::empty();
+        grid.add_variant(cage(&[(0, 0), (0, 1), (0, 2), (0, 3)], 10));
+        grid.add_variant(cage(&[(0, 4), (0, 5), (0, 6), (0, 7)], 26));
+        grid
+    }
+
+    #[test]
+    fn innie_of_a_row() {
+        let grid = nearly_caged_row();
+
+        assert!(found(&grid).contains(&cage(&[(0, 8)], 9)));
+    }
+
+    #[test]
+    fn outie_of_a_row() {
+        let mut grid = SudokuGrid::empty();
+        grid.add_variant(cage(&[(0, 0), (0, 1), (0, 2), (1, 0)], 20));
+        grid.add_variant(cage(&[(0, 3), (0, 4), (0, 5), (0, 6), (0, 7), (0, 8)], 30));
+
+        assert!(found(&grid).contains(&cage(&[(1, 0)], 5)));
+    }
+
+    #[test]
+    fn innie_of_two_boxes() {
+        let mut grid = SudokuGrid::empty();
+        // Each cage straddles boxes 1 and 2, so neither box on its own has a cage inside it
+        grid.add_variant(cage(&[(0, 0), (0, 1), (0, 2), (0, 3), (0, 4), (0, 5)], 30));
+        grid.add_variant(cage(&[(1, 0), (1, 1), (1, 2), (1, 3), (1, 4), (1, 5)], 30));
+        grid.add_variant(cage(&[(2, 0), (2, 1), (2, 2), (2, 3), (2, 4)], 25));
+
+        assert!(found(&grid).contains(&cage(&[(2, 5)], 5)));
+    }
+
+    #[test]
+    fn only_sums_cells_that_can_repeat() {
+        let mut grid = nearly_caged_row();
+        grid.add_variant(cage(&[(1, 1), (1, 2), (1, 3), (1, 4)], 10));
+        grid.add_variant(cage(&[(1, 5), (1, 6), (1, 7), (1, 8)], 26));
+
+        let cages = found(&grid);
+
+        assert!(cages.contains(&cage(&[(0, 8)], 9)));
+        assert!(cages.contains(&cage(&[(1, 0)], 9)));
+        // The innies of rows 1 and 2 together don't see each other, so could both be 9
+        assert!(!cages.contains(&cage(&[(0, 8), (1, 0)], 18)));
+        assert!(cages.contains(&cell_sum(&[(0, 8), (1, 0)], 18)));
+    }
+
+    #[test]
+    fn outies_in_different_houses() {
+        let mut grid = SudokuGrid::empty();
+        grid.add_variant(cage(&[(0, 0), (0, 1), (0, 2), (0, 3), (0, 4), (1, 8)], 30));
+        grid.add_variant(cage(&[(0, 5), (0, 6), (0, 7), (0, 8), (2, 0)], 25));
+
+        let cages = found(&grid);
+
+        // r2c9 and r3c1 poke out of row 1, and don't see each other
+        assert!(cages.contains(&cell_sum(&[(1, 8), (2, 0)], 10)));
+        assert!(!cages.contains(&cage(&[(1, 8), (2, 0)], 10)));
+    }
+
+    #[test]
+    fn no_cages_without_killers() {
+        assert!(found(&SudokuGrid::empty()).is_empty());
+    }
+
+    #[test]
+    fn solver_uses_virtual_cages() {
+        let mut grid = nearly_caged_row();
+
+        let solver = Solver::new(&mut grid).unwrap();
+
+        assert_eq!(solver.candidates.get(0, 8), DigitSet::from_digits(&[9]));
+    }
+
+    #[test]
+    fn virtual_cages_help_draft_day() {
+        let mut grid = example("draft_day.txt");
+        let mut copy = grid.clone();
+        let mut without = Solver::build(&mut copy, false).unwrap();
+        assert_eq!(without.solve(), SolveOutcome::Solved);
+
+        let mut solver = Solver::new(&mut grid).unwrap();
+        assert_eq!(solver.solve(), SolveOutcome::Solved);
+
+        let cage = Technique::Propagation {
+            variant: Some("KillerCage"),
+        };
+        // Its cages are small and spread out, so the derived ones propagate further
+        let (with, without) = (solver.stats(), without.stats());
+        assert!(with.eliminations[&cage] > without.eliminations[&cage]);
+        assert!(with.nodes < without.nodes);
+    }
+}
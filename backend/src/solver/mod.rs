@@ -0,0 +1,2909 @@
+use std::collections::{HashMap, VecDeque};
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use itertools::Itertools;
+
+use crate::candidates::{CandidateGrid, DigitSet};
+use crate::variant::VariantContradiction;
+use crate::{GridSize, SudokuGrid, SudokuVariant};
+use cell_set::CellSet;
+use parallel::Shared;
+
+mod cell_set;
+mod chains;
+mod config;
+mod conflict;
+mod dlx;
+mod fish;
+mod forcing;
+mod heuristic;
+mod innies;
+mod parallel;
+mod rating;
+mod stats;
+mod step;
+mod trace;
+mod wings;
+
+pub use config::{
+    Backend, Branching, CancellationToken, Deduction, Progress, SolveOutcome, SolverConfig,
+};
+pub use conflict::{Conflict, minimal_conflict};
+pub use heuristic::{BranchHeuristic, SearchState};
+pub use rating::{Band, DifficultyReport, Tier, rate};
+pub use stats::SolveStats;
+pub use step::{FishKind, Step, Technique, Unit};
+pub(crate) use step::{cell_list, cell_name};
+pub use trace::{PrintObserver, SolveEvent, SolveObserver, TraceRecorder};
+
+pub struct Solver<'a> {
+    sudoku_grid: GridHandle<'a>,
+    candidates: CandidateGrid,
+    // The grid's variants, followed by the cages and sums derived from killer cages by the rule
+    // of 45
+    variants: Vec<SudokuVariant>,
+    // For each cell (indexed by `GridSize::index`), the indices into `variants` of those
+    // constraining it
+    cell_variants: Vec<Vec<usize>>,
+    // For each cell, the other cells sharing its row, column or box
+    peers: Vec<Vec<(usize, usize)>>,
+    // For each cell, the cells that can't share its digit, i.e. its peers plus any cells a
+    // variant forbids from repeating it
+    sees: Vec<CellSet>,
+    // The rows, columns and boxes, followed by the houses and groups the variants add
+    units: Vec<Unit>,
+    config: SolverConfig,
+    progress: Option<ProgressCallback>,
+    observer: Option<Box<dyn SolveObserver + Send>>,
+    // The time each kind of variant took to give its starting candidates, reported with the
+    // stats of every search
+    possibilities_time: HashMap<&'static str, Duration>,
+    stats: SolveStats,
+}
+
+/// Callback given search progress every `interval` nodes.
+struct ProgressCallback {
+    interval: usize,
+    callback: Box<dyn FnMut(Progress) + Send>,
+}
+
+/// The variants waiting to propagate again, in the order their cells changed.
+struct Stale {
+    queued: Vec<bool>,
+    order: VecDeque<usize>,
+}
+
+impl Stale {
+    fn new(variants: usize) -> Self {
+        Stale {
+            queued: vec![false; variants],
+            order: VecDeque::new(),
+        }
+    }
+
+    fn mark(&mut self, variants: &[usize]) {
+        for &i in variants {
+            if !self.queued[i] {
+                self.queued[i] = true;
+                self.order.push_back(i);
+            }
+        }
+    }
+
+    fn next(&mut self) -> Option<usize> {
+        let i = self.order.pop_front()?;
+        self.queued[i] = false;
+        Some(i)
+    }
+}
+
+impl<'a> Solver<'a> {
+    pub fn new(sudoku_grid: &'a mut SudokuGrid) -> Result<Self, VariantContradiction> {
+        Self::build(sudoku_grid, true)
+    }
+
+    /// `new`, optionally leaving out the cages derived by the rule of 45.
+    fn build(
+        sudoku_grid: &'a mut SudokuGrid,
+        virtual_cages: bool,
+    ) -> Result<Self, VariantContradiction> {
+        let size = sudoku_grid.size();
+        let peers: Vec<Vec<(usize, usize)>> = size
+            .cells()
+            .map(|(row, col)| classic_peers(size, row, col))
+            .collect();
+        let mut sees: Vec<CellSet> = peers
+            .iter()
+            .map(|cells| cells.iter().copied().collect())
+            .collect();
+        for variant in sudoku_grid.variants() {
+            for ((row, col), seen) in size.cells().zip(sees.iter_mut()) {
+                seen.extend(variant.seen_cells(size, row, col));
+            }
+        }
+        let mut variants: Vec<SudokuVariant> = sudoku_grid.variants().cloned().collect();
+        if virtual_cages {
+            variants.extend(innies::virtual_cages(sudoku_grid, &sees));
+        }
+        let mut possibilities_time = HashMap::new();
+        let candidates =
+            Self::get_all_possibilities(sudoku_grid, &variants, &mut possibilities_time)?;
+        let mut cell_variants = vec![Vec::new(); size.side() * size.side()];
+        for (i, variant) in variants.iter().enumerate() {
+            for cell in variant.constrained_cells(size) {
+                let entry: &mut Vec<usize> = &mut cell_variants[size.index(cell)];
+                if !entry.contains(&i) {
+                    entry.push(i);
+                }
+            }
+        }
+        let mut units = Unit::all(size);
+        for variant in &variants {
+            let name = variant.name();
+            let houses = variant.houses(size).into_iter().map(|cells| Unit::House {
+                variant: name,
+                cells,
+            });
+            let groups = variant
+                .all_different_groups(size)
+                .into_iter()
+                .filter(|cells| cells.len() > 1)
+                .map(|cells| Unit::Group {
+                    variant: name,
+                    cells,
+                });
+            for unit in houses.chain(groups) {
+                if !units.contains(&unit) {
+                    units.push(unit);
+                }
+            }
+        }
+        Ok(Solver {
+            sudoku_grid: GridHandle::Borrowed(sudoku_grid),
+            candidates,
+            variants,
+            cell_variants,
+            peers,
+            sees,
+            units,
+            config: SolverConfig::default(),
+            progress: None,
+            observer: None,
+            possibilities_time,
+            stats: SolveStats::default(),
+        })
+    }
+
+    /// Replaces the default search limits and options.
+    pub fn with_config(mut self, config: SolverConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Calls `callback` every `interval` nodes of a search with how far it has got, e.g. to
+    /// drive a progress bar. The callback is not carried over to `solutions`.
+    pub fn on_progress(
+        mut self,
+        interval: usize,
+        callback: impl FnMut(Progress) + Send + 'static,
+    ) -> Self {
+        self.progress = Some(ProgressCallback {
+            interval: interval.max(1),
+            callback: Box::new(callback),
+        });
+        self
+    }
+
+    /// Reports every guess, backtrack, deduction and contradiction to `observer`. Like the
+    /// progress callback, the observer is not carried over to `solutions`.
+    pub fn with_observer(mut self, observer: impl SolveObserver + Send + 'static) -> Self {
+        self.observer = Some(Box::new(observer));
+        self
+    }
+
+    /// Searches for a solution, leaving it in the grid if one is found. Otherwise the grid is
+    /// left as it was passed in.
+    pub fn solve(&mut self) -> SolveOutcome {
+        let start = Instant::now();
+        self.reset_stats();
+        let exact_cover = match self.config.backend {
+            Backend::DancingLinks => self.solve_exact_cover(),
+            Backend::Search => None,
+        };
+        // Stop at the first solution, leaving it in the grid
+        let outcome = exact_cover.unwrap_or_else(|| {
+            if self.config.threads > 1 {
+                self.solve_parallel()
+            } else {
+                let initial_candidates = self.candidates;
+                let mut search = Search::new(&self.config);
+                let outcome = self.next_solution(&mut search);
+                if !outcome.is_solved() {
+                    self.undo_search(search, initial_candidates);
+                }
+                outcome
+            }
+        });
+        self.stats.wall_time = start.elapsed();
+        outcome
+    }
+
+    /// Statistics of the last call to `solve`, `count_solutions` or `uniqueness`.
+    pub fn stats(&self) -> &SolveStats {
+        &self.stats
+    }
+
+    /// Clears the stats for a new search, keeping the time the variants took to give their
+    /// starting candidates.
+    fn reset_stats(&mut self) {
+        self.stats = SolveStats {
+            variant_time: self.possibilities_time.clone(),
+            ..SolveStats::default()
+        };
+    }
+
+    /// Passes an event to the observer, only building it if there is one.
+    fn emit(&mut self, event: impl FnOnce() -> SolveEvent) {
+        if let Some(observer) = &mut self.observer {
+            observer.on_event(&event());
+        }
+    }
+
+    /// Counts the solutions of the puzzle, stopping once `limit` have been found.
+    ///
+    /// Also returns how the search ended: `Solved` if it stopped at `limit`, `NoSolution` if it
+    /// counted every solution, or why it gave up first, in which case the count is only a lower
+    /// bound. The grid is left as it was passed in.
+    pub fn count_solutions(&mut self, limit: usize) -> (usize, SolveOutcome) {
+        let (solutions, outcome) = self.collect_solutions(limit);
+        (solutions.len(), outcome)
+    }
+
+    /// Checks whether the puzzle has exactly one solution, returning the solution(s) found as
+    /// witnesses, or `Unknown` if the search gave up before it could tell.
+    pub fn uniqueness(&mut self) -> Uniqueness {
+        let (solutions, outcome) = self.collect_solutions(2);
+        let mut solutions = solutions.into_iter();
+        match (solutions.next(), solutions.next(), outcome) {
+            (Some(first), Some(second), _) => Uniqueness::Multiple(first, second),
+            (
+                _,
+                _,
+                SolveOutcome::LimitReached | SolveOutcome::TimedOut | SolveOutcome::Cancelled,
+            ) => Uniqueness::Unknown(outcome),
+            (None, _, _) => Uniqueness::NoSolution,
+            (Some(solution), None, _) => Uniqueness::Unique(solution),
+        }
+    }
+
+    pub fn is_unique(&mut self) -> bool {
+        matches!(self.uniqueness(), Uniqueness::Unique(_))
+    }
+
+    /// Lazily iterates over every solution of the puzzle, starting from the current grid.
+    ///
+    /// The iterator works on its own copy of the grid, so the solver (and the grid it was
+    /// created from) can be used independently while solutions are being streamed. Each call to
+    /// `next` gives up at the configured node or time limit without finding a solution.
+    pub fn solutions(&self) -> Solutions {
+        Solutions {
+            solver: self.fork(),
+            search: Search::new(&self.config),
+        }
+    }
+
+    /// A copy of the solver with its own copy of the grid, leaving out the progress callback
+    /// and observer.
+    fn fork(&self) -> Solver<'static> {
+        Solver {
+            sudoku_grid: GridHandle::Owned(self.sudoku_grid.clone()),
+            candidates: self.candidates,
+            variants: self.variants.clone(),
+            cell_variants: self.cell_variants.clone(),
+            peers: self.peers.clone(),
+            sees: self.sees.clone(),
+            units: self.units.clone(),
+            config: self.config.clone(),
+            progress: None,
+            observer: None,
+            possibilities_time: self.possibilities_time.clone(),
+            stats: SolveStats::default(),
+        }
+    }
+
+    /// Finds up to `limit` solutions, along with `Solved` if it stopped at `limit`,
+    /// `NoSolution` if there are no more, or why the search gave up.
+    fn collect_solutions(&mut self, limit: usize) -> (Vec<SudokuGrid>, SolveOutcome) {
+        let start = Instant::now();
+        self.reset_stats();
+        if self.config.backend == Backend::DancingLinks
+            && let Some(found) = self.exact_cover_solutions(limit)
+        {
+            self.stats.wall_time = start.elapsed();
+            return found;
+        }
+        if self.config.threads > 1 {
+            let found = self.parallel_solutions(limit);
+            self.stats.wall_time = start.elapsed();
+            return found;
+        }
+        let mut solutions = Vec::new();
+        let mut outcome = SolveOutcome::Solved;
+        let initial_candidates = self.candidates;
+        let mut search = Search::new(&self.config);
+        while solutions.len() < limit {
+            outcome = self.next_solution(&mut search);
+            if !outcome.is_solved() {
+                break;
+            }
+            solutions.push(self.sudoku_grid.clone());
+        }
+        self.undo_search(search, initial_candidates);
+        self.stats.wall_time = start.elapsed();
+        (solutions, outcome)
+    }
+
+    /// Undoes whatever placements the search still has on its stack and restores the
+    /// candidates it started from.
+    fn undo_search(&mut self, search: Search, initial_candidates: CandidateGrid) {
+        for frame in search.frames {
+            self.sudoku_grid.set_cell(frame.row, frame.col, 0);
+        }
+        self.candidates = initial_candidates;
+    }
+
+    /// Runs the depth-first search until it reaches the next solution, returning `Solved` with
+    /// the solution left in the grid, or why it stopped otherwise.
+    ///
+    /// All backtracking state lives in `search`, so calling this again resumes right after the
+    /// previous solution.
+    fn next_solution(&mut self, search: &mut Search) -> SolveOutcome {
+        loop {
+            if search.descend {
+                search.descend = false;
+                self.stats.nodes += 1;
+                if search.count_node() > self.config.max_nodes {
+                    return SolveOutcome::LimitReached;
+                }
+                if search
+                    .deadline
+                    .is_some_and(|deadline| Instant::now() >= deadline)
+                {
+                    return SolveOutcome::TimedOut;
+                }
+                if self.config.is_cancelled() || search.is_stopped() {
+                    return SolveOutcome::Cancelled;
+                }
+                if let Some(progress) = &mut self.progress
+                    && search.steps.is_multiple_of(progress.interval)
+                {
+                    let cells_filled = self
+                        .sudoku_grid
+                        .get_cells()
+                        .iter()
+                        .flatten()
+                        .filter(|&&v| v != 0)
+                        .count();
+                    (progress.callback)(Progress {
+                        nodes: search.steps,
+                        depth: search.frames.len(),
+                        cells_filled,
+                    });
+                }
+
+                // Find the next empty cell (if any)
+                match self.find_branch_cell() {
+                    NextCell::Cell(row, col, digits) => {
+                        search.frames.push(Frame {
+                            row,
+                            col,
+                            digits,
+                            tried: 0,
+                            saved_candidates: self.candidates,
+                        });
+                        self.stats.max_depth = self.stats.max_depth.max(search.frames.len());
+                    }
+                    NextCell::NoEmptyCells => {
+                        if self.validate_solution() {
+                            return SolveOutcome::Solved;
+                        }
+                    }
+                    NextCell::DeadEnd(row, col) => {
+                        self.emit(|| SolveEvent::Contradiction {
+                            contradiction: VariantContradiction::NoPossibilities {
+                                cell: (row, col),
+                                variant: "Solver",
+                                reason: "No candidates left".to_string(),
+                            },
+                        });
+                    }
+                }
+            }
+
+            // Move the deepest cell on to its next candidate, or backtrack out of it
+            let Some(frame) = search.frames.last_mut() else {
+                return SolveOutcome::NoSolution;
+            };
+            let (row, col) = (frame.row, frame.col);
+            let previous = self.sudoku_grid.get_cell(row, col);
+            self.sudoku_grid.set_cell(row, col, 0);
+            self.candidates = frame.saved_candidates;
+            let next = frame.digits.get(frame.tried).copied();
+            frame.tried += 1;
+            if previous != 0 {
+                self.stats.backtracks += 1;
+                self.emit(|| SolveEvent::Backtrack {
+                    cell: (row, col),
+                    digit: previous,
+                });
+            }
+
+            let Some(num) = next else {
+                // If no valid digit leads to a solution, backtrack
+                search.frames.pop();
+                continue;
+            };
+            let depth = search.frames.len();
+            self.emit(|| SolveEvent::Guess {
+                cell: (row, col),
+                digit: num,
+                depth,
+            });
+            match self.place(row, col, num) {
+                Ok(()) => search.descend = true,
+                Err(contradiction) => self.emit(|| SolveEvent::Contradiction { contradiction }),
+            }
+        }
+    }
+
+    /// Places a guess and propagates it, running the enabled deductions if that succeeds.
+    fn place(&mut self, row: usize, col: usize, num: u8) -> Result<(), VariantContradiction> {
+        self.sudoku_grid.set_cell(row, col, num);
+        self.update_possibilities(row, col)?;
+        if self.config.is_enabled(Deduction::NakedSubsets) {
+            self.apply_naked_subsets();
+        }
+        if self.config.is_enabled(Deduction::PointingPairs) {
+            self.apply_pointing_pairs();
+        }
+        if self.config.is_enabled(Deduction::HiddenPairs) {
+            self.apply_hidden_pairs();
+        }
+        if self.config.is_enabled(Deduction::Fish) {
+            self.apply_fish();
+        }
+        if self.config.is_enabled(Deduction::ForcingChains) {
+            self.apply_forcing()?;
+        }
+        Ok(())
+    }
+
+    fn validate_solution(&self) -> bool {
+        // Check that the sudoku grid is valid
+        if !self.sudoku_grid.is_board_valid() {
+            return false;
+        }
+        // Check that all variants are satisfied
+        for variant in &self.variants {
+            if !variant.validate_solution(&self.sudoku_grid) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Picks the cell to branch on next and the order of its digits, as set by the configured
+    /// heuristic.
+    fn find_branch_cell(&self) -> NextCell {
+        let mut filled = true;
+        for (row, col) in self.size().cells() {
+            if self.sudoku_grid.get_cell(row, col) != 0 {
+                continue;
+            }
+            if self.candidates.get(row, col).is_empty() {
+                return NextCell::DeadEnd(row, col);
+            }
+            filled = false;
+        }
+        if filled {
+            return NextCell::NoEmptyCells;
+        }
+
+        let mut heuristic = self.config.heuristic.get();
+        let state = SearchState {
+            grid: &self.sudoku_grid,
+            candidates: &self.candidates,
+            cell_variants: &self.cell_variants,
+            sees: &self.sees,
+        };
+        let mut cell = heuristic.choose_cell(&state);
+        // A custom heuristic may hand back a cell that can't be branched on
+        if !state.empty_cells().any(|empty| empty == cell) {
+            heuristic = &Branching::MostConstrained;
+            cell = heuristic.choose_cell(&state);
+        }
+        let (row, col) = cell;
+        // Keep each candidate once, in the heuristic's order, then any it left out
+        let mut left = self.candidates.get(row, col);
+        let mut digits: Vec<u8> = heuristic
+            .order_digits(&state, (row, col), left)
+            .into_iter()
+            .filter(|&digit| left.remove(digit))
+            .collect();
+        digits.extend(left.iter());
+        NextCell::Cell(row, col, digits)
+    }
+
+    /// Builds the candidate grid from scratch: classic row/column/box exclusions, intersected
+    /// with the possibilities reported by every variant. The time each kind of variant takes is
+    /// added to `times`.
+    fn get_all_possibilities(
+        sudoku_grid: &SudokuGrid,
+        variants: &[SudokuVariant],
+        times: &mut HashMap<&'static str, Duration>,
+    ) -> Result<CandidateGrid, VariantContradiction> {
+        let size = sudoku_grid.size();
+        let mut candidates = CandidateGrid::with_size(size);
+        if sudoku_grid.find_empty_cell().is_none() {
+            return Ok(candidates);
+        }
+
+        // Digits already used in each row, column and box
+        let mut rows = vec![DigitSet::EMPTY; size.side()];
+        let mut cols = vec![DigitSet::EMPTY; size.side()];
+        let mut boxes = vec![DigitSet::EMPTY; size.side()];
+        for (row, col) in size.cells() {
+            let value = sudoku_grid.get_cell(row, col);
+            if value != 0 {
+                rows[row].insert(value);
+                cols[col].insert(value);
+                boxes[size.box_index(row, col)].insert(value);
+            }
+        }
+        for (row, col) in size.cells() {
+            if sudoku_grid.get_cell(row, col) == 0 {
+                let used = rows[row] | cols[col] | boxes[size.box_index(row, col)];
+                candidates.set(row, col, size.digits() - used);
+            }
+        }
+
+        // Apply all variant constraints
+        for variant in variants {
+            let start = Instant::now();
+            let possibilities = variant.get_possibilities(sudoku_grid);
+            *times.entry(variant.name()).or_default() += start.elapsed();
+            for ((row, col), values) in possibilities? {
+                if sudoku_grid.get_cell(row, col) == 0 {
+                    candidates.restrict(row, col, DigitSet::from_digits(&values));
+                }
+            }
+        }
+
+        for (row, col) in size.cells() {
+            if sudoku_grid.get_cell(row, col) == 0 && candidates.get(row, col).is_empty() {
+                return Err(VariantContradiction::NoPossibilities {
+                    cell: (row, col),
+                    variant: "Solver",
+                    reason: "No candidates after intersecting rules".to_string(),
+                });
+            }
+        }
+        Ok(candidates)
+    }
+
+    /// Propagates the placement at (`row`, `col`) through the candidate grid.
+    ///
+    /// Only the placed cell's peers and the variants touching a changed cell are re-evaluated.
+    /// Any cell reduced to a single candidate is queued in turn, and its digit is removed from
+    /// its own peers; once no singles are left, the variants whose cells lost candidates
+    /// propagate in turn, until nothing changes.
+    ///
+    /// Each candidate removed is reported to the observer as a `Propagation` elimination.
+    fn update_possibilities(&mut self, row: usize, col: usize) -> Result<(), VariantContradiction> {
+        let size = self.size();
+        self.candidates.set(row, col, DigitSet::EMPTY);
+        let mut queued = CellSet::EMPTY;
+        queued.insert((row, col));
+        let mut worklist = VecDeque::from([(row, col)]);
+        let mut stale = Stale::new(self.variants.len());
+        stale.mark(&self.cell_variants[size.index((row, col))]);
+
+        loop {
+            while let Some((r, c)) = worklist.pop_front() {
+                let digit = match self.sudoku_grid.get_cell(r, c) {
+                    0 => match self.candidates.get(r, c).single_digit() {
+                        Some(single) => single,
+                        // The cell has since lost its last candidate; whatever emptied it
+                        // reported it
+                        None => continue,
+                    },
+                    placed => placed,
+                };
+
+                // Classic row, column and box peers
+                for &(pr, pc) in &self.peers[size.index((r, c))] {
+                    if self.sudoku_grid.get_cell(pr, pc) == 0
+                        && self.candidates.remove(pr, pc, digit)
+                    {
+                        let technique = Technique::Propagation { variant: None };
+                        *self.stats.eliminations.entry(technique).or_insert(0) += 1;
+                        // `emit` would borrow the peers being iterated over
+                        if let Some(observer) = &mut self.observer {
+                            observer.on_event(&SolveEvent::Elimination {
+                                technique,
+                                cell: (pr, pc),
+                                digit,
+                            });
+                        }
+                        stale.mark(&self.cell_variants[size.index((pr, pc))]);
+                        self.enqueue_if_single(pr, pc, &mut queued, &mut worklist)?;
+                    }
+                }
+            }
+
+            let Some(i) = stale.next() else {
+                return Ok(());
+            };
+            let variant = &self.variants[i];
+            let name = variant.name();
+            let start = Instant::now();
+            let eliminations = variant.propagate(&self.sudoku_grid, &self.candidates);
+            *self.stats.propagation_time.entry(name).or_default() += start.elapsed();
+            for ((vr, vc), digit) in eliminations? {
+                if self.candidates.remove(vr, vc, digit) {
+                    let technique = Technique::Propagation {
+                        variant: Some(name),
+                    };
+                    *self.stats.eliminations.entry(technique).or_insert(0) += 1;
+                    self.emit(|| SolveEvent::Elimination {
+                        technique,
+                        cell: (vr, vc),
+                        digit,
+                    });
+                    stale.mark(&self.cell_variants[size.index((vr, vc))]);
+                    self.enqueue_if_single(vr, vc, &mut queued, &mut worklist)?;
+                }
+            }
+        }
+    }
+
+    /// Called after removing candidates from (`row`, `col`): fails if the cell has run out of
+    /// candidates, and queues it for propagation if it is down to one.
+    fn enqueue_if_single(
+        &self,
+        row: usize,
+        col: usize,
+        queued: &mut CellSet,
+        worklist: &mut VecDeque<(usize, usize)>,
+    ) -> Result<(), VariantContradiction> {
+        let remaining = self.candidates.get(row, col);
+        if remaining.is_empty() {
+            return Err(VariantContradiction::NoPossibilities {
+                cell: (row, col),
+                variant: "Solver",
+                reason: "No candidates after intersecting rules".to_string(),
+            });
+        }
+        if remaining.len() == 1 && !queued.contains((row, col)) {
+            queued.insert((row, col));
+            worklist.push_back((row, col));
+        }
+        Ok(())
+    }
+
+    /// Finds the simplest deduction available from the current candidates, applies it, and
+    /// returns it with its justification. Returns `None` once no known technique makes progress.
+    ///
+    /// Placements are propagated like any other placement, so the candidates stay consistent
+    /// with the grid between calls. Deductions turned off in the config aren't used, so forcing
+    /// chains are only tried once enabled with `Deduction::ForcingChains`.
+    pub fn next_step(&mut self) -> Option<Step> {
+        let step = self.find_step()?;
+        self.apply_step(&step);
+        Some(step)
+    }
+
+    /// Solves as far as logic alone allows, taking the simplest step available until none is
+    /// left, without ever guessing. Like `next_step`, only the deductions enabled in the config
+    /// are used.
+    ///
+    /// The grid is left partly solved, and the result says whether it was completed.
+    pub fn solve_logically(&mut self) -> LogicalSolve {
+        let steps: Vec<Step> = std::iter::from_fn(|| self.next_step()).collect();
+        LogicalSolve {
+            grid: self.sudoku_grid.clone(),
+            candidates: self.candidates,
+            steps,
+            completed: self.sudoku_grid.find_empty_cell().is_none() && self.validate_solution(),
+        }
+    }
+
+    fn find_step(&self) -> Option<Step> {
+        if let Some(step) = self.find_naked_single() {
+            return Some(step);
+        }
+        let units = &self.units;
+        if let Some(step) = units.iter().find_map(|unit| self.find_hidden_single(unit)) {
+            return Some(step);
+        }
+        if self.config.is_enabled(Deduction::PointingPairs)
+            && let Some(step) = (0..self.size().side())
+                .flat_map(|b| self.pointing_steps(b))
+                .chain(
+                    units
+                        .iter()
+                        .flat_map(|unit| self.house_pointing_steps(unit)),
+                )
+                .find(Step::makes_progress)
+        {
+            return Some(step);
+        }
+        for size in 2..=4 {
+            if self.config.is_enabled(Deduction::NakedSubsets) {
+                let naked = units
+                    .iter()
+                    .flat_map(|unit| self.naked_subset_steps(unit, size))
+                    .find(Step::makes_progress);
+                if naked.is_some() {
+                    return naked;
+                }
+            }
+            if size < 4 && self.config.is_enabled(Deduction::HiddenPairs) {
+                let hidden = units
+                    .iter()
+                    .flat_map(|unit| self.hidden_subset_steps(unit, size))
+                    .find(Step::makes_progress);
+                if hidden.is_some() {
+                    return hidden;
+                }
+            }
+        }
+        if self.config.is_enabled(Deduction::Fish)
+            && let Some(step) = (2..=4).find_map(|size| self.fish_steps(size).into_iter().next())
+        {
+            return Some(step);
+        }
+        if self.config.is_enabled(Deduction::Wings)
+            && let Some(step) = self
+                .xy_wing_steps()
+                .into_iter()
+                .chain(self.xyz_wing_steps())
+                .chain(self.w_wing_steps())
+                .next()
+        {
+            return Some(step);
+        }
+        if self.config.is_enabled(Deduction::SimpleColoring)
+            && let Some(step) = self.simple_coloring_steps().into_iter().next()
+        {
+            return Some(step);
+        }
+        if self.config.is_enabled(Deduction::Chains)
+            && let Some(step) = self.aic_step()
+        {
+            return Some(step);
+        }
+        self.config
+            .is_enabled(Deduction::ForcingChains)
+            .then(|| self.forcing_step())
+            .flatten()
+    }
+
+    fn apply_step(&mut self, step: &Step) {
+        for &((row, col), digit) in &step.eliminations {
+            if self.candidates.get(row, col).contains(digit) {
+                self.candidates.remove(row, col, digit);
+                *self.stats.eliminations.entry(step.technique).or_insert(0) += 1;
+                self.emit(|| SolveEvent::Elimination {
+                    technique: step.technique,
+                    cell: (row, col),
+                    digit,
+                });
+            }
+        }
+        for &((row, col), digit) in &step.placements {
+            self.sudoku_grid.set_cell(row, col, digit);
+            self.emit(|| SolveEvent::Placement {
+                technique: step.technique,
+                cell: (row, col),
+                digit,
+            });
+            // A contradiction leaves an empty candidate set behind, which the next search or
+            // step will run into
+            if let Err(contradiction) = self.update_possibilities(row, col) {
+                self.emit(|| SolveEvent::Contradiction { contradiction });
+            }
+        }
+    }
+
+    fn find_naked_single(&self) -> Option<Step> {
+        self.size()
+            .cells()
+            .filter(|&(r, c)| self.sudoku_grid.get_cell(r, c) == 0)
+            .find_map(|(r, c)| {
+                let digit = self.candidates.get(r, c).single_digit()?;
+                Some(Step {
+                    technique: Technique::NakedSingle,
+                    unit: None,
+                    digits: DigitSet::EMPTY,
+                    cells: vec![(r, c)],
+                    placements: vec![((r, c), digit)],
+                    eliminations: Vec::new(),
+                    chains: Vec::new(),
+                })
+            })
+    }
+
+    fn find_hidden_single(&self, unit: &Unit) -> Option<Step> {
+        if !unit.is_complete() {
+            return None;
+        }
+        let cells = unit.cells(self.size());
+        (1..=self.size().max_digit()).find_map(|digit| {
+            let positions = self.positions_in_unit(&cells, digit);
+            if positions.count_ones() != 1 {
+                return None;
+            }
+            let cell = cells[positions.trailing_zeros() as usize];
+            Some(Step {
+                technique: Technique::HiddenSingle,
+                unit: Some(unit.clone()),
+                digits: DigitSet::single(digit),
+                cells: vec![cell],
+                placements: vec![(cell, digit)],
+                eliminations: Vec::new(),
+                chains: Vec::new(),
+            })
+        })
+    }
+
+    /// Applies naked pairs/triples/quads logic to all rows, columns, and boxes, and to the
+    /// groups of cells variants keep from repeating a digit.
+    /// This eliminates candidates from other cells in the same unit.
+    pub fn apply_naked_subsets(&mut self) {
+        for i in 0..self.units.len() {
+            for size in 2..=4 {
+                for step in self.naked_subset_steps(&self.units[i], size) {
+                    self.apply_step(&step);
+                }
+            }
+        }
+    }
+
+    /// Finds the naked subsets of `size` cells in a single unit (row, col, box or variant
+    /// group), i.e. `size` cells whose candidates together hold only `size` digits. Those digits
+    /// can then be removed from the rest of the unit.
+    fn naked_subset_steps(&self, unit: &Unit, size: usize) -> Vec<Step> {
+        let cells = unit.cells(self.size());
+        // Only consider cells with 2-4 candidates
+        let cell_poss: Vec<((usize, usize), DigitSet)> = cells
+            .iter()
+            .map(|&(r, c)| ((r, c), self.candidates.get(r, c)))
+            .filter(|(_, poss)| (2..=4).contains(&poss.len()))
+            .collect();
+
+        let mut steps = Vec::new();
+        // Find all combinations of `size` cells
+        for combo in cell_poss.iter().combinations(size) {
+            let all_candidates = combo
+                .iter()
+                .fold(DigitSet::EMPTY, |acc, (_, poss)| acc | *poss);
+            if all_candidates.len() != size {
+                continue;
+            }
+            // Naked subset found: eliminate these candidates from other cells in the unit
+            let subset: Vec<(usize, usize)> = combo.iter().map(|(cell, _)| *cell).collect();
+            let eliminations = cells
+                .iter()
+                .filter(|cell| !subset.contains(cell))
+                .flat_map(|&(r, c)| {
+                    (self.candidates.get(r, c) & all_candidates)
+                        .iter()
+                        .map(move |d| ((r, c), d))
+                })
+                .collect();
+            steps.push(Step {
+                technique: Technique::naked_subset(size),
+                unit: Some(unit.clone()),
+                digits: all_candidates,
+                cells: subset,
+                placements: Vec::new(),
+                eliminations,
+                chains: Vec::new(),
+            });
+        }
+        steps
+    }
+
+    /// Applies the logic of pointing pairs. I.e. if a particular value's possibilities in
+    /// a particular box are all in the same row/column, then that value cannot be present
+    /// in any cells in that row/column outside the box.
+    ///
+    /// Variant houses point too: if a digit's places in a diagonal (say) all see the same cell,
+    /// that cell can't hold the digit.
+    pub fn apply_pointing_pairs(&mut self) {
+        for b in 0..self.size().side() {
+            for step in self.pointing_steps(b) {
+                self.apply_step(&step);
+            }
+        }
+        for i in 0..self.units.len() {
+            for step in self.house_pointing_steps(&self.units[i]) {
+                self.apply_step(&step);
+            }
+        }
+    }
+
+    fn pointing_steps(&self, b: usize) -> Vec<Step> {
+        let size = self.size();
+        let a_box = Unit::Box(b).cells(size);
+        let mut steps = Vec::new();
+        for value in 1..=size.max_digit() {
+            let candidates = a_box
+                .iter()
+                .copied()
+                .filter(|&(r, c)| self.candidates.get(r, c).contains(value))
+                .collect::<Vec<(usize, usize)>>();
+            if candidates.is_empty() {
+                continue;
+            }
+            let technique = if candidates.len() == 3 {
+                Technique::PointingTriple
+            } else {
+                Technique::PointingPair
+            };
+            let all_in_one_row = candidates.iter().all(|&(r, _)| r == candidates[0].0);
+            let all_in_one_col = candidates.iter().all(|&(_, c)| c == candidates[0].1);
+
+            // All candidates in the same row (column): eliminate `value` from the rest of it
+            let mut lines = Vec::new();
+            if all_in_one_row {
+                lines.push(Unit::Row(candidates[0].0));
+            }
+            if all_in_one_col {
+                lines.push(Unit::Column(candidates[0].1));
+            }
+            for line in lines {
+                let eliminations = line
+                    .cells(size)
+                    .into_iter()
+                    .filter(|&(r, c)| {
+                        size.box_index(r, c) != b && self.candidates.get(r, c).contains(value)
+                    })
+                    .map(|cell| (cell, value))
+                    .collect();
+                steps.push(Step {
+                    technique,
+                    unit: Some(Unit::Box(b)),
+                    digits: DigitSet::single(value),
+                    cells: candidates.clone(),
+                    placements: Vec::new(),
+                    eliminations,
+                    chains: Vec::new(),
+                });
+            }
+        }
+        steps
+    }
+
+    /// Finds the digits whose remaining places in a variant house all see some other cells,
+    /// which then can't hold the digit. Rows, columns and boxes are left to `pointing_steps`.
+    fn house_pointing_steps(&self, unit: &Unit) -> Vec<Step> {
+        let Unit::House { cells, .. } = unit else {
+            return Vec::new();
+        };
+        let mut steps = Vec::new();
+        for digit in 1..=self.size().max_digit() {
+            let places: Vec<(usize, usize)> = cells
+                .iter()
+                .copied()
+                .filter(|&(r, c)| self.candidates.get(r, c).contains(digit))
+                .collect();
+            if !(2..=3).contains(&places.len()) {
+                continue;
+            }
+            let in_house: CellSet = cells.iter().copied().collect();
+            let seen_by_all = places
+                .iter()
+                .fold(CellSet::FULL, |acc, &cell| acc & self.seen_by(cell))
+                - in_house;
+            let eliminations: Vec<((usize, usize), u8)> = seen_by_all
+                .iter()
+                .filter(|&(r, c)| self.candidates.get(r, c).contains(digit))
+                .map(|cell| (cell, digit))
+                .collect();
+            if eliminations.is_empty() {
+                continue;
+            }
+            steps.push(Step {
+                technique: if places.len() == 3 {
+                    Technique::PointingTriple
+                } else {
+                    Technique::PointingPair
+                },
+                unit: Some(unit.clone()),
+                digits: DigitSet::single(digit),
+                cells: places,
+                placements: Vec::new(),
+                eliminations,
+                chains: Vec::new(),
+            });
+        }
+        steps
+    }
+
+    /// https://www.sudokuwiki.org/Hidden_Candidates#HP
+    pub fn apply_hidden_pairs(&mut self) {
+        for i in 0..self.units.len() {
+            for size in 2..=3 {
+                for step in self.hidden_subset_steps(&self.units[i], size) {
+                    self.apply_step(&step);
+                }
+            }
+        }
+    }
+
+    /// Finds `subset_size` digits that only appear in the same `subset_size` cells of the unit.
+    /// Every other candidate can then be removed from those cells.
+    fn hidden_subset_steps(&self, unit: &Unit, subset_size: usize) -> Vec<Step> {
+        if !unit.is_complete() {
+            return Vec::new();
+        }
+        let cells = unit.cells(self.size());
+        // Positions (as a bitmask over the unit) of the cells holding each digit, skipping the
+        // digits in too many cells to be part of a subset
+        let digit_positions: Vec<(u8, u16)> = (1..=self.size().max_digit())
+            .map(|d| (d, self.positions_in_unit(&cells, d)))
+            .filter(|&(_, p)| p != 0 && p.count_ones() as usize <= subset_size)
+            .collect();
+        let mut steps = Vec::new();
+        for combo in digit_positions.iter().combinations(subset_size) {
+            let positions: Vec<u16> = combo.iter().map(|&&(_, p)| p).collect();
+            let combo: DigitSet = combo.iter().map(|&&(d, _)| d).collect();
+            let cells_with_combo = positions.iter().fold(0, |acc, p| acc | p);
+            // If exactly subset_size cells, and all contain every digit in combo
+            if cells_with_combo.count_ones() as usize == subset_size
+                && positions.iter().all(|&p| p == cells_with_combo)
+            {
+                let subset: Vec<(usize, usize)> = cells
+                    .iter()
+                    .enumerate()
+                    .filter(|&(i, _)| cells_with_combo & (1 << i) != 0)
+                    .map(|(_, &cell)| cell)
+                    .collect();
+                let eliminations = subset
+                    .iter()
+                    .flat_map(|&(r, c)| {
+                        (self.candidates.get(r, c) - combo)
+                            .iter()
+                            .map(move |d| ((r, c), d))
+                    })
+                    .collect();
+                steps.push(Step {
+                    technique: Technique::hidden_subset(subset_size),
+                    unit: Some(unit.clone()),
+                    digits: combo,
+                    cells: subset,
+                    placements: Vec::new(),
+                    eliminations,
+                    chains: Vec::new(),
+                });
+            }
+        }
+        steps
+    }
+
+    /// Returns a bitmask of the indices of `unit` whose cells still have `digit` as a candidate.
+    fn positions_in_unit(&self, unit: &[(usize, usize)], digit: u8) -> u16 {
+        unit.iter()
+            .enumerate()
+            .filter(|&(_, &(r, c))| self.candidates.get(r, c).contains(digit))
+            .fold(0, |acc, (i, _)| acc | (1 << i))
+    }
+
+    fn size(&self) -> GridSize {
+        self.sudoku_grid.size()
+    }
+
+    /// The cells that can't share a digit with `cell`.
+    fn seen_by(&self, cell: (usize, usize)) -> CellSet {
+        self.sees[self.size().index(cell)]
+    }
+
+    pub fn possibilities_to_string(&self, row: usize, col: usize) -> String {
+        if self.sudoku_grid.get_cell(row, col) != 0 {
+            return format!("No possibilities for ({row}, {col})");
+        }
+        let vals_str = self.candidates.get(row, col).iter().join(", ");
+        format!("({row}, {col}) -> [{vals_str}]")
+    }
+}
+
+/// All cells sharing a row, column or box with (`row`, `col`), excluding the cell itself.
+fn classic_peers(size: GridSize, row: usize, col: usize) -> Vec<(usize, usize)> {
+    let box_index = size.box_index(row, col);
+    size.cells()
+        .filter(|&(r, c)| {
+            (r, c) != (row, col) && (r == row || c == col || size.box_index(r, c) == box_index)
+        })
+        .collect()
+}
+
+/// Result of checking a puzzle for a unique solution.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Uniqueness {
+    NoSolution,
+    Unique(SudokuGrid),
+    /// Two distinct solutions, proving the puzzle is not unique.
+    Multiple(SudokuGrid, SudokuGrid),
+    /// The search gave up before finding a second solution or ruling one out, for the given
+    /// reason, so the puzzle may have any number of solutions.
+    Unknown(SolveOutcome),
+}
+
+/// How far `Solver::solve_logically` got.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogicalSolve {
+    /// The grid with every digit logic could place.
+    pub grid: SudokuGrid,
+    /// The candidates left in the grid's empty cells.
+    pub candidates: CandidateGrid,
+    /// The steps taken, in order.
+    pub steps: Vec<Step>,
+    /// Whether the grid was completed without guessing.
+    pub completed: bool,
+}
+
+/// Iterator over the solutions of a puzzle, created by `Solver::solutions`.
+pub struct Solutions {
+    solver: Solver<'static>,
+    search: Search,
+}
+
+impl Iterator for Solutions {
+    type Item = SudokuGrid;
+
+    fn next(&mut self) -> Option<SudokuGrid> {
+        self.search.restart_budget(&self.solver.config);
+        self.solver
+            .next_solution(&mut self.search)
+            .is_solved()
+            .then(|| self.solver.sudoku_grid.clone())
+    }
+}
+
+/// The grid a solver works on: either the caller's grid, or a private copy when the search
+/// must not tie up the caller's borrow.
+enum GridHandle<'a> {
+    Borrowed(&'a mut SudokuGrid),
+    Owned(SudokuGrid),
+}
+
+impl Deref for GridHandle<'_> {
+    type Target = SudokuGrid;
+
+    fn deref(&self) -> &SudokuGrid {
+        match self {
+            GridHandle::Borrowed(grid) => grid,
+            GridHandle::Owned(grid) => grid,
+        }
+    }
+}
+
+impl DerefMut for GridHandle<'_> {
+    fn deref_mut(&mut self) -> &mut SudokuGrid {
+        match self {
+            GridHandle::Borrowed(grid) => grid,
+            GridHandle::Owned(grid) => grid,
+        }
+    }
+}
+
+/// Backtracking state of a depth-first search, kept outside the call stack so the search can
+/// be paused at a solution and resumed later.
+struct Search {
+    frames: Vec<Frame>,
+    // Whether the last placement succeeded and the search should pick a new cell
+    descend: bool,
+    steps: usize,
+    deadline: Option<Instant>,
+    // Node count and stop flag shared by the threads of a parallel search
+    shared: Option<Arc<Shared>>,
+}
+
+impl Search {
+    fn new(config: &SolverConfig) -> Self {
+        Search {
+            frames: Vec::new(),
+            descend: true,
+            steps: 0,
+            deadline: config.time_limit.map(|limit| Instant::now() + limit),
+            shared: None,
+        }
+    }
+
+    /// Counts a node, returning the total to check against the node limit: this search's, or
+    /// that of every thread in a parallel search.
+    fn count_node(&mut self) -> usize {
+        self.steps += 1;
+        match &self.shared {
+            Some(shared) => shared.nodes.fetch_add(1, Ordering::Relaxed) + 1,
+            None => self.steps,
+        }
+    }
+
+    /// Whether another thread of a parallel search has asked the rest to stop.
+    fn is_stopped(&self) -> bool {
+        self.shared
+            .as_ref()
+            .is_some_and(|shared| shared.stop.load(Ordering::Relaxed))
+    }
+
+    /// Gives the search a fresh node count and time limit, keeping its position.
+    fn restart_budget(&mut self, config: &SolverConfig) {
+        self.steps = 0;
+        self.deadline = config.time_limit.map(|limit| Instant::now() + limit);
+    }
+}
+
+/// A cell the search has branched on.
+struct Frame {
+    row: usize,
+    col: usize,
+    // The cell's candidates in the order the heuristic tries them
+    digits: Vec<u8>,
+    // How many of `digits` have been placed so far
+    tried: usize,
+    // Candidates from before any digit was placed in this cell
+    saved_candidates: CandidateGrid,
+}
+
+enum NextCell {
+    /// An empty cell to branch on, with its candidates in the order to try them.
+    Cell(usize, usize, Vec<u8>),
+    NoEmptyCells,
+    /// An empty cell with no candidates left.
+    DeadEnd(usize, usize),
+}
+
+#[cfg(test)]
+#[allow(clippy::identity_op)]
+mod tests {
+    use super::*;
+
+    /// Candidates of an empty cell, or `None` if the cell is filled.
+    fn candidates_at(solver: &Solver, row: usize, col: usize) -> Option<Vec<u8>> {
+        (solver.sudoku_grid.get_cell(row, col) == 0)
+            .then(|| solver.candidates.get(row, col).to_vec())
+    }
+
+    mod apply_naked_subsets {
+        use super::*;
+
+        #[test]
+        fn naked_pair_in_row() {
+            let mut grid = SudokuGrid::empty();
+            // Set up possibilities: row 0 col 0 and 1 have [1,2], others have [1,2,3]
+            let mut solver = Solver::new(&mut grid).unwrap();
+            solver.candidates.set(0, 0, DigitSet::from_digits(&[1, 2]));
+            solver.candidates.set(0, 1, DigitSet::from_digits(&[1, 2]));
+            // Only test row 0 for simplicity
+            solver.apply_naked_subsets();
+            // The naked pair [1,2] in (0,0) and (0,1) should remove 1,2 from other cells in row 0
+            for c in 0..9 {
+                let poss = &candidates_at(&solver, 0, c).unwrap();
+                if c == 0 || c == 1 {
+                    assert_eq!(
+                        poss,
+                        &vec![1, 2],
+                        "Cell (0, {c}) should only have [1, 2] left, but has: {:?}",
+                        poss
+                    );
+                } else {
+                    assert_eq!(
+                        poss,
+                        &vec![3, 4, 5, 6, 7, 8, 9],
+                        "Cell (0, {c}) should only have [3,4,5,6,7,8,9] left, but has: {:?}",
+                        poss
+                    );
+                }
+            }
+            // Now also check the cells in Box 1 of the grid - top left box.
+            for dr in 0..3 {
+                for dc in 0..3 {
+                    let poss = &candidates_at(&solver, 0 + dr, 0 + dc).unwrap();
+                    if dr == 0 && (dc == 0 || dc == 1) {
+                        assert_eq!(
+                            poss,
+                            &vec![1, 2],
+                            "Cell ({}, {}) should only have [1, 2] left, but has: {:?}",
+                            0 + dr,
+                            0 + dc,
+                            poss
+                        );
+                    } else {
+                        assert_eq!(
+                            poss,
+                            &vec![3, 4, 5, 6, 7, 8, 9],
+                            "Cell ({}, {}) should only have [3,4,5,6,7,8,9] left, but has: {:?}",
+                            0 + dr,
+                            0 + dc,
+                            poss
+                        );
+                    }
+                }
+            }
+            // Check column 0
+            for r in 0..9 {
+                let poss = &candidates_at(&solver, r, 0).unwrap();
+                let expected = match r {
+                    0 => vec![1, 2],
+                    1..=2 => vec![3, 4, 5, 6, 7, 8, 9],
+                    3..9 => vec![1, 2, 3, 4, 5, 6, 7, 8, 9],
+                    _ => panic!("Row out of bounds"),
+                };
+
+                assert_eq!(
+                    poss, &expected,
+                    "Cell ({r}, 0) should only have {:?} left, but has: {:?}",
+                    expected, poss
+                );
+            }
+        }
+
+        #[test]
+        fn naked_triple_in_column() {
+            let mut grid = SudokuGrid::empty();
+            let mut solver = Solver::new(&mut grid).unwrap();
+            // Setup a naked triple in column 0, rows 0, 3, 6 - so only the column should be affected
+            solver
+                .candidates
+                .set(0, 0, DigitSet::from_digits(&[1, 2, 3]));
+            solver
+                .candidates
+                .set(3, 0, DigitSet::from_digits(&[1, 2, 3]));
+            solver
+                .candidates
+                .set(6, 0, DigitSet::from_digits(&[1, 2, 3]));
+
+            solver.apply_naked_subsets();
+
+            // Check that the column is as expected
+            for r in 0..9 {
+                let poss = &candidates_at(&solver, r, 0).unwrap();
+                if r == 0 || r == 3 || r == 6 {
+                    assert_eq!(
+                        poss,
+                        &vec![1, 2, 3],
+                        "Cell ({r}, 0) should only have [1,2,3] left, but has {:?}",
+                        poss
+                    );
+                } else {
+                    assert_eq!(
+                        poss,
+                        &vec![4, 5, 6, 7, 8, 9],
+                        "Cell ({r}, 0) should only have [4,5,6,7,8,9] left, but has {:?}",
+                        poss
+                    );
+                }
+            }
+            // Check row 0 is unaffected
+            for c in 0..9 {
+                let poss = &candidates_at(&solver, 0, c).unwrap();
+                if c == 0 {
+                    assert_eq!(
+                        poss,
+                        &vec![1, 2, 3],
+                        "Cell (0, {c}) should only have [1,2,3] left, but has {:?}",
+                        poss
+                    );
+                } else {
+                    assert_eq!(
+                        poss,
+                        &vec![1, 2, 3, 4, 5, 6, 7, 8, 9],
+                        "Cell (0, {c}) should have all possibilities left, but has {:?}",
+                        poss
+                    );
+                }
+            }
+            // Check box 0 is only affected down column 0
+            for dr in 0..3 {
+                for dc in 0..3 {
+                    let poss = &candidates_at(&solver, 0 + dr, 0 + dc).unwrap();
+                    if dr == 0 && dc == 0 {
+                        assert_eq!(
+                            poss,
+                            &vec![1, 2, 3],
+                            "Cell ({}, {}) should only have [1, 2, 3] left, but has: {:?}",
+                            0 + dr,
+                            0 + dc,
+                            poss
+                        );
+                    } else if dc == 0 {
+                        assert_eq!(
+                            poss,
+                            &vec![4, 5, 6, 7, 8, 9],
+                            "Cell ({}, {}) should only have [4,5,6,7,8,9] left, but has {:?}",
+                            0 + dr,
+                            0 + dc,
+                            poss
+                        );
+                    } else {
+                        assert_eq!(
+                            poss,
+                            &vec![1, 2, 3, 4, 5, 6, 7, 8, 9],
+                            "Cell ({}, {}) should have all possibilities left, but has: {:?}",
+                            0 + dr,
+                            0 + dc,
+                            poss
+                        );
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn naked_triple_with_subset() {
+            let mut grid = SudokuGrid::empty();
+            let mut solver = Solver::new(&mut grid).unwrap();
+            // Cells (0, 0) and (0, 1) have [1,2,3] as possibilities, and cell (0, 2) has [2,3].
+            // This should have the affect of the three cells being a triple
+            solver
+                .candidates
+                .set(0, 0, DigitSet::from_digits(&[1, 2, 3]));
+            solver
+                .candidates
+                .set(0, 1, DigitSet::from_digits(&[1, 2, 3]));
+            solver.candidates.set(0, 2, DigitSet::from_digits(&[1, 2]));
+            solver.apply_naked_subsets();
+
+            // Test that row 0 has a triple
+            for c in 0..9 {
+                let poss = &candidates_at(&solver, 0, c).unwrap();
+                if c == 0 || c == 1 {
+                    assert_eq!(
+                        poss,
+                        &vec![1, 2, 3],
+                        "Cell (0, {c} should only have [1,2,3] left, but has {:?}",
+                        poss
+                    );
+                } else if c == 2 {
+                    assert_eq!(
+                        poss,
+                        &vec![1, 2],
+                        "Cell (0, {c} should only have [1,2] left, but has {:?}",
+                        poss
+                    );
+                } else {
+                    assert_eq!(
+                        poss,
+                        &vec![4, 5, 6, 7, 8, 9],
+                        "Cell (0, {c} should only have [4,5,6,7,8,9] left, but has {:?}",
+                        poss
+                    );
+                }
+            }
+
+            // Test that box 0 has a triple
+            for dr in 0..3 {
+                for dc in 0..3 {
+                    let poss = &candidates_at(&solver, 0 + dr, 0 + dc).unwrap();
+                    if dr == 0 && (dc == 0 || dc == 1) {
+                        assert_eq!(
+                            poss,
+                            &vec![1, 2, 3],
+                            "Cell ({}, {}) should only have [1, 2, 3] left, but has: {:?}",
+                            0 + dr,
+                            0 + dc,
+                            poss
+                        );
+                    } else if dr == 0 && dc == 2 {
+                        assert_eq!(
+                            poss,
+                            &vec![1, 2],
+                            "Cell ({}, {}) should only have [1, 2] left, but has: {:?}",
+                            0 + dr,
+                            0 + dc,
+                            poss
+                        );
+                    } else {
+                        assert_eq!(
+                            poss,
+                            &vec![4, 5, 6, 7, 8, 9],
+                            "Cell ({}, {}) should only have [4,5,6,7,8,9] left, but has: {:?}",
+                            0 + dr,
+                            0 + dc,
+                            poss
+                        );
+                    }
+                }
+            }
+
+            // Check column 0
+            for r in 0..9 {
+                let poss = &candidates_at(&solver, r, 0).unwrap();
+                let expected = match r {
+                    0 => vec![1, 2, 3],
+                    1..=2 => vec![4, 5, 6, 7, 8, 9],
+                    3..9 => vec![1, 2, 3, 4, 5, 6, 7, 8, 9],
+                    _ => panic!("Row out of bounds"),
+                };
+
+                assert_eq!(
+                    poss, &expected,
+                    "Cell ({r}, 0) should only have {:?} left, but has: {:?}",
+                    expected, poss
+                );
+            }
+        }
+
+        #[test]
+        fn naked_triple_less_options() {
+            let mut grid = SudokuGrid::empty();
+            let mut solver = Solver::new(&mut grid).unwrap();
+            // Row 0: Cells 0, 1, 2 have a [1,2,3] triple
+            // Other cells have combinations of the rest of the available values
+            let expected: [Vec<u8>; 9] = [
+                vec![1, 2, 3],
+                vec![1, 2, 3],
+                vec![1, 2, 3],
+                vec![4, 5],
+                vec![5, 6],
+                vec![6, 7],
+                vec![7, 8],
+                vec![8, 9],
+                vec![4, 9],
+            ];
+            for (c, e) in expected.iter().enumerate() {
+                solver.candidates.set(0, c, DigitSet::from_digits(e));
+            }
+            solver.apply_naked_subsets();
+            // Check row 0. Shouldn't have any changes
+            for (c, e) in expected.iter().enumerate() {
+                let poss = &candidates_at(&solver, 0, c).unwrap();
+                assert_eq!(
+                    poss, e,
+                    "Cell (0, {c}) should only have {:?}, but has {:?}",
+                    e, poss
+                );
+            }
+            // Check column 0
+            for r in 0..9 {
+                let poss = &candidates_at(&solver, r, 0).unwrap();
+                if r == 0 {
+                    assert_eq!(
+                        poss,
+                        &vec![1, 2, 3],
+                        "Cell ({r}, 0) should only have [1,2,3], but has {:?}",
+                        poss
+                    );
+                } else if r < 3 {
+                    assert_eq!(
+                        poss,
+                        &vec![4, 5, 6, 7, 8, 9],
+                        "Cell ({r}, 0) should only have [4,5,6,7,8,9], but has {:?}",
+                        poss
+                    );
+                } else {
+                    assert_eq!(
+                        poss,
+                        &vec![1, 2, 3, 4, 5, 6, 7, 8, 9],
+                        "Cell ({r}, 0) should have all possibilities, but has {:?}",
+                        poss
+                    );
+                }
+            }
+            // Check column 3
+            for r in 0..9 {
+                let poss = &candidates_at(&solver, r, 3).unwrap();
+                if r == 0 {
+                    assert_eq!(
+                        poss, &expected[3],
+                        "Cell ({r}, 3) should only have {:?}, but has {:?}",
+                        &expected[3], poss
+                    );
+                } else {
+                    assert_eq!(
+                        poss,
+                        &vec![1, 2, 3, 4, 5, 6, 7, 8, 9],
+                        "Cell ({r}, 3) should have all possibilities, but has {:?}",
+                        poss
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn naked_triple_and_double() {
+            let mut grid = SudokuGrid::empty();
+            let mut solver = Solver::new(&mut grid).unwrap();
+            // Row 0: Cells 0, 1, 2 have a [1,2,3] triple
+            // Cells 5, 6 have a [4,5] double
+            // Cells 3, 6, 7, 8 contain rest of [6,7,8,9]
+            let row_0_possibilities: [Vec<u8>; 9] = [
+                vec![1, 2, 3],
+                vec![1, 2, 3],
+                vec![1, 2, 3],
+                vec![1, 2, 3, 4, 5, 6, 7],
+                vec![4, 5],
+                vec![4, 5],
+                vec![1, 2, 3, 4, 5, 7, 8],
+                vec![1, 2, 3, 4, 5, 8, 9],
+                vec![1, 2, 3, 4, 5, 6, 9],
+            ];
+            let expected: [Vec<u8>; 9] = [
+                vec![1, 2, 3],
+                vec![1, 2, 3],
+                vec![1, 2, 3],
+                vec![6, 7],
+                vec![4, 5],
+                vec![4, 5],
+                vec![7, 8],
+                vec![8, 9],
+                vec![6, 9],
+            ];
+            for (c, p) in row_0_possibilities.iter().enumerate() {
+                solver.candidates.set(0, c, DigitSet::from_digits(p));
+            }
+            solver.apply_naked_subsets();
+            // Check row 0.
+            for (c, e) in expected.iter().enumerate() {
+                let poss = &candidates_at(&solver, 0, c).unwrap();
+                assert_eq!(
+                    poss, e,
+                    "Cell (0, {c}) should only have {:?}, but has {:?}",
+                    e, poss
+                );
+            }
+            // Check column 0
+            for r in 0..9 {
+                let poss = &candidates_at(&solver, r, 0).unwrap();
+                if r == 0 {
+                    assert_eq!(
+                        poss,
+                        &vec![1, 2, 3],
+                        "Cell ({r}, 0) should only have [1,2,3], but has {:?}",
+                        poss
+                    );
+                } else if r < 3 {
+                    assert_eq!(
+                        poss,
+                        &vec![4, 5, 6, 7, 8, 9],
+                        "Cell ({r}, 0) should only have [4,5,6,7,8,9], but has {:?}",
+                        poss
+                    );
+                } else {
+                    assert_eq!(
+                        poss,
+                        &vec![1, 2, 3, 4, 5, 6, 7, 8, 9],
+                        "Cell ({r}, 0) should have all possibilities, but has {:?}",
+                        poss
+                    );
+                }
+            }
+            // Check column 3
+            for r in 0..9 {
+                let poss = &candidates_at(&solver, r, 3).unwrap();
+                if r == 0 {
+                    assert_eq!(
+                        poss,
+                        &vec![6, 7],
+                        "Cell ({r}, 3) should only have [6,7], but has {:?}",
+                        poss
+                    );
+                } else if r < 3 {
+                    assert_eq!(
+                        poss,
+                        &vec![1, 2, 3, 6, 7, 8, 9],
+                        "Cell ({r}, 3) should only have [1,2,3,6,7,8,9], but has {:?}",
+                        poss
+                    );
+                } else {
+                    assert_eq!(
+                        poss,
+                        &vec![1, 2, 3, 4, 5, 6, 7, 8, 9],
+                        "Cell ({r}, 3) should have all possibilities, but has {:?}",
+                        poss
+                    );
+                }
+            }
+        }
+    }
+
+    mod update_possibilities {
+        use super::*;
+        use crate::SudokuVariant;
+        use crate::variant::KropkiDot;
+
+        #[test]
+        fn placement_propagates_through_variants_and_singles() {
+            let mut grid = SudokuGrid::empty();
+            grid.add_variant(SudokuVariant::Kropki(KropkiDot::new(
+                vec![(8, 7), (8, 8)],
+                "black",
+            )));
+            let mut solver = Solver::new(&mut grid).unwrap();
+
+            solver.sudoku_grid.set_cell(8, 8, 3);
+            solver.update_possibilities(8, 8).unwrap();
+
+            // The dot forces 6 next to the 3, and that single is pushed out to its own peers
+            assert_eq!(candidates_at(&solver, 8, 7), Some(vec![6]));
+            assert!(!candidates_at(&solver, 0, 7).unwrap().contains(&6));
+            assert!(!candidates_at(&solver, 8, 0).unwrap().contains(&6));
+            assert!(!candidates_at(&solver, 8, 0).unwrap().contains(&3));
+            // Singles are narrowed but never written into the grid
+            assert_eq!(solver.sudoku_grid.get_cell(8, 7), 0);
+            // Cells outside every affected unit keep all their candidates
+            assert_eq!(candidates_at(&solver, 0, 0).unwrap().len(), 9);
+        }
+
+        #[test]
+        fn variants_react_to_lost_candidates() {
+            let mut grid = SudokuGrid::empty();
+            grid.add_variant(SudokuVariant::Killer(crate::KillerCage::new(
+                vec![(0, 0), (0, 1)],
+                10,
+            )));
+            let mut solver = Solver::new(&mut grid).unwrap();
+            solver
+                .candidates
+                .set(0, 0, DigitSet::from_digits(&[1, 2, 3]));
+
+            solver.sudoku_grid.set_cell(8, 0, 3);
+            solver.update_possibilities(8, 0).unwrap();
+
+            // Nothing in the cage was placed, but r1c1 is now 1 or 2, so r1c2 is 9 or 8
+            assert_eq!(candidates_at(&solver, 0, 1), Some(vec![8, 9]));
+        }
+
+        #[test]
+        fn emptied_peer_is_a_contradiction() {
+            let mut grid = SudokuGrid::empty();
+            let mut solver = Solver::new(&mut grid).unwrap();
+            solver.candidates.set(0, 8, DigitSet::from_digits(&[5]));
+
+            solver.sudoku_grid.set_cell(0, 0, 5);
+            let result = solver.update_possibilities(0, 0);
+
+            assert!(matches!(
+                result,
+                Err(VariantContradiction::NoPossibilities { cell: (0, 8), .. })
+            ));
+        }
+    }
+
+    mod solve {
+        use super::*;
+        use crate::test_support::{contradictory_grid, example};
+
+        #[test]
+        fn solves_with_naked_pairs() {
+            let mut grid = SudokuGrid::empty();
+            let givens = [
+                (0, 0, 5),
+                (0, 1, 1),
+                (0, 2, 7),
+                (0, 3, 6),
+                (0, 7, 3),
+                (0, 8, 4),
+                (1, 0, 2),
+                (1, 1, 8),
+                (1, 2, 9),
+                (1, 5, 4),
+                (2, 0, 3),
+                (2, 1, 4),
+                (2, 2, 6),
+                (2, 3, 2),
+                (2, 5, 5),
+                (2, 7, 9),
+                (3, 0, 6),
+                (3, 2, 2),
+                (3, 7, 1),
+                (4, 1, 3),
+                (4, 2, 8),
+                (4, 5, 6),
+                (4, 7, 4),
+                (4, 8, 7),
+                (6, 1, 9),
+                (6, 7, 7),
+                (6, 8, 8),
+                (7, 0, 7),
+                (7, 2, 3),
+                (7, 3, 4),
+                (7, 6, 5),
+                (7, 7, 6),
+            ];
+            for &(r, c, v) in &givens {
+                grid.set_cell(r, c, v);
+            }
+
+            let mut solver = Solver::new(&mut grid).unwrap();
+            let solved = solver.solve().is_solved();
+            assert!(solved, "Solver should solve the puzzle using naked pairs");
+            // Assert the final grid state matches the expected solution.
+            let solution = [
+                [5, 1, 7, 6, 9, 8, 2, 3, 4],
+                [2, 8, 9, 1, 3, 4, 7, 5, 6],
+                [3, 4, 6, 2, 7, 5, 8, 9, 1],
+                [6, 7, 2, 8, 4, 9, 3, 1, 5],
+                [1, 3, 8, 5, 2, 6, 9, 4, 7],
+                [9, 5, 4, 7, 1, 3, 6, 8, 2],
+                [4, 9, 5, 3, 6, 2, 1, 7, 8],
+                [7, 2, 3, 4, 8, 1, 5, 6, 9],
+                [8, 6, 1, 9, 5, 7, 4, 2, 3],
+            ];
+            for (r, row) in solution.iter().enumerate() {
+                for (c, val) in row.iter().enumerate() {
+                    assert_eq!(
+                        &grid.get_cell(r, c),
+                        val,
+                        "Cell ({r}, {c}) expected: {val}, got: {}",
+                        grid.get_cell(r, c)
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn solves_with_pointing_pairs() {
+            // https://sudoku.com/sudoku-rules/pointing-pairs/
+            let mut grid = SudokuGrid::empty();
+            let givens = [
+                (0, 2, 9),
+                (0, 4, 7),
+                (1, 1, 8),
+                (1, 3, 4),
+                (2, 2, 3),
+                (2, 7, 2),
+                (2, 8, 8),
+                (3, 0, 1),
+                (3, 6, 6),
+                (3, 7, 7),
+                (4, 1, 2),
+                (4, 4, 1),
+                (4, 5, 3),
+                (4, 7, 4),
+                (5, 1, 4),
+                (5, 5, 7),
+                (5, 6, 8),
+                (6, 0, 6),
+                (6, 4, 3),
+                (7, 1, 1),
+                (8, 6, 2),
+                (8, 7, 8),
+                (8, 8, 4),
+            ];
+            for &(r, c, v) in &givens {
+                grid.set_cell(r, c, v);
+            }
+            let mut solver = Solver::new(&mut grid).unwrap();
+            let solved = solver.solve().is_solved();
+            assert!(
+                solved,
+                "Solver should solve the puzzle using pointing pairs"
+            );
+            // Assert the final grid state matches the expected solution.
+            let solution = [
+                [2, 6, 9, 3, 7, 8, 4, 1, 5],
+                [5, 8, 1, 4, 2, 9, 7, 6, 3],
+                [4, 7, 3, 5, 6, 1, 9, 2, 8],
+                [1, 3, 5, 9, 8, 4, 6, 7, 2],
+                [7, 2, 8, 6, 1, 3, 5, 4, 9],
+                [9, 4, 6, 2, 5, 7, 8, 3, 1],
+                [6, 9, 4, 8, 3, 2, 1, 5, 7],
+                [8, 1, 2, 7, 4, 5, 3, 9, 6],
+                [3, 5, 7, 1, 9, 6, 2, 8, 4],
+            ];
+            for (r, row) in solution.iter().enumerate() {
+                for (c, val) in row.iter().enumerate() {
+                    assert_eq!(
+                        &grid.get_cell(r, c),
+                        val,
+                        "Cell ({r}, {c}) expected: {val}, got: {}",
+                        grid.get_cell(r, c)
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn reports_node_limit_apart_from_no_solution() {
+            let mut grid = SudokuGrid::empty();
+            let config = SolverConfig::default().max_nodes(10);
+            let mut solver = Solver::new(&mut grid).unwrap().with_config(config);
+
+            assert_eq!(solver.solve(), SolveOutcome::LimitReached);
+        }
+
+        #[test]
+        fn giving_up_leaves_the_grid_unchanged() {
+            let mut grid = example("sudoku.txt");
+            let givens = grid.get_cells();
+            let config = SolverConfig::default().max_nodes(5);
+            let mut solver = Solver::new(&mut grid).unwrap().with_config(config);
+
+            assert_eq!(solver.solve(), SolveOutcome::LimitReached);
+            assert_eq!(solver.sudoku_grid.get_cells(), givens);
+            // The candidates are back to the givens' too, so solving again starts afresh
+            solver.config = SolverConfig::default();
+            assert_eq!(solver.solve(), SolveOutcome::Solved);
+        }
+
+        #[test]
+        fn reports_timeout() {
+            let mut grid = SudokuGrid::empty();
+            let config = SolverConfig::default().time_limit(std::time::Duration::ZERO);
+            let mut solver = Solver::new(&mut grid).unwrap().with_config(config);
+
+            assert_eq!(solver.solve(), SolveOutcome::TimedOut);
+        }
+
+        #[test]
+        fn reports_no_solution() {
+            let mut grid = contradictory_grid();
+            let mut solver = Solver::new(&mut grid).unwrap();
+
+            assert_eq!(solver.solve(), SolveOutcome::NoSolution);
+        }
+
+        #[test]
+        fn solves_without_deductions_or_most_constrained_branching() {
+            let mut grid = example("sudoku.txt");
+            let config = SolverConfig::default()
+                .enable(Deduction::NakedSubsets, false)
+                .enable(Deduction::PointingPairs, false)
+                .enable(Deduction::HiddenPairs, false)
+                .enable(Deduction::Fish, false)
+                .branching(Branching::FirstEmpty);
+            let mut solver = Solver::new(&mut grid).unwrap().with_config(config);
+
+            assert_eq!(solver.solve(), SolveOutcome::Solved);
+            assert!(grid.find_empty_cell().is_none());
+            assert!(grid.is_board_valid());
+        }
+
+        #[test]
+        fn stops_when_cancelled() {
+            let mut grid = SudokuGrid::empty();
+            let token = CancellationToken::new();
+            token.cancel();
+            let config = SolverConfig::default().cancellation(token);
+            let mut solver = Solver::new(&mut grid).unwrap().with_config(config);
+
+            assert_eq!(solver.solve(), SolveOutcome::Cancelled);
+            assert!(grid.find_empty_cell().is_some());
+        }
+
+        #[test]
+        fn progress_callback_can_cancel() {
+            use std::sync::{Arc, Mutex};
+
+            let mut grid = SudokuGrid::empty();
+            let token = CancellationToken::new();
+            let reports = Arc::new(Mutex::new(Vec::new()));
+            let config = SolverConfig::default().cancellation(token.clone());
+            let recorded = Arc::clone(&reports);
+            let mut solver = Solver::new(&mut grid)
+                .unwrap()
+                .with_config(config)
+                .on_progress(5, move |progress| {
+                    recorded.lock().unwrap().push(progress);
+                    if progress.nodes >= 10 {
+                        token.cancel();
+                    }
+                });
+
+            assert_eq!(solver.solve(), SolveOutcome::Cancelled);
+            let reports = reports.lock().unwrap();
+            assert_eq!(reports.len(), 2);
+            assert_eq!(reports[0].nodes, 5);
+            assert_eq!(reports[1].nodes, 10);
+            assert!(reports[1].depth > reports[0].depth);
+            assert!(reports[1].cells_filled > reports[0].cells_filled);
+        }
+    }
+
+    mod count_solutions {
+        use super::*;
+        use crate::check;
+        use crate::test_support::{contradictory_grid, example};
+
+        #[test]
+        fn classic_example_is_unique() {
+            let mut grid = example("sudoku.txt");
+            let givens = grid.get_cells();
+            let mut solver = Solver::new(&mut grid).unwrap();
+
+            assert_eq!(solver.count_solutions(10), (1, SolveOutcome::NoSolution));
+            assert!(solver.is_unique());
+            let Uniqueness::Unique(solution) = solver.uniqueness() else {
+                panic!("Expected a unique solution");
+            };
+            assert!(solution.is_board_valid());
+            assert_eq!(solver.sudoku_grid.get_cells(), givens);
+        }
+
+        #[test]
+        fn variant_example_is_unique() {
+            let mut grid = example("draft_day.txt");
+            let givens = grid.get_cells();
+            let mut solver = Solver::new(&mut grid).unwrap();
+
+            let Uniqueness::Unique(solution) = solver.uniqueness() else {
+                panic!("Expected a unique solution");
+            };
+            assert!(check(&solution).is_empty());
+            assert_eq!(solver.sudoku_grid.get_cells(), givens);
+        }
+
+        #[test]
+        fn empty_grid_has_two_distinct_witnesses() {
+            let mut grid = SudokuGrid::empty();
+            let mut solver = Solver::new(&mut grid).unwrap();
+
+            assert_eq!(solver.count_solutions(5), (5, SolveOutcome::Solved));
+            let Uniqueness::Multiple(first, second) = solver.uniqueness() else {
+                panic!("Expected multiple solutions");
+            };
+            assert_ne!(first.get_cells(), second.get_cells());
+            for witness in [&first, &second] {
+                assert!(witness.find_empty_cell().is_none());
+                assert!(witness.is_board_valid());
+            }
+            assert_eq!(solver.sudoku_grid.get_cells(), [[0; 9]; 9]);
+        }
+
+        #[test]
+        fn contradictory_givens_have_no_solution() {
+            let mut grid = contradictory_grid();
+            let mut solver = Solver::new(&mut grid).unwrap();
+
+            assert_eq!(solver.count_solutions(2), (0, SolveOutcome::NoSolution));
+            assert_eq!(solver.uniqueness(), Uniqueness::NoSolution);
+            assert!(!solver.is_unique());
+        }
+
+        #[test]
+        fn giving_up_is_inconclusive() {
+            for backend in [Backend::Search, Backend::DancingLinks] {
+                let config = SolverConfig::default().backend(backend);
+                let mut grid = SudokuGrid::empty();
+                let mut solver = Solver::new(&mut grid).unwrap().with_config(config.clone());
+                assert!(solver.solve().is_solved());
+                let first_solution = solver.stats().nodes;
+
+                // Before finding any solution, and right after finding the first
+                for max_nodes in [1, first_solution] {
+                    let mut grid = SudokuGrid::empty();
+                    let config = config.clone().max_nodes(max_nodes);
+                    let mut solver = Solver::new(&mut grid).unwrap().with_config(config);
+
+                    let (count, outcome) = solver.count_solutions(2);
+                    assert_eq!(outcome, SolveOutcome::LimitReached, "{backend:?}");
+                    assert_eq!(count, usize::from(max_nodes > 1), "{backend:?}");
+                    assert_eq!(
+                        solver.uniqueness(),
+                        Uniqueness::Unknown(SolveOutcome::LimitReached),
+                        "{backend:?}"
+                    );
+                    assert!(!solver.is_unique());
+                }
+            }
+        }
+    }
+
+    mod solutions {
+        use super::*;
+
+        /// A solved grid with its top two rows cleared, leaving 8 ways to refill them.
+        fn grid_with_cleared_rows() -> SudokuGrid {
+            let mut grid = SudokuGrid::empty();
+            for row in 2..9 {
+                for col in 0..9 {
+                    grid.set_cell(row, col, ((row * 3 + row / 3 + col) % 9) as u8 + 1);
+                }
+            }
+            grid
+        }
+
+        #[test]
+        fn iterates_every_solution_once() {
+            let mut grid = grid_with_cleared_rows();
+            let mut solver = Solver::new(&mut grid).unwrap();
+
+            let solutions: Vec<SudokuGrid> = solver.solutions().collect();
+            assert_eq!(solutions.len(), 8);
+            assert_eq!(solver.count_solutions(100).0, 8);
+            for (i, solution) in solutions.iter().enumerate() {
+                assert!(solution.find_empty_cell().is_none());
+                assert!(solution.is_board_valid());
+                assert!(
+                    solutions[i + 1..]
+                        .iter()
+                        .all(|other| other.get_cells() != solution.get_cells())
+                );
+            }
+        }
+
+        #[test]
+        fn iterator_does_not_borrow_the_grid() {
+            let mut grid = SudokuGrid::empty();
+            let mut solutions = Solver::new(&mut grid).unwrap().solutions();
+
+            let first = solutions.next().unwrap();
+            grid.set_cell(0, 0, first.get_cell(0, 0) % 9 + 1);
+            let second = solutions.next().unwrap();
+
+            assert_ne!(first.get_cells(), second.get_cells());
+            assert!(second.is_board_valid());
+        }
+    }
+
+    mod next_step {
+        use super::*;
+        use crate::test_support::example;
+
+        #[test]
+        fn explains_naked_pair() {
+            let mut grid = SudokuGrid::empty();
+            let mut solver = Solver::new(&mut grid).unwrap();
+            solver.candidates.set(0, 0, DigitSet::from_digits(&[1, 2]));
+            solver.candidates.set(0, 1, DigitSet::from_digits(&[1, 2]));
+
+            let step = solver.next_step().unwrap();
+
+            assert_eq!(step.technique, Technique::NakedPair);
+            assert_eq!(step.unit, Some(Unit::Row(0)));
+            assert_eq!(step.digits, DigitSet::from_digits(&[1, 2]));
+            assert_eq!(step.cells, vec![(0, 0), (0, 1)]);
+            assert!(step.placements.is_empty());
+            assert_eq!(step.eliminations.len(), 14);
+            assert!(
+                step.to_string()
+                    .starts_with("Naked pair {1,2} in row 1 removes 1 from r1c3, 2 from r1c3")
+            );
+            assert_eq!(
+                candidates_at(&solver, 0, 5),
+                Some(vec![3, 4, 5, 6, 7, 8, 9])
+            );
+        }
+
+        #[test]
+        fn hints_solve_classic_example() {
+            let mut grid = example("sudoku.txt");
+            let mut solver = Solver::new(&mut grid).unwrap();
+
+            let mut steps = Vec::new();
+            while let Some(step) = solver.next_step() {
+                assert!(step.makes_progress(), "Step without progress: {step}");
+                steps.push(step);
+            }
+
+            assert!(solver.sudoku_grid.find_empty_cell().is_none());
+            assert!(solver.sudoku_grid.is_board_valid());
+            assert!(
+                steps
+                    .iter()
+                    .any(|step| step.technique == Technique::NakedSingle)
+            );
+        }
+
+        #[test]
+        fn no_step_on_solved_grid() {
+            let mut grid = SudokuGrid::empty();
+            for row in 0..9 {
+                for col in 0..9 {
+                    grid.set_cell(row, col, ((row * 3 + row / 3 + col) % 9) as u8 + 1);
+                }
+            }
+            let mut solver = Solver::new(&mut grid).unwrap();
+
+            assert_eq!(solver.next_step(), None);
+        }
+    }
+
+    mod solve_logically {
+        use super::*;
+        use crate::test_support::example;
+
+        #[test]
+        fn completes_classic_example() {
+            let mut grid = example("sudoku.txt");
+            let mut solver = Solver::new(&mut grid).unwrap();
+
+            let logical = solver.solve_logically();
+
+            assert!(logical.completed);
+            assert!(!logical.steps.is_empty());
+            assert!(logical.grid.find_empty_cell().is_none());
+            assert!(logical.grid.is_board_valid());
+            assert_eq!(logical.candidates, CandidateGrid::new());
+        }
+
+        #[test]
+        fn stops_without_guessing() {
+            let mut grid = SudokuGrid::empty();
+            grid.set_cell(0, 0, 1);
+            let mut solver = Solver::new(&mut grid).unwrap();
+
+            let logical = solver.solve_logically();
+
+            assert!(!logical.completed);
+            assert!(logical.steps.is_empty());
+            assert_eq!(logical.grid.get_cells(), solver.sudoku_grid.get_cells());
+            assert_eq!(logical.candidates.get(0, 1).len(), 8);
+            assert_eq!(logical.candidates.get(4, 4).len(), 9);
+        }
+
+        #[test]
+        fn skips_disabled_deductions() {
+            let config = SolverConfig::default()
+                .enable(Deduction::Wings, false)
+                .enable(Deduction::SimpleColoring, false)
+                .enable(Deduction::Chains, false);
+            let mut grid = example("pelican.txt");
+            let mut solver = Solver::new(&mut grid).unwrap().with_config(config);
+
+            let logical = solver.solve_logically();
+
+            // Pelican needs two chain steps otherwise, see `rating::tests::rates_examples`
+            assert!(!logical.completed);
+            assert!(
+                logical
+                    .steps
+                    .iter()
+                    .all(|step| step.technique.tier() < Tier::Chains)
+            );
+        }
+    }
+
+    mod variant_units {
+        use super::*;
+        use crate::variant::Diagonal;
+        use crate::{KillerCage, SudokuVariant};
+
+        fn diagonal_solver(grid: &mut SudokuGrid) -> Solver<'_> {
+            grid.add_variant(SudokuVariant::Diagonal(Diagonal::new(false)));
+            Solver::new(grid).unwrap()
+        }
+
+        #[test]
+        fn hidden_single_in_diagonal() {
+            let mut grid = SudokuGrid::empty();
+            let mut solver = diagonal_solver(&mut grid);
+            for i in (0..9).filter(|&i| i != 4) {
+                solver.candidates.remove(i, i, 5);
+            }
+
+            let step = solver.next_step().unwrap();
+
+            assert_eq!(
+                step.to_string(),
+                "Hidden single {5} in Diagonal at r1c1 places 5 in r5c5"
+            );
+        }
+
+        #[test]
+        fn diagonal_points_into_box() {
+            let mut grid = SudokuGrid::empty();
+            let mut solver = diagonal_solver(&mut grid);
+            for i in 2..9 {
+                solver.candidates.remove(i, i, 7);
+            }
+
+            solver.apply_pointing_pairs();
+
+            for cell in [(0, 1), (0, 2), (1, 0), (1, 2), (2, 0), (2, 1)] {
+                assert!(!solver.candidates.get(cell.0, cell.1).contains(7));
+            }
+            assert!(solver.candidates.get(0, 0).contains(7));
+            assert!(solver.candidates.get(0, 3).contains(7));
+        }
+
+        #[test]
+        fn naked_pair_in_killer_cage() {
+            let mut grid = SudokuGrid::empty();
+            grid.add_variant(SudokuVariant::Killer(KillerCage::new(
+                vec![(0, 0), (4, 4), (8, 8)],
+                15,
+            )));
+            let mut solver = Solver::new(&mut grid).unwrap();
+            solver.candidates.set(0, 0, DigitSet::from_digits(&[1, 2]));
+            solver.candidates.set(4, 4, DigitSet::from_digits(&[1, 2]));
+            let cage = solver.units.last().unwrap().clone();
+            assert!(!cage.is_complete());
+
+            let steps = solver.naked_subset_steps(&cage, 2);
+
+            assert_eq!(steps.len(), 1);
+            assert_eq!(steps[0].eliminations, vec![((8, 8), 1), ((8, 8), 2)]);
+            // A cage needn't hold every digit, so it has no hidden singles
+            assert_eq!(solver.find_hidden_single(&cage), None);
+        }
+    }
+
+    mod pointing_pairs {
+        use super::*;
+
+        #[test]
+        fn poining_pair_row_removal() {
+            let mut grid = SudokuGrid::empty();
+            let mut solver = Solver::new(&mut grid).unwrap();
+
+            // Set up: value 5 is a candidate in (0, 0) and (0, 1) (both in row 0, box 0)
+            // Do this by removing 5 as a possibilitiy from all other cells in box 0
+            solver.candidates.remove(0, 2, 5);
+            solver.candidates.remove(1, 0, 5);
+            solver.candidates.remove(1, 1, 5);
+            solver.candidates.remove(1, 2, 5);
+            solver.candidates.remove(2, 0, 5);
+            solver.candidates.remove(2, 1, 5);
+            solver.candidates.remove(2, 2, 5);
+            solver.apply_pointing_pairs();
+            // Check all of row 0 to ensure 5 is only a possibility in cells (0, 0) and (0, 1)
+            for c in 0..9 {
+                let poss = &candidates_at(&solver, 0, c).unwrap();
+                if c == 0 || c == 1 {
+                    assert!(
+                        poss.contains(&5),
+                        "Cell (0, {c}) possibilities should contain a 5"
+                    );
+                } else {
+                    assert!(
+                        !poss.contains(&5),
+                        "Cell (0, {c}) possibilities should NOT contain a 5. Possibilities are: {:?}",
+                        poss
+                    );
+                }
+            }
+            // Check all of row 1, to ensure 5 is not possible in first 3 cells (box 0), but is possible in all other cells
+            for c in 0..9 {
+                let poss = &candidates_at(&solver, 1, c).unwrap();
+                if c < 3 {
+                    assert!(
+                        !poss.contains(&5),
+                        "Cell (1, {c}) possibilities should NOT contain a 5. Possibilities are: {:?}",
+                        poss
+                    );
+                } else {
+                    assert!(
+                        poss.contains(&5),
+                        "Cell (1, {c}) possibilities should contain a 5"
+                    );
+                }
+            }
+            // Check row 3, to ensure 5 is a possibility in all cells
+            for c in 0..9 {
+                let poss = &candidates_at(&solver, 3, c).unwrap();
+                assert!(
+                    poss.contains(&5),
+                    "Cell (1, {c}) possibilities should contain a 5"
+                );
+            }
+        }
+
+        #[test]
+        fn pointing_pair_column_removal() {
+            let mut grid = SudokuGrid::empty();
+            let mut solver = Solver::new(&mut grid).unwrap();
+
+            // Set up: value 5 is a candidate in (0, 6) and (1, 6) (both in col 6, box 2)
+            // Do this by removing 5 as a possibilitiy from all other cells in box 2
+            solver.candidates.remove(0, 7, 5);
+            solver.candidates.remove(0, 8, 5);
+            solver.candidates.remove(1, 7, 5);
+            solver.candidates.remove(1, 8, 5);
+            solver.candidates.remove(2, 6, 5);
+            solver.candidates.remove(2, 7, 5);
+            solver.candidates.remove(2, 8, 5);
+            solver.apply_pointing_pairs();
+            // Check all of col 6 to ensure 5 is only a possibility in cells (0, 6) and (1, 6)
+            for r in 0..9 {
+                let poss = &candidates_at(&solver, r, 6).unwrap();
+                if r == 0 || r == 1 {
+                    assert!(
+                        poss.contains(&5),
+                        "Cell ({r}, 6) possibilities should contain a 5"
+                    );
+                } else {
+                    assert!(
+                        !poss.contains(&5),
+                        "Cell ({r}, 6) possibilities should NOT contain a 5. Possibilities are: {:?}",
+                        poss
+                    );
+                }
+            }
+            // Check all of col 7, to ensure 5 is not possible in first 3 cells (box 2), but is possible in all other cells
+            for r in 0..9 {
+                let poss = &candidates_at(&solver, r, 7).unwrap();
+                if r < 3 {
+                    assert!(
+                        !poss.contains(&5),
+                        "Cell ({r}, 7) possibilities should NOT contain a 5. Possibilities are: {:?}",
+                        poss
+                    );
+                } else {
+                    assert!(
+                        poss.contains(&5),
+                        "Cell ({r}, 7) possibilities should contain a 5"
+                    );
+                }
+            }
+            // Check col 0, to ensure 5 is a possibility in all cells
+            for r in 0..9 {
+                let poss = &candidates_at(&solver, r, 0).unwrap();
+                assert!(
+                    poss.contains(&5),
+                    "Cell ({r}, 7) possibilities should contain a 5"
+                );
+            }
+        }
+    }
+
+    mod hidden_pairs {
+        use super::*;
+
+        #[test]
+        fn hidden_pair_row() {
+            // Example from: https://www.sudokuwiki.org/Hidden_Candidates#HP
+            // The 6's and 7's in boxes 1 and 2, and column 7, mean that 6 and 7
+            // must be in row 1, column 8 and 9.
+            let mut grid = SudokuGrid::empty();
+            // Givens: (row, col, value)
+            let givens = [
+                (1, 0, 9),
+                (1, 2, 4),
+                (1, 3, 6),
+                (1, 5, 7),
+                (2, 1, 7),
+                (2, 2, 6),
+                (2, 3, 8),
+                (2, 5, 4),
+                (2, 6, 1),
+                (3, 0, 3),
+                (3, 2, 9),
+                (3, 3, 7),
+                (3, 5, 1),
+                (3, 7, 8),
+                (4, 2, 8),
+                (4, 6, 3),
+                (5, 1, 5),
+                (5, 3, 3),
+                (5, 5, 8),
+                (5, 6, 7),
+                (5, 8, 2),
+                (6, 2, 7),
+                (6, 3, 5),
+                (6, 5, 2),
+                (6, 6, 6),
+                (6, 7, 1),
+                (7, 3, 4),
+                (7, 5, 3),
+                (7, 6, 2),
+                (7, 8, 8),
+            ];
+            for &(r, c, v) in &givens {
+                grid.set_cell(r, c, v);
+            }
+            // grid.display(false);
+            let mut solver = Solver::new(&mut grid).unwrap();
+            // Check that the possibilities in (0, 7) and (0, 8) have many options before `apply_hidden_pairs` called
+            assert_eq!(
+                candidates_at(&solver, 0, 7).as_ref(),
+                Some(&vec![2, 3, 4, 5, 6, 7, 9]),
+                "Incorrect possibilities for (0, 7) before function call."
+            );
+            assert_eq!(
+                candidates_at(&solver, 0, 8).as_ref(),
+                Some(&vec![3, 4, 5, 6, 7, 9]),
+                "Incorrect possibilities for (0, 8) before function call."
+            );
+            solver.apply_hidden_pairs();
+            // Check that the possibilities of (0, 7) and (0, 8) are now minimised
+            assert_eq!(
+                candidates_at(&solver, 0, 7).as_ref(),
+                Some(&vec![6, 7]),
+                "After fn call: cell (0, 7) possibilities: {:?}, should be [6, 7]",
+                candidates_at(&solver, 0, 7).as_ref()
+            );
+            assert_eq!(
+                candidates_at(&solver, 0, 8).as_ref(),
+                Some(&vec![6, 7]),
+                "After fn call: cell (0, 8) possibilities: {:?}, should be [6, 7]",
+                candidates_at(&solver, 0, 8).as_ref()
+            );
+            // Check that no other cells in the row or box can contain a 6 or a 7
+            // Check row 0, but ignore columns 7 and 8 (already tested above)
+            for col in 0..7 {
+                if let Some(poss) = &candidates_at(&solver, 0, col) {
+                    assert!(!poss.contains(&6), "Cell (0, {col}) should not contain a 6");
+                    assert!(!poss.contains(&7), "Cell (0, {col}) should not contain a 7");
+                }
+            }
+            for dr in 0..3 {
+                for dc in 0..3 {
+                    let row = 0 + dr;
+                    let col = 6 + dc;
+                    if let Some(poss) = &candidates_at(&solver, row, col) {
+                        if row == 0 && (col == 7 || col == 8) {
+                            continue;
+                        }
+                        assert!(
+                            !poss.contains(&6),
+                            "Cell ({row}, {col}) should not contain a 6"
+                        );
+                        assert!(
+                            !poss.contains(&7),
+                            "Cell ({row}, {col}) should not contain a 7"
+                        );
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn hidden_pair_columns() {
+            let mut grid = SudokuGrid::empty();
+            let givens = [
+                (2, 3, 9),
+                (2, 7, 8),
+                (3, 1, 8),
+                (5, 2, 9),
+                (7, 2, 8),
+                (8, 1, 9),
+            ];
+            for &(r, c, v) in &givens {
+                grid.set_cell(r, c, v);
+            }
+            let mut solver = Solver::new(&mut grid).unwrap();
+            for row in 0..2 {
+                if let Some(poss) = &candidates_at(&solver, row, 0) {
+                    assert_eq!(
+                        poss,
+                        &vec![1, 2, 3, 4, 5, 6, 7, 8, 9],
+                        "Cell ({row}, 0) should have all possibilities. Got: {:?}",
+                        poss
+                    );
+                }
+            }
+            solver.apply_hidden_pairs();
+            for row in 0..2 {
+                if let Some(poss) = &candidates_at(&solver, row, 0) {
+                    assert_eq!(
+                        poss,
+                        &vec![8, 9],
+                        "Cell ({row}, 0) should have possibilities [8, 9]. Got: {:?}",
+                        poss
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn hidden_pair_box() {
+            // Expecting to see that [1, 2] can only go in cells (0, 6) and (2, 8) in box 3
+            let mut grid = SudokuGrid::empty();
+            let givens = [
+                (1, 1, 1),
+                (1, 5, 2),
+                (4, 7, 2),
+                (7, 7, 1),
+                (0, 8, 5),
+                (2, 6, 3),
+            ];
+            for &(r, c, v) in &givens {
+                grid.set_cell(r, c, v);
+            }
+            let mut solver = Solver::new(&mut grid).unwrap();
+            assert_eq!(
+                candidates_at(&solver, 0, 6).as_ref(),
+                Some(&vec![1, 2, 4, 6, 7, 8, 9]),
+                "Cell (0, 6) before should be Some(&[1,2,4,6,7,8,9]). Got: {:?}",
+                candidates_at(&solver, 0, 6).as_ref()
+            );
+            assert_eq!(
+                candidates_at(&solver, 2, 8).as_ref(),
+                Some(&vec![1, 2, 4, 6, 7, 8, 9]),
+                "Cell (2, 8) before should be Some(&[1,2,4,6,7,8,9]). Got: {:?}",
+                candidates_at(&solver, 2, 8).as_ref()
+            );
+            solver.apply_hidden_pairs();
+            assert_eq!(
+                candidates_at(&solver, 0, 6).as_ref(),
+                Some(&vec![1, 2]),
+                "Cell (0, 6) before should be Some(&[1,2]). Got: {:?}",
+                candidates_at(&solver, 0, 6).as_ref()
+            );
+            assert_eq!(
+                candidates_at(&solver, 2, 8).as_ref(),
+                Some(&vec![1, 2]),
+                "Cell (2, 8) before should be Some(&[1,2]). Got: {:?}",
+                candidates_at(&solver, 2, 8).as_ref()
+            );
+        }
+
+        #[test]
+        fn no_hidden_pair() {
+            let mut grid = SudokuGrid::empty();
+            let givens = [
+                (1, 1, 1),
+                (1, 2, 2),
+                (2, 3, 1),
+                (2, 4, 2),
+                (4, 6, 1),
+                (5, 7, 2),
+            ];
+            for &(r, c, v) in &givens {
+                grid.set_cell(r, c, v);
+            }
+            let mut solver = Solver::new(&mut grid).unwrap();
+            let expected = [
+                &vec![2, 3, 4, 5, 6, 7, 8, 9],
+                &vec![1, 3, 4, 5, 6, 7, 8, 9],
+                &vec![1, 2, 3, 4, 5, 6, 7, 8, 9],
+            ];
+            for (i, &(r, c)) in [(0, 6), (0, 7), (0, 8)].iter().enumerate() {
+                let poss = candidates_at(&solver, r, c);
+                let poss = poss.as_ref();
+                assert_eq!(
+                    poss,
+                    Some(expected[i]),
+                    "Cell ({r}, {c}) should have Some({:?}), but has {:?}",
+                    expected[i],
+                    poss
+                );
+            }
+            solver.apply_hidden_pairs();
+            // Shouldn't change
+            for (i, &(r, c)) in [(0, 6), (0, 7), (0, 8)].iter().enumerate() {
+                let poss = candidates_at(&solver, r, c);
+                let poss = poss.as_ref();
+                assert_eq!(
+                    poss,
+                    Some(expected[i]),
+                    "Cell ({r}, {c}) should have Some({:?}), but has {:?}",
+                    expected[i],
+                    poss
+                );
+            }
+        }
+
+        #[test]
+        fn overlapping_hidden_and_naked_pairs() {
+            let mut grid = SudokuGrid::empty();
+            let givens = [
+                (1, 7, 1),
+                (1, 8, 2),
+                (3, 5, 1),
+                (4, 5, 2),
+                (6, 4, 1),
+                (7, 4, 2),
+            ];
+            for &(r, c, v) in &givens {
+                grid.set_cell(r, c, v);
+            }
+            let mut solver = Solver::new(&mut grid).unwrap();
+            solver.candidates.set(0, 1, DigitSet::from_digits(&[3, 4]));
+            solver.candidates.set(0, 2, DigitSet::from_digits(&[3, 4]));
+            // Check the possibilities in row 0 from initial setup
+            for col in 0..9 {
+                let poss = candidates_at(&solver, 0, col);
+                let poss = poss.as_ref();
+                if col == 1 || col == 2 {
+                    assert_eq!(
+                        poss,
+                        Some(&vec![3, 4]),
+                        "Before: Cell (0, {col}) should be Some([3,4]), but got: {:?}",
+                        poss
+                    );
+                } else if col == 0 || col == 3 {
+                    assert_eq!(
+                        poss,
+                        Some(&vec![1, 2, 3, 4, 5, 6, 7, 8, 9]),
+                        "Before: Cell (0, {col}) should be any value, but got: {:?}",
+                        poss
+                    );
+                } else {
+                    assert_eq!(
+                        poss,
+                        Some(&vec![3, 4, 5, 6, 7, 8, 9]),
+                        "Before: Cell (0, {col} should be Some([3,4,5,6,7,8,9]), but got: {:?}",
+                        poss
+                    );
+                }
+            }
+            // Apply naked subsets and hidden pairs
+            solver.apply_naked_subsets();
+            solver.apply_hidden_pairs();
+            // Check possibilities for row 0
+            for col in 0..9 {
+                let poss = candidates_at(&solver, 0, col);
+                let poss = poss.as_ref();
+                if col == 1 || col == 2 {
+                    // Naked pair
+                    assert_eq!(
+                        poss,
+                        Some(&vec![3, 4]),
+                        "After: Cell (0, {col}) should be Some([3,4]), but got: {:?}",
+                        poss
+                    );
+                } else if col == 0 || col == 3 {
+                    // Hidden pair
+                    assert_eq!(
+                        poss,
+                        Some(&vec![1, 2]),
+                        "After: Cell (0, {col}) should be Some([1,2]), but got: {:?}",
+                        poss
+                    );
+                } else {
+                    // The rest
+                    assert_eq!(
+                        poss,
+                        Some(&vec![5, 6, 7, 8, 9]),
+                        "After: Cell (0, {col} should be Some([5,6,7,8,9]), but got: {:?}",
+                        poss
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn hidden_pair_solve() {
+            let mut grid = SudokuGrid::empty();
+            // Givens: (row, col, value)
+            let givens = [
+                (1, 0, 9),
+                (1, 2, 4),
+                (1, 3, 6),
+                (1, 5, 7),
+                (2, 1, 7),
+                (2, 2, 6),
+                (2, 3, 8),
+                (2, 5, 4),
+                (2, 6, 1),
+                (3, 0, 3),
+                (3, 2, 9),
+                (3, 3, 7),
+                (3, 5, 1),
+                (3, 7, 8),
+                (4, 2, 8),
+                (4, 6, 3),
+                (5, 1, 5),
+                (5, 3, 3),
+                (5, 5, 8),
+                (5, 6, 7),
+                (5, 8, 2),
+                (6, 2, 7),
+                (6, 3, 5),
+                (6, 5, 2),
+                (6, 6, 6),
+                (6, 7, 1),
+                (7, 3, 4),
+                (7, 5, 3),
+                (7, 6, 2),
+                (7, 8, 8),
+            ];
+            for &(r, c, v) in &givens {
+                grid.set_cell(r, c, v);
+            }
+            // grid.display(false);
+            let mut solver = Solver::new(&mut grid).unwrap();
+            let solution = [
+                [5, 8, 3, 2, 1, 9, 4, 6, 7],
+                [9, 1, 4, 6, 3, 7, 8, 2, 5],
+                [2, 7, 6, 8, 5, 4, 1, 3, 9],
+                [3, 4, 9, 7, 2, 1, 5, 8, 6],
+                [7, 2, 8, 9, 6, 5, 3, 4, 1],
+                [6, 5, 1, 3, 4, 8, 7, 9, 2],
+                [4, 9, 7, 5, 8, 2, 6, 1, 3],
+                [1, 6, 5, 4, 9, 3, 2, 7, 8],
+                [8, 3, 2, 1, 7, 6, 9, 5, 4],
+            ];
+            let solved = solver.solve().is_solved();
+            // grid.display(false);
+            assert!(solved, "Solver should solve the puzzle");
+            for (r, row) in solution.iter().enumerate() {
+                for (c, val) in row.iter().enumerate() {
+                    assert_eq!(
+                        &grid.get_cell(r, c),
+                        val,
+                        "Cell ({r}, {c}) expected: {val}, got, {}",
+                        grid.get_cell(r, c)
+                    );
+                }
+            }
+        }
+    }
+
+    mod grid_sizes {
+        use super::*;
+        use crate::test_support::example;
+        use crate::{Diagonal, KillerCage, SudokuVariant, check};
+
+        /// Solves the grid, checking the solution is the only one and keeps the givens.
+        fn assert_solves_uniquely(puzzle: &SudokuGrid) -> SudokuGrid {
+            let mut grid = puzzle.clone();
+            assert!(Solver::new(&mut grid).unwrap().solve().is_solved());
+            assert!(check(&grid).is_empty(), "{:?}", check(&grid));
+            for (row, col) in puzzle.size().cells() {
+                let given = puzzle.get_cell(row, col);
+                assert!(given == 0 || grid.get_cell(row, col) == given);
+            }
+            assert_eq!(
+                Solver::new(&mut puzzle.clone())
+                    .unwrap()
+                    .count_solutions(2)
+                    .0,
+                1
+            );
+            grid
+        }
+
+        #[test]
+        fn solves_a_4x4_grid() {
+            let mut grid = SudokuGrid::with_size(GridSize::new(2, 2).unwrap());
+            for (row, col, digit) in [(0, 1, 2), (1, 2, 1), (1, 3, 2), (2, 2, 3), (2, 3, 1)] {
+                grid.set_cell(row, col, digit);
+            }
+
+            let solved = assert_solves_uniquely(&grid);
+
+            assert_eq!(
+                solved.get_cells(),
+                [[1, 2, 4, 3], [4, 3, 1, 2], [2, 4, 3, 1], [3, 1, 2, 4]]
+            );
+        }
+
+        #[test]
+        fn solves_6x6_grids_with_either_box_shape() {
+            let wide = example("sudoku_6x6.txt");
+            let tall = example("sudoku_6x6_tall.txt");
+            assert_eq!(wide.size(), GridSize::new(2, 3).unwrap());
+            assert_eq!(tall.size(), GridSize::new(3, 2).unwrap());
+
+            for puzzle in [wide, tall] {
+                let solved = assert_solves_uniquely(&puzzle);
+                assert!(
+                    Solver::new(&mut puzzle.clone())
+                        .unwrap()
+                        .solve_logically()
+                        .completed
+                );
+                assert_eq!(solved.size(), puzzle.size());
+            }
+        }
+
+        #[test]
+        fn solves_a_16x16_grid() {
+            let solved = assert_solves_uniquely(&example("sudoku_16x16.txt"));
+
+            assert_eq!(solved.get_cell(0, 2), 11);
+            assert!((0..16).any(|col| solved.get_cell(15, col) == 16));
+        }
+
+        #[test]
+        fn variants_use_the_grid_digits() {
+            // A pair summing to 11 can only be 5 and 6 on a 6x6 grid, and a cage covering a row
+            // must add up to 21 there
+            let mut grid = SudokuGrid::with_size(GridSize::new(2, 3).unwrap());
+            grid.add_variant(SudokuVariant::Killer(KillerCage::new(
+                vec![(0, 0), (0, 1)],
+                11,
+            )));
+            grid.add_variant(SudokuVariant::Killer(KillerCage::new(
+                (0..6).map(|col| (5, col)).collect(),
+                21,
+            )));
+            grid.add_variant(SudokuVariant::Diagonal(Diagonal::new(true)));
+
+            let mut solver = Solver::new(&mut grid).unwrap();
+            assert_eq!(candidates_at(&solver, 0, 0), Some(vec![5, 6]));
+            assert!(solver.solve().is_solved());
+            assert!(check(&grid).is_empty());
+        }
+    }
+}
@@ -0,0 +1,275 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::time::Instant;
+
+use super::{NextCell, Search, SolveOutcome, Solver};
+use crate::SudokuGrid;
+use crate::candidates::CandidateGrid;
+
+/// Pieces of the search tree to aim for per thread, so threads that finish early can take more.
+const PIECES_PER_THREAD: usize = 4;
+/// The most branching cells expanded before the pieces are handed out.
+const MAX_SPLIT_DEPTH: usize = 3;
+
+/// State shared by the threads of a parallel search.
+#[derive(Default)]
+pub(super) struct Shared {
+    pub(super) nodes: AtomicUsize,
+    pub(super) stop: AtomicBool,
+    solutions: AtomicUsize,
+}
+
+/// A subtree of the search: the grid after the guesses leading to it, and its candidates.
+struct Piece {
+    grid: SudokuGrid,
+    candidates: CandidateGrid,
+}
+
+impl Solver<'_> {
+    /// `solve` over several threads, leaving the first solution any of them finds in the grid.
+    pub(super) fn solve_parallel(&mut self) -> SolveOutcome {
+        let (solutions, outcome) = self.parallel_solutions(1);
+        if let Some(solution) = solutions.into_iter().next() {
+            *self.sudoku_grid = solution;
+        }
+        outcome
+    }
+
+    /// Finds up to `limit` solutions with `config.threads` threads, returning them in the order
+    /// a single thread would, along with `Solved` if it reached `limit`, `NoSolution` if there
+    /// are no more, or else why the search gave up. Once `limit` is reached the threads stop, so
+    /// these needn't be the very first solutions a single thread would find.
+    pub(super) fn parallel_solutions(&mut self, limit: usize) -> (Vec<SudokuGrid>, SolveOutcome) {
+        let shared = Arc::new(Shared::default());
+        let deadline = Search::new(&self.config).deadline;
+        let pieces = self.split(self.config.threads * PIECES_PER_THREAD, &shared);
+        let next = AtomicUsize::new(0);
+        let workers: Vec<Solver<'static>> = (0..self.config.threads.min(pieces.len()))
+            .map(|_| self.fork())
+            .collect();
+
+        let results: Vec<_> = thread::scope(|scope| {
+            let handles: Vec<_> = workers
+                .into_iter()
+                .map(|mut worker| {
+                    let (pieces, next, shared) = (&pieces, &next, &shared);
+                    scope.spawn(move || {
+                        let (found, outcome) = worker.work(pieces, next, shared, deadline, limit);
+                        (found, outcome, worker.stats)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("search thread panicked"))
+                .collect()
+        });
+
+        let mut found = Vec::new();
+        let mut outcome = SolveOutcome::NoSolution;
+        for (solutions, stopped, stats) in results {
+            found.extend(solutions);
+            if outcome == SolveOutcome::NoSolution {
+                outcome = stopped;
+            }
+            self.stats.absorb(stats);
+        }
+        // Reaching the limit settles the search, even if a thread gave up meanwhile
+        if found.len() >= limit {
+            outcome = SolveOutcome::Solved;
+        }
+        // Pieces are numbered in search order, and each thread lists a piece's solutions in order
+        found.sort_by_key(|&(piece, _)| piece);
+        let solutions: Vec<SudokuGrid> = found
+            .into_iter()
+            .map(|(_, grid)| grid)
+            .take(limit)
+            .collect();
+        (solutions, outcome)
+    }
+
+    /// Expands the first few branching cells one level at a time until there are at least
+    /// `target` pieces, listed in the order a single thread would search them.
+    fn split(&mut self, target: usize, shared: &Shared) -> Vec<Piece> {
+        let mut splitter = self.fork();
+        let mut pieces = vec![Piece {
+            grid: self.sudoku_grid.clone(),
+            candidates: self.candidates,
+        }];
+        for _ in 0..MAX_SPLIT_DEPTH {
+            if pieces.len() >= target {
+                break;
+            }
+            let mut expanded = Vec::new();
+            for piece in pieces {
+                *splitter.sudoku_grid = piece.grid.clone();
+                splitter.candidates = piece.candidates;
+                let (row, col, digits) = match splitter.find_branch_cell() {
+                    NextCell::Cell(row, col, digits) => (row, col, digits),
+                    // A full grid is left for a thread to check
+                    NextCell::NoEmptyCells => {
+                        expanded.push(piece);
+                        continue;
+                    }
+                    NextCell::DeadEnd(..) => continue,
+                };
+                splitter.stats.nodes += 1;
+                shared.nodes.fetch_add(1, Ordering::Relaxed);
+                for digit in digits {
+                    *splitter.sudoku_grid = piece.grid.clone();
+                    splitter.candidates = piece.candidates;
+                    if splitter.place(row, col, digit).is_ok() {
+                        expanded.push(Piece {
+                            grid: splitter.sudoku_grid.clone(),
+                            candidates: splitter.candidates,
+                        });
+                    }
+                }
+            }
+            pieces = expanded;
+        }
+        self.stats.absorb(splitter.stats);
+        pieces
+    }
+
+    /// Searches pieces in turn until they run out or the threads are told to stop, returning the
+    /// solutions found with the number of their piece, and why this thread stopped early if it
+    /// did.
+    fn work(
+        &mut self,
+        pieces: &[Piece],
+        next: &AtomicUsize,
+        shared: &Arc<Shared>,
+        deadline: Option<Instant>,
+        limit: usize,
+    ) -> (Vec<(usize, SudokuGrid)>, SolveOutcome) {
+        let mut found = Vec::new();
+        while !shared.stop.load(Ordering::Relaxed) {
+            let index = next.fetch_add(1, Ordering::Relaxed);
+            let Some(piece) = pieces.get(index) else {
+                break;
+            };
+            *self.sudoku_grid = piece.grid.clone();
+            self.candidates = piece.candidates;
+            let mut search = Search {
+                deadline,
+                shared: Some(Arc::clone(shared)),
+                ..Search::new(&self.config)
+            };
+            loop {
+                match self.next_solution(&mut search) {
+                    SolveOutcome::Solved => {
+                        found.push((index, self.sudoku_grid.clone()));
+                        if shared.solutions.fetch_add(1, Ordering::Relaxed) + 1 >= limit {
+                            shared.stop.store(true, Ordering::Relaxed);
+                        }
+                    }
+                    SolveOutcome::NoSolution => break,
+                    // Stopped by another thread
+                    SolveOutcome::Cancelled if !self.config.is_cancelled() => break,
+                    stopped => {
+                        shared.stop.store(true, Ordering::Relaxed);
+                        return (found, stopped);
+                    }
+                }
+            }
+        }
+        (found, SolveOutcome::NoSolution)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{contradictory_grid, example};
+    use crate::{CancellationToken, SolverConfig, Uniqueness};
+
+    fn threads(threads: usize) -> SolverConfig {
+        SolverConfig::default().threads(threads)
+    }
+
+    /// The classic example with its first row cleared, which has several solutions.
+    fn loose() -> SudokuGrid {
+        let mut grid = example("sudoku.txt");
+        for col in 0..9 {
+            grid.set_cell(0, col, 0);
+        }
+        grid
+    }
+
+    fn solutions(grid: &SudokuGrid, config: SolverConfig, limit: usize) -> Vec<SudokuGrid> {
+        let mut grid = grid.clone();
+        let mut solver = Solver::new(&mut grid).unwrap().with_config(config);
+        solver.collect_solutions(limit).0
+    }
+
+    #[test]
+    fn solves_like_a_single_thread() {
+        let mut sequential = example("sudoku.txt");
+        let mut parallel = sequential.clone();
+        Solver::new(&mut sequential).unwrap().solve();
+
+        let mut solver = Solver::new(&mut parallel).unwrap().with_config(threads(4));
+
+        assert_eq!(solver.solve(), SolveOutcome::Solved);
+        assert!(solver.stats().nodes > 0);
+        assert_eq!(parallel, sequential);
+    }
+
+    #[test]
+    fn lists_solutions_in_search_order() {
+        let grid = loose();
+        let sequential = solutions(&grid, SolverConfig::default(), 1000);
+        assert!(sequential.len() > 1);
+
+        assert_eq!(solutions(&grid, threads(3), 1000), sequential);
+    }
+
+    #[test]
+    fn stops_at_the_limit() {
+        assert_eq!(solutions(&loose(), threads(4), 2).len(), 2);
+
+        let mut grid = SudokuGrid::empty();
+        let mut solver = Solver::new(&mut grid).unwrap().with_config(threads(4));
+        assert!(matches!(solver.uniqueness(), Uniqueness::Multiple(..)));
+        assert!(grid.find_empty_cell().is_some());
+    }
+
+    #[test]
+    fn reports_no_solution() {
+        let mut grid = contradictory_grid();
+        let mut solver = Solver::new(&mut grid).unwrap().with_config(threads(4));
+
+        assert_eq!(solver.solve(), SolveOutcome::NoSolution);
+    }
+
+    #[test]
+    fn shares_the_node_limit() {
+        let mut grid = SudokuGrid::empty();
+        let config = threads(4).max_nodes(20);
+        let mut solver = Solver::new(&mut grid).unwrap().with_config(config);
+
+        assert_eq!(
+            solver.count_solutions(usize::MAX),
+            (0, SolveOutcome::LimitReached)
+        );
+        // Each thread may count one node past the limit before stopping
+        assert!(solver.stats().nodes <= 20 + 4);
+        assert_eq!(
+            solver.uniqueness(),
+            Uniqueness::Unknown(SolveOutcome::LimitReached)
+        );
+    }
+
+    #[test]
+    fn honours_cancellation() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let mut grid = SudokuGrid::empty();
+        let config = threads(2).cancellation(token);
+        let mut solver = Solver::new(&mut grid).unwrap().with_config(config);
+
+        assert_eq!(solver.solve(), SolveOutcome::Cancelled);
+    }
+}
@@ -0,0 +1,314 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::Solver;
+use super::step::{FishKind, Technique};
+use crate::variant::VariantContradiction;
+use crate::{Deduction, SolverConfig, SudokuGrid};
+
+/// Rating given to a puzzle that can't be finished without guessing.
+const GUESSING_RATING: f32 = 10.0;
+
+/// How hard a puzzle is to solve by hand, as produced by `rate`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DifficultyReport {
+    /// How many steps of each tier the logical solve used.
+    pub tiers: BTreeMap<Tier, usize>,
+    /// How many times each technique was used, in order of first use.
+    pub techniques: Vec<(Technique, usize)>,
+    /// The rating of the hardest step, in the style of Sudoku Explainer.
+    pub score: f32,
+    pub band: Band,
+    /// Whether the techniques ran out before the grid was filled.
+    pub requires_guessing: bool,
+}
+
+/// Families of techniques, from easiest to hardest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Tier {
+    Singles,
+    Intersections,
+    Subsets,
+    Fish,
+    Chains,
+    /// Assuming a candidate and following what it forces.
+    Forcing,
+    /// Trial and error, needed once no technique applies.
+    Bifurcation,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Band {
+    Easy,
+    Medium,
+    Hard,
+    Fiendish,
+    Extreme,
+}
+
+/// Rates a puzzle by solving it with logic alone, always taking the simplest step available.
+///
+/// Every deduction is enabled, forcing chains included, so only puzzles that defeat them all
+/// count as requiring guessing. The grid itself is left untouched. Fails if the givens contradict
+/// the variants, or logic leaves a cell without candidates, as there is no puzzle to rate then.
+pub fn rate(grid: &SudokuGrid) -> Result<DifficultyReport, VariantContradiction> {
+    let mut grid = grid.clone();
+    let mut tiers = BTreeMap::new();
+    let mut techniques: Vec<(Technique, usize)> = Vec::new();
+    let mut score: f32 = 0.0;
+
+    let config = SolverConfig::default().enable(Deduction::ForcingChains, true);
+    let mut solver = Solver::new(&mut grid)?.with_config(config);
+    let logical = solver.solve_logically();
+    for step in &logical.steps {
+        *tiers.entry(step.technique.tier()).or_insert(0) += 1;
+        match techniques.iter_mut().find(|(t, _)| *t == step.technique) {
+            Some((_, count)) => *count += 1,
+            None => techniques.push((step.technique, 1)),
+        }
+        score = score.max(step.technique.rating());
+    }
+    let grid = &logical.grid;
+    if let Some(cell) = grid.size().cells().find(|&(row, col)| {
+        grid.get_cell(row, col) == 0 && logical.candidates.get(row, col).is_empty()
+    }) {
+        return Err(VariantContradiction::NoPossibilities {
+            cell,
+            variant: "Solver",
+            reason: String::from("No candidates left after solving logically"),
+        });
+    }
+    let requires_guessing = !logical.completed;
+    if requires_guessing {
+        tiers.insert(Tier::Bifurcation, 1);
+        score = GUESSING_RATING;
+    }
+
+    Ok(DifficultyReport {
+        tiers,
+        techniques,
+        score,
+        band: Band::from_score(score),
+        requires_guessing,
+    })
+}
+
+impl Technique {
+    pub fn tier(&self) -> Tier {
+        match self {
+            Technique::NakedSingle | Technique::HiddenSingle | Technique::Propagation { .. } => {
+                Tier::Singles
+            }
+            Technique::PointingPair | Technique::PointingTriple => Tier::Intersections,
+            Technique::NakedPair
+            | Technique::HiddenPair
+            | Technique::NakedTriple
+            | Technique::HiddenTriple
+            | Technique::NakedQuad => Tier::Subsets,
+            Technique::Fish { .. } => Tier::Fish,
+            Technique::XYWing
+            | Technique::XYZWing
+            | Technique::WWing
+            | Technique::SimpleColoring
+            | Technique::Aic => Tier::Chains,
+            Technique::Nishio | Technique::CellForcingChain => Tier::Forcing,
+        }
+    }
+
+    /// The Sudoku Explainer rating of the technique. Techniques Sudoku Explainer doesn't know
+    /// are slotted in next to their closest relative.
+    pub fn rating(&self) -> f32 {
+        match self {
+            // Never a step of its own, only what follows a placement
+            Technique::Propagation { .. } => 1.0,
+            Technique::HiddenSingle => 1.5,
+            Technique::NakedSingle => 2.3,
+            Technique::PointingPair | Technique::PointingTriple => 2.6,
+            Technique::NakedPair => 3.0,
+            Technique::HiddenPair => 3.4,
+            Technique::NakedTriple => 3.6,
+            Technique::HiddenTriple => 4.0,
+            Technique::NakedQuad => 5.0,
+            Technique::Fish { size, kind } => {
+                let base = match size {
+                    2 => 3.2,
+                    3 => 3.8,
+                    _ => 5.2,
+                };
+                match kind {
+                    FishKind::Basic => base,
+                    FishKind::Finned => base + 0.2,
+                    FishKind::Sashimi => base + 0.3,
+                }
+            }
+            Technique::XYWing => 4.2,
+            Technique::XYZWing | Technique::WWing => 4.4,
+            Technique::SimpleColoring => 4.5,
+            Technique::Aic => 7.0,
+            Technique::Nishio => 7.5,
+            Technique::CellForcingChain => 8.3,
+        }
+    }
+}
+
+impl Band {
+    fn from_score(score: f32) -> Band {
+        match score {
+            s if s <= 2.3 => Band::Easy,
+            s if s <= 3.4 => Band::Medium,
+            s if s <= 4.5 => Band::Hard,
+            s if s < GUESSING_RATING => Band::Fiendish,
+            _ => Band::Extreme,
+        }
+    }
+}
+
+impl fmt::Display for Band {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Band::Easy => "Easy",
+            Band::Medium => "Medium",
+            Band::Hard => "Hard",
+            Band::Fiendish => "Fiendish",
+            Band::Extreme => "Extreme",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Summarises the report, e.g. "Hard (4.2): Naked single x40, Hidden single x12, XY-Wing x1".
+impl fmt::Display for DifficultyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:.1})", self.band, self.score)?;
+        let techniques = self
+            .techniques
+            .iter()
+            .map(|(technique, count)| format!("{technique} x{count}"))
+            .collect::<Vec<_>>();
+        if !techniques.is_empty() {
+            write!(f, ": {}", techniques.join(", "))?;
+        }
+        if self.requires_guessing {
+            write!(f, ", requires guessing")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{contradictory_grid, example};
+    use crate::{SudokuVariant, Thermometer};
+
+    #[test]
+    fn classic_example_needs_no_guessing() {
+        let grid = example("sudoku.txt");
+
+        let report = rate(&grid).unwrap();
+
+        assert!(!report.requires_guessing);
+        assert!(report.tiers[&Tier::Singles] > 0);
+        assert!(!report.tiers.contains_key(&Tier::Bifurcation));
+        assert!(report.band < Band::Extreme);
+        // The grid passed in is left alone
+        assert!(grid.find_empty_cell().is_some());
+    }
+
+    #[test]
+    fn empty_grid_requires_guessing() {
+        let report = rate(&SudokuGrid::empty()).unwrap();
+
+        assert!(report.requires_guessing);
+        assert_eq!(report.tiers.get(&Tier::Bifurcation), Some(&1));
+        assert_eq!(report.band, Band::Extreme);
+        assert!(report.to_string().ends_with("requires guessing"));
+    }
+
+    #[test]
+    fn contradiction_is_not_rated() {
+        // Logic places the 9 in one of the last two cells of row 1, leaving the other empty
+        assert!(rate(&contradictory_grid()).is_err());
+
+        // The 9 can't start a thermometer, which the solver sees before any step
+        let mut grid = SudokuGrid::empty();
+        grid.set_cell(0, 0, 9);
+        grid.add_variant(SudokuVariant::Thermometer(Thermometer::new(vec![
+            (0, 0),
+            (1, 0),
+        ])));
+        assert!(Solver::new(&mut grid.clone()).is_err());
+        assert!(rate(&grid).is_err());
+    }
+
+    #[test]
+    fn rates_examples() {
+        let cases = [
+            (
+                "sudoku_hars_20250604.txt",
+                vec![(Tier::Singles, 55)],
+                2.3,
+                Band::Easy,
+            ),
+            (
+                "sudoku.txt",
+                vec![
+                    (Tier::Singles, 47),
+                    (Tier::Intersections, 7),
+                    (Tier::Subsets, 4),
+                ],
+                3.4,
+                Band::Medium,
+            ),
+            (
+                "draft_day.txt",
+                vec![
+                    (Tier::Singles, 81),
+                    (Tier::Intersections, 8),
+                    (Tier::Subsets, 7),
+                ],
+                3.6,
+                Band::Hard,
+            ),
+            (
+                "pelican.txt",
+                vec![
+                    (Tier::Singles, 81),
+                    (Tier::Intersections, 23),
+                    (Tier::Subsets, 7),
+                    (Tier::Fish, 1),
+                    (Tier::Chains, 2),
+                ],
+                7.0,
+                Band::Fiendish,
+            ),
+        ];
+        for (name, tiers, score, band) in cases {
+            let report = rate(&example(name)).unwrap();
+
+            assert_eq!(report.tiers, BTreeMap::from_iter(tiers), "{name}");
+            assert_eq!(report.score, score, "{name}");
+            assert_eq!(report.band, band, "{name}");
+            assert!(!report.requires_guessing, "{name}");
+        }
+    }
+
+    #[test]
+    fn bands() {
+        assert_eq!(
+            Band::from_score(Technique::HiddenSingle.rating()),
+            Band::Easy
+        );
+        assert_eq!(
+            Band::from_score(Technique::NakedPair.rating()),
+            Band::Medium
+        );
+        assert_eq!(Band::from_score(Technique::XYWing.rating()), Band::Hard);
+        assert_eq!(Band::from_score(Technique::Aic.rating()), Band::Fiendish);
+        assert_eq!(
+            Band::from_score(Technique::CellForcingChain.rating()),
+            Band::Fiendish
+        );
+        assert_eq!(Band::from_score(GUESSING_RATING), Band::Extreme);
+    }
+}
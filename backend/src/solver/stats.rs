@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+use super::step::Technique;
+
+/// Numbers describing the last search run by a `Solver`, see `Solver::stats`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SolveStats {
+    /// Search nodes visited, i.e. times the search picked a cell to branch on.
+    pub nodes: usize,
+    /// The most cells branched on at once.
+    pub max_depth: usize,
+    /// Guesses taken back.
+    pub backtracks: usize,
+    /// Candidates removed by each technique.
+    pub eliminations: HashMap<Technique, usize>,
+    /// Time spent in `get_possibilities`, by kind of variant (see `SudokuVariant::name`). The
+    /// variants give their starting candidates once, when the solver is created, so every
+    /// search reports the same time.
+    pub variant_time: HashMap<&'static str, Duration>,
+    /// Time spent propagating candidates through variants (`Variant::propagate`), by kind of
+    /// variant.
+    pub propagation_time: HashMap<&'static str, Duration>,
+    pub wall_time: Duration,
+}
+
+impl SolveStats {
+    /// Adds in the numbers of a search run on another thread. The variant time is only
+    /// counted once, when the solver is created, so it is left as it is.
+    pub(super) fn absorb(&mut self, other: SolveStats) {
+        self.nodes += other.nodes;
+        self.max_depth = self.max_depth.max(other.max_depth);
+        self.backtracks += other.backtracks;
+        for (technique, count) in other.eliminations {
+            *self.eliminations.entry(technique).or_default() += count;
+        }
+        for (name, time) in other.propagation_time {
+            *self.propagation_time.entry(name).or_default() += time;
+        }
+    }
+}
+
+/// Lists the totals, then eliminations, variant time and propagation time sorted from largest
+/// to smallest.
+impl fmt::Display for SolveStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?}, {} nodes, max depth {}, {} backtracks",
+            self.wall_time, self.nodes, self.max_depth, self.backtracks
+        )?;
+        let mut eliminations: Vec<_> = self.eliminations.iter().collect();
+        eliminations.sort_by_key(|&(technique, &count)| (usize::MAX - count, technique.name()));
+        for (technique, count) in eliminations {
+            write!(f, "\n  {technique}: {count} eliminations")?;
+        }
+        let mut variant_time: Vec<_> = self.variant_time.iter().collect();
+        variant_time.sort_by_key(|&(&name, &time)| (Duration::MAX - time, name));
+        for (name, time) in variant_time {
+            write!(f, "\n  {name}: {time:?} in get_possibilities")?;
+        }
+        let mut propagation_time: Vec<_> = self.propagation_time.iter().collect();
+        propagation_time.sort_by_key(|&(&name, &time)| (Duration::MAX - time, name));
+        for (name, time) in propagation_time {
+            write!(f, "\n  {name}: {time:?} propagating")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::example;
+    use crate::{SolveOutcome, Solver, SudokuGrid, SudokuVariant, Thermometer};
+
+    #[test]
+    fn counts_search_and_deductions() {
+        let mut grid = SudokuGrid::empty();
+        grid.add_variant(SudokuVariant::Thermometer(Thermometer::new(vec![
+            (0, 0),
+            (0, 1),
+            (0, 2),
+        ])));
+        let mut solver = Solver::new(&mut grid).unwrap();
+
+        assert_eq!(solver.solve(), SolveOutcome::Solved);
+
+        let stats = solver.stats();
+        assert!(stats.nodes > 0);
+        assert!(stats.max_depth > 0 && stats.max_depth <= stats.nodes);
+        assert!(stats.variant_time.contains_key("Thermometer"));
+        assert!(stats.propagation_time.contains_key("Thermometer"));
+        assert!(stats.wall_time > Duration::ZERO);
+        assert!(stats.to_string().contains("nodes, max depth"));
+        assert!(stats.to_string().contains("in get_possibilities"));
+        assert!(stats.to_string().contains("propagating"));
+    }
+
+    #[test]
+    fn counts_propagation_eliminations() {
+        let mut grid = example("draft_day.txt");
+        let mut solver = Solver::new(&mut grid).unwrap();
+
+        assert_eq!(solver.solve(), SolveOutcome::Solved);
+
+        let eliminations = &solver.stats().eliminations;
+        // Placements rule candidates out through their peers and through the variants
+        for variant in [None, Some("KillerCage"), Some("Thermometer")] {
+            assert!(eliminations[&Technique::Propagation { variant }] > 0);
+        }
+    }
+
+    #[test]
+    fn resets_between_solves() {
+        let mut grid = SudokuGrid::empty();
+        let mut solver = Solver::new(&mut grid).unwrap();
+        solver.solve();
+        let first = solver.stats().nodes;
+
+        solver.solve();
+
+        // The grid is already solved, so the second search is a single node
+        assert!(first > 1);
+        assert_eq!(solver.stats().nodes, 1);
+        assert_eq!(solver.stats().backtracks, 0);
+    }
+}
@@ -0,0 +1,324 @@
+use std::fmt;
+
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::GridSize;
+use crate::candidates::{Candidate, DigitSet};
+
+/// A single logical deduction, as found by `Solver::next_step`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub technique: Technique,
+    /// The unit the pattern lives in, if it is confined to one.
+    pub unit: Option<Unit>,
+    /// The digits forming the pattern, e.g. the three digits of a naked triple.
+    pub digits: DigitSet,
+    /// The cells that justify the deduction.
+    pub cells: Vec<(usize, usize)>,
+    /// Digits placed by this step, as `((row, col), digit)`.
+    pub placements: Vec<((usize, usize), u8)>,
+    /// Candidates removed by this step, as `((row, col), digit)`.
+    pub eliminations: Vec<((usize, usize), u8)>,
+    /// For forcing chains, each assumption followed by the digits it forced in turn.
+    pub chains: Vec<Vec<Candidate>>,
+}
+
+impl Step {
+    /// Whether applying the step changes anything.
+    pub fn makes_progress(&self) -> bool {
+        !self.placements.is_empty() || !self.eliminations.is_empty()
+    }
+}
+
+/// Renders the step as a sentence, e.g. "Naked triple {2,5,7} in column 4 removes 5 from r7c4".
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.technique)?;
+        if !self.digits.is_empty() {
+            write!(f, " {}", self.digits)?;
+        }
+        if let Some(unit) = &self.unit {
+            write!(f, " in {unit}")?;
+        } else if self.placements.is_empty() && !self.cells.is_empty() {
+            // Patterns spanning several units are identified by their cells instead
+            let cells = self.cells.iter().map(|&cell| cell_name(cell)).join(", ");
+            write!(f, " on {cells}")?;
+        }
+        if !self.placements.is_empty() {
+            let placements = self
+                .placements
+                .iter()
+                .map(|&(cell, digit)| format!("{digit} in {}", cell_name(cell)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            write!(f, " places {placements}")?;
+            if !self.eliminations.is_empty() {
+                write!(f, " and")?;
+            }
+        }
+        if !self.eliminations.is_empty() {
+            let eliminations = self
+                .eliminations
+                .iter()
+                .map(|&(cell, digit)| format!("{digit} from {}", cell_name(cell)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            write!(f, " removes {eliminations}")?;
+        }
+        for (i, chain) in self.chains.iter().enumerate() {
+            let links = chain
+                .iter()
+                .map(|&(cell, digit)| format!("{digit} in {}", cell_name(cell)))
+                .join(" -> ");
+            write!(f, "{}{links}", if i == 0 { ": " } else { "; " })?;
+            if self.technique == Technique::Nishio {
+                write!(f, " -> contradiction")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The solving techniques a `Step` can use, roughly in order of difficulty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum Technique {
+    NakedSingle,
+    HiddenSingle,
+    PointingPair,
+    PointingTriple,
+    NakedPair,
+    HiddenPair,
+    NakedTriple,
+    HiddenTriple,
+    NakedQuad,
+    /// A fish of `size` base lines (2 for X-Wing, 3 for Swordfish, 4 for Jellyfish).
+    Fish {
+        size: usize,
+        kind: FishKind,
+    },
+    XYWing,
+    XYZWing,
+    WWing,
+    SimpleColoring,
+    /// Alternating inference chain.
+    Aic,
+    /// Assuming a candidate leads to a contradiction, so it can be removed.
+    Nishio,
+    /// Both candidates of a bivalue cell force the same digit somewhere else.
+    CellForcingChain,
+    /// What the solver removes after every placement, before looking for any step: the placed
+    /// digit from the cells that see it (`variant` is `None`), or the candidates a variant
+    /// rules out given the others. Only reported to a `SolveObserver`, never as a `Step`.
+    Propagation {
+        variant: Option<&'static str>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum FishKind {
+    Basic,
+    /// Base candidates outside the cover lines, all in one box.
+    Finned,
+    /// A finned fish where some base line only keeps one candidate once the fins are removed.
+    Sashimi,
+}
+
+impl Technique {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Technique::NakedSingle => "Naked single",
+            Technique::HiddenSingle => "Hidden single",
+            Technique::PointingPair => "Pointing pair",
+            Technique::PointingTriple => "Pointing triple",
+            Technique::NakedPair => "Naked pair",
+            Technique::HiddenPair => "Hidden pair",
+            Technique::NakedTriple => "Naked triple",
+            Technique::HiddenTriple => "Hidden triple",
+            Technique::NakedQuad => "Naked quad",
+            Technique::Fish { size, kind } => match (kind, size) {
+                (FishKind::Basic, 2) => "X-Wing",
+                (FishKind::Basic, 3) => "Swordfish",
+                (FishKind::Basic, _) => "Jellyfish",
+                (FishKind::Finned, 2) => "Finned X-Wing",
+                (FishKind::Finned, 3) => "Finned Swordfish",
+                (FishKind::Finned, _) => "Finned Jellyfish",
+                (FishKind::Sashimi, 2) => "Sashimi X-Wing",
+                (FishKind::Sashimi, 3) => "Sashimi Swordfish",
+                (FishKind::Sashimi, _) => "Sashimi Jellyfish",
+            },
+            Technique::XYWing => "XY-Wing",
+            Technique::XYZWing => "XYZ-Wing",
+            Technique::WWing => "W-Wing",
+            Technique::SimpleColoring => "Simple coloring",
+            Technique::Aic => "AIC",
+            Technique::Nishio => "Nishio",
+            Technique::CellForcingChain => "Cell forcing chain",
+            Technique::Propagation { variant } => variant.unwrap_or("Propagation"),
+        }
+    }
+
+    /// The naked subset technique for a subset of `size` cells.
+    pub(crate) fn naked_subset(size: usize) -> Technique {
+        match size {
+            2 => Technique::NakedPair,
+            3 => Technique::NakedTriple,
+            _ => Technique::NakedQuad,
+        }
+    }
+
+    /// The hidden subset technique for a subset of `size` digits.
+    pub(crate) fn hidden_subset(size: usize) -> Technique {
+        match size {
+            2 => Technique::HiddenPair,
+            _ => Technique::HiddenTriple,
+        }
+    }
+}
+
+impl fmt::Display for Technique {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A row, column or box of the grid, or a group of cells a variant keeps from repeating a
+/// digit. Indices are 0-based; `Display` shows them 1-based.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Unit {
+    Row(usize),
+    Column(usize),
+    Box(usize),
+    /// Cells holding every digit once because of a variant, e.g. a diagonal.
+    House {
+        variant: &'static str,
+        cells: Vec<(usize, usize)>,
+    },
+    /// Cells that may not repeat a digit because of a variant, e.g. a killer cage, but needn't
+    /// hold every digit.
+    Group {
+        variant: &'static str,
+        cells: Vec<(usize, usize)>,
+    },
+}
+
+impl Unit {
+    /// All rows, columns and boxes of a grid of the given `size`, 27 on a classic grid.
+    pub fn all(size: GridSize) -> Vec<Unit> {
+        let rows = (0..size.side()).map(Unit::Row);
+        let cols = (0..size.side()).map(Unit::Column);
+        let boxes = (0..size.side()).map(Unit::Box);
+        rows.chain(cols).chain(boxes).collect()
+    }
+
+    /// The unit's cells on a grid of the given `size`, in reading order for rows, columns and
+    /// boxes.
+    pub fn cells(&self, size: GridSize) -> Vec<(usize, usize)> {
+        match self {
+            &Unit::Row(r) => (0..size.side()).map(|c| (r, c)).collect(),
+            &Unit::Column(c) => (0..size.side()).map(|r| (r, c)).collect(),
+            &Unit::Box(b) => size.box_cells(b),
+            Unit::House { cells, .. } | Unit::Group { cells, .. } => cells.clone(),
+        }
+    }
+
+    /// Whether every digit must appear in the unit, so a digit's last place in it must hold it.
+    pub fn is_complete(&self) -> bool {
+        !matches!(self, Unit::Group { .. })
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unit::Row(r) => write!(f, "row {}", r + 1),
+            Unit::Column(c) => write!(f, "column {}", c + 1),
+            Unit::Box(b) => write!(f, "box {}", b + 1),
+            Unit::House { variant, cells } | Unit::Group { variant, cells } => {
+                write!(f, "{variant} at {}", cell_name(cells[0]))
+            }
+        }
+    }
+}
+
+/// Formats a cell in the usual 1-based "r7c4" notation.
+pub(crate) fn cell_name((row, col): (usize, usize)) -> String {
+    format!("r{}c{}", row + 1, col + 1)
+}
+
+/// Formats cells as "r1c1, r1c2 and r1c3".
+pub(crate) fn cell_list(cells: &[(usize, usize)]) -> String {
+    let names: Vec<String> = cells.iter().map(|&cell| cell_name(cell)).collect();
+    match names.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} and {last}", rest.join(", ")),
+        _ => names.concat(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_eliminations() {
+        let step = Step {
+            technique: Technique::NakedTriple,
+            unit: Some(Unit::Column(3)),
+            digits: DigitSet::from_digits(&[2, 5, 7]),
+            cells: vec![(0, 3), (2, 3), (4, 3)],
+            placements: Vec::new(),
+            eliminations: vec![((6, 3), 5)],
+            chains: Vec::new(),
+        };
+        assert_eq!(
+            step.to_string(),
+            "Naked triple {2,5,7} in column 4 removes 5 from r7c4"
+        );
+    }
+
+    #[test]
+    fn describes_placements() {
+        let step = Step {
+            technique: Technique::HiddenSingle,
+            unit: Some(Unit::Box(4)),
+            digits: DigitSet::single(6),
+            cells: vec![(4, 5)],
+            placements: vec![((4, 5), 6)],
+            eliminations: Vec::new(),
+            chains: Vec::new(),
+        };
+        assert_eq!(
+            step.to_string(),
+            "Hidden single {6} in box 5 places 6 in r5c6"
+        );
+    }
+
+    #[test]
+    fn describes_multi_unit_patterns_by_cells() {
+        let step = Step {
+            technique: Technique::Fish {
+                size: 2,
+                kind: FishKind::Basic,
+            },
+            unit: None,
+            digits: DigitSet::single(4),
+            cells: vec![(1, 2), (1, 6), (6, 2), (6, 6)],
+            placements: Vec::new(),
+            eliminations: vec![((3, 2), 4)],
+            chains: Vec::new(),
+        };
+        assert_eq!(
+            step.to_string(),
+            "X-Wing {4} on r2c3, r2c7, r7c3, r7c7 removes 4 from r4c3"
+        );
+    }
+
+    #[test]
+    fn unit_cells() {
+        let classic = GridSize::CLASSIC;
+        assert_eq!(Unit::all(classic).len(), 27);
+        assert_eq!(Unit::Box(5).cells(classic)[0], (3, 6));
+        assert_eq!(Unit::Box(5).cells(classic)[8], (5, 8));
+        assert!(Unit::Column(2).cells(classic).iter().all(|&(_, c)| c == 2));
+    }
+}
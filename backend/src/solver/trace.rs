@@ -0,0 +1,258 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+use super::step::{Technique, cell_name};
+use crate::variant::VariantContradiction;
+
+/// Something the solver did, as reported to a `SolveObserver`. Cells are 0-based `(row, col)`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum SolveEvent {
+    /// The search tried `digit` in `cell`, with `depth` cells branched on including this one.
+    Guess {
+        cell: (usize, usize),
+        digit: u8,
+        depth: usize,
+    },
+    /// The search took back the guess of `digit` in `cell`.
+    Backtrack { cell: (usize, usize), digit: u8 },
+    /// `technique` removed `digit` from the candidates of `cell`.
+    Elimination {
+        technique: Technique,
+        cell: (usize, usize),
+        digit: u8,
+    },
+    /// `technique` placed `digit` in `cell`.
+    Placement {
+        technique: Technique,
+        cell: (usize, usize),
+        digit: u8,
+    },
+    /// The grid can't be completed from here.
+    Contradiction { contradiction: VariantContradiction },
+}
+
+impl fmt::Display for SolveEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveEvent::Guess { cell, digit, depth } => {
+                write!(f, "Trying {digit} in {} (depth {depth})", cell_name(*cell))
+            }
+            SolveEvent::Backtrack { cell, digit } => {
+                write!(f, "Backtracking {digit} from {}", cell_name(*cell))
+            }
+            SolveEvent::Elimination {
+                technique,
+                cell,
+                digit,
+            } => write!(f, "{technique} removes {digit} from {}", cell_name(*cell)),
+            SolveEvent::Placement {
+                technique,
+                cell,
+                digit,
+            } => write!(f, "{technique} places {digit} in {}", cell_name(*cell)),
+            SolveEvent::Contradiction { contradiction } => {
+                write!(f, "Contradiction: {contradiction}")
+            }
+        }
+    }
+}
+
+/// Receives the events of a solve, see `Solver::with_observer`.
+pub trait SolveObserver {
+    fn on_event(&mut self, event: &SolveEvent);
+}
+
+impl<F: FnMut(&SolveEvent)> SolveObserver for F {
+    fn on_event(&mut self, event: &SolveEvent) {
+        self(event)
+    }
+}
+
+/// Prints every event on its own line, for debugging.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PrintObserver;
+
+impl SolveObserver for PrintObserver {
+    fn on_event(&mut self, event: &SolveEvent) {
+        println!("{event}");
+    }
+}
+
+/// Keeps every event of a solve so the trace can be saved and replayed.
+///
+/// Clones share the same trace, so keep one clone and hand the other to the solver.
+#[derive(Debug, Clone, Default)]
+pub struct TraceRecorder {
+    events: Arc<Mutex<Vec<SolveEvent>>>,
+}
+
+impl TraceRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn events(&self) -> Vec<SolveEvent> {
+        self.events.lock().unwrap().clone()
+    }
+
+    /// The trace as a JSON array of events.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(&*self.events.lock().unwrap())
+    }
+}
+
+impl SolveObserver for TraceRecorder {
+    fn on_event(&mut self, event: &SolveEvent) {
+        self.events.lock().unwrap().push(event.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::candidates::DigitSet;
+    use crate::test_support::{contradictory_grid, example};
+    use crate::{SolveOutcome, Solver, SudokuGrid};
+
+    #[test]
+    fn records_guesses_contradictions_and_backtracks() {
+        let mut grid = contradictory_grid();
+        let recorder = TraceRecorder::new();
+        let mut solver = Solver::new(&mut grid)
+            .unwrap()
+            .with_observer(recorder.clone());
+
+        assert_eq!(solver.solve(), SolveOutcome::NoSolution);
+
+        let events = recorder.events();
+        assert!(matches!(events[0], SolveEvent::Guess { depth: 1, .. }));
+        assert!(
+            events
+                .iter()
+                .any(|e| matches!(e, SolveEvent::Contradiction { .. }))
+        );
+        assert!(
+            events
+                .iter()
+                .any(|e| matches!(e, SolveEvent::Backtrack { .. }))
+        );
+    }
+
+    #[test]
+    fn records_technique_eliminations() {
+        let mut grid = SudokuGrid::empty();
+        let recorder = TraceRecorder::new();
+        let mut solver = Solver::new(&mut grid)
+            .unwrap()
+            .with_observer(recorder.clone());
+        solver.candidates.set(0, 0, DigitSet::from_digits(&[1, 2]));
+        solver.candidates.set(0, 1, DigitSet::from_digits(&[1, 2]));
+
+        solver.apply_naked_subsets();
+
+        let events = recorder.events();
+        // 1 and 2 go from the other 7 cells of row 1 and the other 6 cells of box 1
+        assert_eq!(events.len(), 26);
+        assert_eq!(
+            events[0],
+            SolveEvent::Elimination {
+                technique: Technique::NakedPair,
+                cell: (0, 2),
+                digit: 1,
+            }
+        );
+        assert_eq!(events[0].to_string(), "Naked pair removes 1 from r1c3");
+    }
+
+    #[test]
+    fn trace_replays_every_candidate_removed() {
+        let mut grid = example("draft_day.txt");
+        let recorder = TraceRecorder::new();
+        let mut solver = Solver::new(&mut grid)
+            .unwrap()
+            .with_observer(recorder.clone());
+        let mut replayed = (solver.sudoku_grid.clone(), solver.candidates);
+
+        assert_eq!(solver.solve(), SolveOutcome::Solved);
+
+        // Each guess saves the state to go back to when it is taken back
+        let mut saved = Vec::new();
+        for event in recorder.events() {
+            let (grid, candidates) = &mut replayed;
+            match event {
+                SolveEvent::Guess { cell, digit, .. } => {
+                    saved.push((grid.clone(), *candidates));
+                    grid.set_cell(cell.0, cell.1, digit);
+                    candidates.set(cell.0, cell.1, DigitSet::EMPTY);
+                }
+                SolveEvent::Placement { cell, digit, .. } => {
+                    grid.set_cell(cell.0, cell.1, digit);
+                    candidates.set(cell.0, cell.1, DigitSet::EMPTY);
+                }
+                SolveEvent::Elimination { cell, digit, .. } => {
+                    assert!(candidates.remove(cell.0, cell.1, digit), "{event}");
+                }
+                SolveEvent::Backtrack { .. } => replayed = saved.pop().unwrap(),
+                SolveEvent::Contradiction { .. } => {}
+            }
+        }
+        assert_eq!(replayed.0, *solver.sudoku_grid);
+        assert_eq!(replayed.1, solver.candidates);
+        let events = recorder.events();
+        for variant in [None, Some("KillerCage"), Some("Thermometer")] {
+            let technique = Technique::Propagation { variant };
+            assert!(
+                events.iter().any(|event| matches!(
+                    event,
+                    SolveEvent::Elimination { technique: t, .. } if *t == technique
+                )),
+                "{technique}"
+            );
+        }
+    }
+
+    #[test]
+    fn serializes_trace_to_json() {
+        let mut grid = contradictory_grid();
+        let recorder = TraceRecorder::new();
+        let mut solver = Solver::new(&mut grid)
+            .unwrap()
+            .with_observer(recorder.clone());
+        solver.solve();
+
+        let json: serde_json::Value = serde_json::from_str(&recorder.to_json().unwrap()).unwrap();
+
+        let events = json.as_array().unwrap();
+        assert_eq!(events.len(), recorder.events().len());
+        assert!(events[0]["Guess"]["cell"].is_array());
+        assert!(
+            events
+                .iter()
+                .any(|e| e["Contradiction"]["contradiction"]["NoPossibilities"].is_object())
+        );
+    }
+
+    #[test]
+    fn closures_are_observers() {
+        let mut guesses = 0;
+        let mut count = |event: &SolveEvent| {
+            if matches!(event, SolveEvent::Guess { .. }) {
+                guesses += 1;
+            }
+        };
+
+        count.on_event(&SolveEvent::Backtrack {
+            cell: (0, 0),
+            digit: 1,
+        });
+        count.on_event(&SolveEvent::Guess {
+            cell: (0, 0),
+            digit: 2,
+            depth: 1,
+        });
+
+        assert_eq!(guesses, 1);
+    }
+}
@@ -0,0 +1,435 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use super::Solver;
+use super::cell_set::CellSet;
+use super::step::{Step, Technique};
+use crate::candidates::DigitSet;
+
+type Cell = (usize, usize);
+
+impl Solver<'_> {
+    /// Finds XY-Wings: a pivot {x,y} seeing two pincers {x,z} and {y,z}. Whichever digit the
+    /// pivot takes, one pincer is z, so z can be removed from every cell seeing both pincers.
+    ///
+    /// https://www.sudokuwiki.org/Y_Wing_Strategy
+    pub(super) fn xy_wing_steps(&self) -> Vec<Step> {
+        let bivalue = self.cells_with_candidate_count(2);
+        let mut steps = Vec::new();
+        for &pivot in &bivalue {
+            let pivot_digits = self.candidates.get(pivot.0, pivot.1);
+            let pincers: Vec<Cell> = bivalue
+                .iter()
+                .copied()
+                .filter(|&cell| self.sees(pivot, cell))
+                .collect();
+            for (a, b) in pincers.iter().copied().tuple_combinations() {
+                let a_digits = self.candidates.get(a.0, a.1);
+                let b_digits = self.candidates.get(b.0, b.1);
+                // The pincers share z, and each shares a different digit with the pivot
+                let z = a_digits & b_digits;
+                if z.len() != 1
+                    || (z & pivot_digits) != DigitSet::EMPTY
+                    || (a_digits | b_digits | pivot_digits).len() != 3
+                {
+                    continue;
+                }
+                let z = z.min().unwrap();
+                self.push_wing_step(&mut steps, Technique::XYWing, &[pivot, a, b], &[a, b], z);
+            }
+        }
+        steps
+    }
+
+    /// Finds XYZ-Wings: a pivot {x,y,z} seeing two pincers {x,z} and {y,z}. One of the three
+    /// cells is z, so z can be removed from every cell seeing all of them.
+    ///
+    /// https://www.sudokuwiki.org/XYZ_Wing
+    pub(super) fn xyz_wing_steps(&self) -> Vec<Step> {
+        let bivalue = self.cells_with_candidate_count(2);
+        let mut steps = Vec::new();
+        for pivot in self.cells_with_candidate_count(3) {
+            let pivot_digits = self.candidates.get(pivot.0, pivot.1);
+            let pincers: Vec<Cell> = bivalue
+                .iter()
+                .copied()
+                .filter(|&cell| {
+                    self.sees(pivot, cell)
+                        && self.candidates.get(cell.0, cell.1).is_subset(pivot_digits)
+                })
+                .collect();
+            for (a, b) in pincers.iter().copied().tuple_combinations() {
+                let z = self.candidates.get(a.0, a.1) & self.candidates.get(b.0, b.1);
+                if z.len() != 1 {
+                    continue;
+                }
+                let z = z.min().unwrap();
+                self.push_wing_step(
+                    &mut steps,
+                    Technique::XYZWing,
+                    &[pivot, a, b],
+                    &[pivot, a, b],
+                    z,
+                );
+            }
+        }
+        steps
+    }
+
+    /// Finds W-Wings: two cells {x,y} that don't see each other, where a strong link on x has
+    /// one end seeing each cell. One of the two cells must then be y, so y can be removed from
+    /// every cell seeing both.
+    ///
+    /// https://www.sudokuwiki.org/W_Wing_Strategy
+    pub(super) fn w_wing_steps(&self) -> Vec<Step> {
+        let bivalue = self.cells_with_candidate_count(2);
+        let mut steps = Vec::new();
+        for (a, b) in bivalue.iter().copied().tuple_combinations() {
+            let digits = self.candidates.get(a.0, a.1);
+            if digits != self.candidates.get(b.0, b.1) || self.sees(a, b) {
+                continue;
+            }
+            for x in digits {
+                let y = (digits - DigitSet::single(x)).min().unwrap();
+                for (c, d) in self.conjugate_pairs(x) {
+                    if [c, d].iter().any(|cell| *cell == a || *cell == b) {
+                        continue;
+                    }
+                    let linked = (self.sees(c, a) && self.sees(d, b))
+                        || (self.sees(c, b) && self.sees(d, a));
+                    if linked {
+                        self.push_wing_step(
+                            &mut steps,
+                            Technique::WWing,
+                            &[a, b, c, d],
+                            &[a, b],
+                            y,
+                        );
+                    }
+                }
+            }
+        }
+        steps
+    }
+
+    /// Colors each chain of conjugate pairs of a digit with two alternating colors, one of which
+    /// must be true. If two cells of the same color see each other, that color is false and the
+    /// digit is removed from all its cells. Otherwise, any cell seeing both colors loses it.
+    ///
+    /// https://www.sudokuwiki.org/Simple_Colouring
+    pub(super) fn simple_coloring_steps(&self) -> Vec<Step> {
+        let mut steps = Vec::new();
+        for digit in 1..=self.size().max_digit() {
+            let mut links: HashMap<Cell, Vec<Cell>> = HashMap::new();
+            for (c, d) in self.conjugate_pairs(digit) {
+                links.entry(c).or_default().push(d);
+                links.entry(d).or_default().push(c);
+            }
+            let mut colored: HashMap<Cell, bool> = HashMap::new();
+            for &start in links.keys().sorted() {
+                if colored.contains_key(&start) {
+                    continue;
+                }
+                let Some(chain) = color_chain(&links, start, &mut colored) else {
+                    // An odd loop: the search will run into the contradiction itself
+                    continue;
+                };
+                if let Some(step) = self.coloring_step(digit, &chain) {
+                    steps.push(step);
+                }
+            }
+        }
+        steps
+    }
+
+    fn coloring_step(&self, digit: u8, chain: &[(Cell, bool)]) -> Option<Step> {
+        let cells: Vec<Cell> = chain.iter().map(|&(cell, _)| cell).collect();
+        let of_color = |color: bool| -> Vec<Cell> {
+            chain
+                .iter()
+                .filter(|&&(_, c)| c == color)
+                .map(|&(cell, _)| cell)
+                .collect()
+        };
+
+        // Color wrap: a color that sees itself is false
+        for color in [true, false] {
+            let same = of_color(color);
+            if same
+                .iter()
+                .tuple_combinations()
+                .any(|(&a, &b)| self.sees(a, b))
+            {
+                let eliminations = same.into_iter().map(|cell| (cell, digit)).collect();
+                return Some(self.coloring(digit, cells, eliminations));
+            }
+        }
+
+        // Color trap: a cell seeing both colors can't hold the digit
+        let on: CellSet = of_color(true).into_iter().collect();
+        let off: CellSet = of_color(false).into_iter().collect();
+        let eliminations: Vec<(Cell, u8)> = self
+            .size()
+            .cells()
+            .filter(|&cell| {
+                let sees = self.seen_by(cell);
+                !(on | off).contains(cell)
+                    && !(sees & on).is_empty()
+                    && !(sees & off).is_empty()
+                    && self.candidates.get(cell.0, cell.1).contains(digit)
+            })
+            .map(|cell| (cell, digit))
+            .collect();
+        (!eliminations.is_empty()).then(|| self.coloring(digit, cells, eliminations))
+    }
+
+    fn coloring(&self, digit: u8, cells: Vec<Cell>, eliminations: Vec<(Cell, u8)>) -> Step {
+        Step {
+            technique: Technique::SimpleColoring,
+            unit: None,
+            digits: DigitSet::single(digit),
+            cells,
+            placements: Vec::new(),
+            eliminations,
+            chains: Vec::new(),
+        }
+    }
+
+    /// Removes `digit` from every cell that sees all of `targets_of`, skipping the pattern's
+    /// own cells, and records the step if anything was removed.
+    fn push_wing_step(
+        &self,
+        steps: &mut Vec<Step>,
+        technique: Technique,
+        pattern: &[Cell],
+        targets_of: &[Cell],
+        digit: u8,
+    ) {
+        let seen_by_all = targets_of
+            .iter()
+            .fold(CellSet::FULL, |acc, &cell| acc & self.seen_by(cell));
+        let eliminations: Vec<(Cell, u8)> = seen_by_all
+            .iter()
+            .filter(|&cell| {
+                !pattern.contains(&cell) && self.candidates.get(cell.0, cell.1).contains(digit)
+            })
+            .map(|cell| (cell, digit))
+            .collect();
+        if eliminations.is_empty() {
+            return;
+        }
+        let digits = pattern.iter().fold(DigitSet::EMPTY, |acc, &(r, c)| {
+            acc | self.candidates.get(r, c)
+        });
+        steps.push(Step {
+            technique,
+            unit: None,
+            digits,
+            cells: pattern.to_vec(),
+            placements: Vec::new(),
+            eliminations,
+            chains: Vec::new(),
+        });
+    }
+
+    /// Whether `a` and `b` can't hold the same digit, through a shared unit or a variant.
+    pub(super) fn sees(&self, a: Cell, b: Cell) -> bool {
+        self.seen_by(a).contains(b)
+    }
+
+    /// Empty cells with exactly `count` candidates, in row-major order.
+    fn cells_with_candidate_count(&self, count: usize) -> Vec<Cell> {
+        self.size()
+            .cells()
+            .filter(|&(r, c)| {
+                self.sudoku_grid.get_cell(r, c) == 0 && self.candidates.get(r, c).len() == count
+            })
+            .collect()
+    }
+
+    /// Pairs of cells that are the only two places for `digit` in some row, column, box or
+    /// variant house.
+    pub(super) fn conjugate_pairs(&self, digit: u8) -> Vec<(Cell, Cell)> {
+        let mut pairs = Vec::new();
+        for unit in self.units.iter().filter(|unit| unit.is_complete()) {
+            let cells: Vec<Cell> = unit
+                .cells(self.size())
+                .into_iter()
+                .filter(|&(r, c)| self.candidates.get(r, c).contains(digit))
+                .collect();
+            if let [c, d] = cells[..]
+                && !pairs.contains(&(c, d))
+            {
+                pairs.push((c, d));
+            }
+        }
+        pairs
+    }
+}
+
+/// Colors the chain of linked cells containing `start`, recording each color in `colored`.
+/// Returns the chain's cells and colors, or `None` if the links can't be two-colored.
+fn color_chain(
+    links: &HashMap<Cell, Vec<Cell>>,
+    start: Cell,
+    colored: &mut HashMap<Cell, bool>,
+) -> Option<Vec<(Cell, bool)>> {
+    let mut chain = vec![(start, true)];
+    colored.insert(start, true);
+    let mut i = 0;
+    let mut consistent = true;
+    while i < chain.len() {
+        let (cell, color) = chain[i];
+        for &next in &links[&cell] {
+            match colored.get(&next) {
+                Some(&existing) => consistent &= existing != color,
+                None => {
+                    colored.insert(next, !color);
+                    chain.push((next, !color));
+                }
+            }
+        }
+        i += 1;
+    }
+    consistent.then_some(chain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Knight, SudokuGrid, SudokuVariant};
+
+    fn set(solver: &mut Solver, cell: Cell, digits: &[u8]) {
+        solver
+            .candidates
+            .set(cell.0, cell.1, DigitSet::from_digits(digits));
+    }
+
+    #[test]
+    fn xy_wing() {
+        let mut grid = SudokuGrid::empty();
+        let mut solver = Solver::new(&mut grid).unwrap();
+        set(&mut solver, (0, 0), &[1, 2]);
+        set(&mut solver, (0, 4), &[1, 3]);
+        set(&mut solver, (4, 0), &[2, 3]);
+
+        let steps = solver.xy_wing_steps();
+
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].cells, vec![(0, 0), (0, 4), (4, 0)]);
+        assert_eq!(steps[0].eliminations, vec![((4, 4), 3)]);
+        assert_eq!(
+            steps[0].to_string(),
+            "XY-Wing {1,2,3} on r1c1, r1c5, r5c1 removes 3 from r5c5"
+        );
+    }
+
+    #[test]
+    fn xy_wing_through_knight_move() {
+        let mut grid = SudokuGrid::empty();
+        let mut solver = Solver::new(&mut grid).unwrap();
+        set(&mut solver, (4, 4), &[1, 2]);
+        set(&mut solver, (2, 5), &[1, 3]);
+        set(&mut solver, (4, 0), &[2, 3]);
+        // The pivot only sees (2, 5) through a knight's move
+        assert!(solver.xy_wing_steps().is_empty());
+
+        let mut grid = SudokuGrid::empty();
+        grid.add_variant(SudokuVariant::Knight(Knight::new()));
+        let mut solver = Solver::new(&mut grid).unwrap();
+        set(&mut solver, (4, 4), &[1, 2]);
+        set(&mut solver, (2, 5), &[1, 3]);
+        set(&mut solver, (4, 0), &[2, 3]);
+
+        let steps = solver.xy_wing_steps();
+
+        assert_eq!(steps.len(), 1);
+        let eliminated: Vec<Cell> = steps[0].eliminations.iter().map(|&(c, _)| c).collect();
+        assert!(eliminated.contains(&(2, 0)));
+        assert!(eliminated.contains(&(4, 5)));
+        // r4c3 is a knight's move from r5c1 but doesn't see r3c6
+        assert!(!eliminated.contains(&(3, 2)));
+    }
+
+    #[test]
+    fn xyz_wing() {
+        let mut grid = SudokuGrid::empty();
+        let mut solver = Solver::new(&mut grid).unwrap();
+        set(&mut solver, (0, 0), &[1, 2, 3]);
+        set(&mut solver, (0, 4), &[1, 3]);
+        set(&mut solver, (1, 1), &[2, 3]);
+
+        let steps = solver.xyz_wing_steps();
+
+        // Only the rest of row 1 inside box 1 sees all three cells
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].eliminations, vec![((0, 1), 3), ((0, 2), 3)]);
+    }
+
+    #[test]
+    fn w_wing() {
+        let mut grid = SudokuGrid::empty();
+        let mut solver = Solver::new(&mut grid).unwrap();
+        set(&mut solver, (0, 0), &[1, 2]);
+        set(&mut solver, (4, 8), &[1, 2]);
+        // 1 can only go in r7c1 or r7c9 in row 7
+        for col in 1..8 {
+            solver.candidates.remove(6, col, 1);
+        }
+
+        let steps = solver.w_wing_steps();
+
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].cells, vec![(0, 0), (4, 8), (6, 0), (6, 8)]);
+        assert_eq!(steps[0].eliminations, vec![((0, 8), 2), ((4, 0), 2)]);
+    }
+
+    #[test]
+    fn simple_coloring_trap() {
+        let mut grid = SudokuGrid::empty();
+        let mut solver = Solver::new(&mut grid).unwrap();
+        // A chain of conjugate pairs on 5: r2c1 - r8c1 - r8c6 - r2c6
+        for i in 0..9 {
+            if i != 1 && i != 7 {
+                solver.candidates.remove(i, 0, 5);
+                solver.candidates.remove(i, 5, 5);
+            }
+            if i != 0 && i != 5 {
+                solver.candidates.remove(7, i, 5);
+            }
+        }
+
+        let steps = solver.simple_coloring_steps();
+
+        assert_eq!(steps.len(), 1);
+        let expected: Vec<(Cell, u8)> =
+            [1, 2, 3, 4, 6, 7, 8].iter().map(|&c| ((1, c), 5)).collect();
+        assert_eq!(steps[0].eliminations, expected);
+    }
+
+    #[test]
+    fn simple_coloring_wrap() {
+        let mut grid = SudokuGrid::empty();
+        let mut solver = Solver::new(&mut grid).unwrap();
+        // r1c1 - r1c5 - r5c5 - r5c2 - r2c2: r1c1 and r2c2 get the same color but share box 1
+        for i in 0..9 {
+            if i != 0 && i != 4 {
+                solver.candidates.remove(0, i, 5);
+                solver.candidates.remove(i, 4, 5);
+            }
+            if i != 1 && i != 4 {
+                solver.candidates.remove(4, i, 5);
+                solver.candidates.remove(i, 1, 5);
+            }
+        }
+
+        let steps = solver.simple_coloring_steps();
+
+        assert!(steps.iter().any(|step| {
+            let mut cells: Vec<Cell> = step.eliminations.iter().map(|&(c, _)| c).collect();
+            cells.sort();
+            cells == vec![(0, 0), (1, 1), (4, 4)]
+        }));
+    }
+}
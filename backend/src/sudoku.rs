@@ -2,7 +2,13 @@ use std::{collections::HashSet, io::Error, path::Path};
 
 use serde::{Deserialize, Serialize};
 
-use crate::{file_parser, variant::*};
+use crate::{
+    GridSize,
+    candidates::{Candidate, CandidateGrid},
+    cnf::Cnf,
+    file_parser,
+    variant::*,
+};
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub enum SudokuVariant {
@@ -21,6 +27,7 @@ pub enum SudokuVariant {
     GermanWhisper(GermanWhisper),
     Shaded(Shaded),
     Nabner(Nabner),
+    CellSum(CellSum),
 }
 
 impl SudokuVariant {
@@ -55,6 +62,7 @@ impl SudokuVariant {
             "nabner" => Nabner::parse(data),
             "king" => King::parse(data),
             "knight" => Knight::parse(data),
+            "sum" => CellSum::parse(data),
             _ => None,
         }
     }
@@ -76,6 +84,7 @@ impl SudokuVariant {
             SudokuVariant::Nabner(n) => n.is_valid(grid, row, col, value),
             SudokuVariant::King(k) => k.is_valid(grid, row, col, value),
             SudokuVariant::Knight(n) => n.is_valid(grid, row, col, value),
+            SudokuVariant::CellSum(sum) => sum.is_valid(grid, row, col, value),
         }
     }
 
@@ -96,26 +105,157 @@ impl SudokuVariant {
             SudokuVariant::Nabner(n) => n.validate_solution(grid),
             SudokuVariant::King(k) => k.validate_solution(grid),
             SudokuVariant::Knight(n) => n.validate_solution(grid),
+            SudokuVariant::CellSum(sum) => sum.validate_solution(grid),
         }
     }
 
-    pub fn constrained_cells(&self) -> Vec<(usize, usize)> {
+    pub fn constrained_cells(&self, size: GridSize) -> Vec<(usize, usize)> {
         match self {
-            SudokuVariant::Diagonal(diag) => diag.constrained_cells(),
-            SudokuVariant::Killer(cage) => cage.constrained_cells(),
-            SudokuVariant::Kropki(dot) => dot.constrained_cells(),
-            SudokuVariant::QuadrupleCircles(circle) => circle.constrained_cells(),
-            SudokuVariant::Renban(ren) => ren.constrained_cells(),
-            SudokuVariant::Thermometer(therm) => therm.constrained_cells(),
-            SudokuVariant::Entropic(ent) => ent.constrained_cells(),
-            SudokuVariant::Arrow(arrow) => arrow.constrained_cells(),
-            SudokuVariant::RegionSum(rs) => rs.constrained_cells(),
-            SudokuVariant::XVDot(xv) => xv.constrained_cells(),
-            SudokuVariant::GermanWhisper(gw) => gw.constrained_cells(),
-            SudokuVariant::Shaded(s) => s.constrained_cells(),
-            SudokuVariant::Nabner(n) => n.constrained_cells(),
-            SudokuVariant::King(k) => k.constrained_cells(),
-            SudokuVariant::Knight(n) => n.constrained_cells(),
+            SudokuVariant::Diagonal(diag) => diag.constrained_cells(size),
+            SudokuVariant::Killer(cage) => cage.constrained_cells(size),
+            SudokuVariant::Kropki(dot) => dot.constrained_cells(size),
+            SudokuVariant::QuadrupleCircles(circle) => circle.constrained_cells(size),
+            SudokuVariant::Renban(ren) => ren.constrained_cells(size),
+            SudokuVariant::Thermometer(therm) => therm.constrained_cells(size),
+            SudokuVariant::Entropic(ent) => ent.constrained_cells(size),
+            SudokuVariant::Arrow(arrow) => arrow.constrained_cells(size),
+            SudokuVariant::RegionSum(rs) => rs.constrained_cells(size),
+            SudokuVariant::XVDot(xv) => xv.constrained_cells(size),
+            SudokuVariant::GermanWhisper(gw) => gw.constrained_cells(size),
+            SudokuVariant::Shaded(s) => s.constrained_cells(size),
+            SudokuVariant::Nabner(n) => n.constrained_cells(size),
+            SudokuVariant::King(k) => k.constrained_cells(size),
+            SudokuVariant::Knight(n) => n.constrained_cells(size),
+            SudokuVariant::CellSum(sum) => sum.constrained_cells(size),
+        }
+    }
+
+    pub fn seen_cells(&self, size: GridSize, row: usize, col: usize) -> Vec<(usize, usize)> {
+        match self {
+            SudokuVariant::Diagonal(diag) => diag.seen_cells(size, row, col),
+            SudokuVariant::Killer(cage) => cage.seen_cells(size, row, col),
+            SudokuVariant::Kropki(dot) => dot.seen_cells(size, row, col),
+            SudokuVariant::QuadrupleCircles(circle) => circle.seen_cells(size, row, col),
+            SudokuVariant::Renban(ren) => ren.seen_cells(size, row, col),
+            SudokuVariant::Thermometer(therm) => therm.seen_cells(size, row, col),
+            SudokuVariant::Entropic(ent) => ent.seen_cells(size, row, col),
+            SudokuVariant::Arrow(arrow) => arrow.seen_cells(size, row, col),
+            SudokuVariant::RegionSum(rs) => rs.seen_cells(size, row, col),
+            SudokuVariant::XVDot(xv) => xv.seen_cells(size, row, col),
+            SudokuVariant::GermanWhisper(gw) => gw.seen_cells(size, row, col),
+            SudokuVariant::Shaded(s) => s.seen_cells(size, row, col),
+            SudokuVariant::Nabner(n) => n.seen_cells(size, row, col),
+            SudokuVariant::King(k) => k.seen_cells(size, row, col),
+            SudokuVariant::Knight(n) => n.seen_cells(size, row, col),
+            SudokuVariant::CellSum(sum) => sum.seen_cells(size, row, col),
+        }
+    }
+
+    /// Groups of cells holding every digit exactly once, on top of the rows, columns and boxes.
+    pub fn houses(&self, size: GridSize) -> Vec<Vec<(usize, usize)>> {
+        match self {
+            SudokuVariant::Diagonal(diag) => diag.houses(size),
+            SudokuVariant::Killer(cage) => cage.houses(size),
+            SudokuVariant::Kropki(dot) => dot.houses(size),
+            SudokuVariant::QuadrupleCircles(circle) => circle.houses(size),
+            SudokuVariant::Renban(ren) => ren.houses(size),
+            SudokuVariant::Thermometer(therm) => therm.houses(size),
+            SudokuVariant::Entropic(ent) => ent.houses(size),
+            SudokuVariant::Arrow(arrow) => arrow.houses(size),
+            SudokuVariant::RegionSum(rs) => rs.houses(size),
+            SudokuVariant::XVDot(xv) => xv.houses(size),
+            SudokuVariant::GermanWhisper(gw) => gw.houses(size),
+            SudokuVariant::Shaded(s) => s.houses(size),
+            SudokuVariant::Nabner(n) => n.houses(size),
+            SudokuVariant::King(k) => k.houses(size),
+            SudokuVariant::Knight(n) => n.houses(size),
+            SudokuVariant::CellSum(sum) => sum.houses(size),
+        }
+    }
+
+    /// Groups of cells whose digits must differ without having to hold every digit.
+    pub fn all_different_groups(&self, size: GridSize) -> Vec<Vec<(usize, usize)>> {
+        match self {
+            SudokuVariant::Diagonal(diag) => diag.all_different_groups(size),
+            SudokuVariant::Killer(cage) => cage.all_different_groups(size),
+            SudokuVariant::Kropki(dot) => dot.all_different_groups(size),
+            SudokuVariant::QuadrupleCircles(circle) => circle.all_different_groups(size),
+            SudokuVariant::Renban(ren) => ren.all_different_groups(size),
+            SudokuVariant::Thermometer(therm) => therm.all_different_groups(size),
+            SudokuVariant::Entropic(ent) => ent.all_different_groups(size),
+            SudokuVariant::Arrow(arrow) => arrow.all_different_groups(size),
+            SudokuVariant::RegionSum(rs) => rs.all_different_groups(size),
+            SudokuVariant::XVDot(xv) => xv.all_different_groups(size),
+            SudokuVariant::GermanWhisper(gw) => gw.all_different_groups(size),
+            SudokuVariant::Shaded(s) => s.all_different_groups(size),
+            SudokuVariant::Nabner(n) => n.all_different_groups(size),
+            SudokuVariant::King(k) => k.all_different_groups(size),
+            SudokuVariant::Knight(n) => n.all_different_groups(size),
+            SudokuVariant::CellSum(sum) => sum.all_different_groups(size),
+        }
+    }
+
+    pub fn weak_links(&self, candidates: &CandidateGrid) -> Vec<(Candidate, Candidate)> {
+        match self {
+            SudokuVariant::Diagonal(diag) => diag.weak_links(candidates),
+            SudokuVariant::Killer(cage) => cage.weak_links(candidates),
+            SudokuVariant::Kropki(dot) => dot.weak_links(candidates),
+            SudokuVariant::QuadrupleCircles(circle) => circle.weak_links(candidates),
+            SudokuVariant::Renban(ren) => ren.weak_links(candidates),
+            SudokuVariant::Thermometer(therm) => therm.weak_links(candidates),
+            SudokuVariant::Entropic(ent) => ent.weak_links(candidates),
+            SudokuVariant::Arrow(arrow) => arrow.weak_links(candidates),
+            SudokuVariant::RegionSum(rs) => rs.weak_links(candidates),
+            SudokuVariant::XVDot(xv) => xv.weak_links(candidates),
+            SudokuVariant::GermanWhisper(gw) => gw.weak_links(candidates),
+            SudokuVariant::Shaded(s) => s.weak_links(candidates),
+            SudokuVariant::Nabner(n) => n.weak_links(candidates),
+            SudokuVariant::King(k) => k.weak_links(candidates),
+            SudokuVariant::Knight(n) => n.weak_links(candidates),
+            SudokuVariant::CellSum(sum) => sum.weak_links(candidates),
+        }
+    }
+
+    pub fn strong_links(&self, candidates: &CandidateGrid) -> Vec<(Candidate, Candidate)> {
+        match self {
+            SudokuVariant::Diagonal(diag) => diag.strong_links(candidates),
+            SudokuVariant::Killer(cage) => cage.strong_links(candidates),
+            SudokuVariant::Kropki(dot) => dot.strong_links(candidates),
+            SudokuVariant::QuadrupleCircles(circle) => circle.strong_links(candidates),
+            SudokuVariant::Renban(ren) => ren.strong_links(candidates),
+            SudokuVariant::Thermometer(therm) => therm.strong_links(candidates),
+            SudokuVariant::Entropic(ent) => ent.strong_links(candidates),
+            SudokuVariant::Arrow(arrow) => arrow.strong_links(candidates),
+            SudokuVariant::RegionSum(rs) => rs.strong_links(candidates),
+            SudokuVariant::XVDot(xv) => xv.strong_links(candidates),
+            SudokuVariant::GermanWhisper(gw) => gw.strong_links(candidates),
+            SudokuVariant::Shaded(s) => s.strong_links(candidates),
+            SudokuVariant::Nabner(n) => n.strong_links(candidates),
+            SudokuVariant::King(k) => k.strong_links(candidates),
+            SudokuVariant::Knight(n) => n.strong_links(candidates),
+            SudokuVariant::CellSum(sum) => sum.strong_links(candidates),
+        }
+    }
+
+    /// The name of the kind of variant, e.g. "KillerCage".
+    pub fn name(&self) -> &'static str {
+        match self {
+            SudokuVariant::Arrow(_) => "Arrow",
+            SudokuVariant::Diagonal(_) => "Diagonal",
+            SudokuVariant::Entropic(_) => "Entropic",
+            SudokuVariant::Killer(_) => "KillerCage",
+            SudokuVariant::King(_) => "King",
+            SudokuVariant::Knight(_) => "Knight",
+            SudokuVariant::CellSum(_) => "CellSum",
+            SudokuVariant::Kropki(_) => "KropkiDot",
+            SudokuVariant::QuadrupleCircles(_) => "QuadrupleCircle",
+            SudokuVariant::RegionSum(_) => "RegionSum",
+            SudokuVariant::Renban(_) => "Renban",
+            SudokuVariant::Thermometer(_) => "Thermometer",
+            SudokuVariant::XVDot(_) => "XVDot",
+            SudokuVariant::GermanWhisper(_) => "GermanWhisper",
+            SudokuVariant::Shaded(_) => "Shaded",
+            SudokuVariant::Nabner(_) => "Nabner",
         }
     }
 
@@ -136,6 +276,77 @@ impl SudokuVariant {
             SudokuVariant::Nabner(n) => n.get_possibilities(grid),
             SudokuVariant::King(k) => k.get_possibilities(grid),
             SudokuVariant::Knight(n) => n.get_possibilities(grid),
+            SudokuVariant::CellSum(sum) => sum.get_possibilities(grid),
+        }
+    }
+
+    /// The candidates the variant rules out given the placed digits and `candidates`.
+    pub fn propagate(
+        &self,
+        grid: &SudokuGrid,
+        candidates: &CandidateGrid,
+    ) -> Result<Eliminations, VariantContradiction> {
+        match self {
+            SudokuVariant::Diagonal(diag) => diag.propagate(grid, candidates),
+            SudokuVariant::Killer(cage) => cage.propagate(grid, candidates),
+            SudokuVariant::Kropki(dot) => dot.propagate(grid, candidates),
+            SudokuVariant::QuadrupleCircles(circle) => circle.propagate(grid, candidates),
+            SudokuVariant::Renban(ren) => ren.propagate(grid, candidates),
+            SudokuVariant::Thermometer(therm) => therm.propagate(grid, candidates),
+            SudokuVariant::Entropic(ent) => ent.propagate(grid, candidates),
+            SudokuVariant::Arrow(arrow) => arrow.propagate(grid, candidates),
+            SudokuVariant::RegionSum(rs) => rs.propagate(grid, candidates),
+            SudokuVariant::XVDot(xv) => xv.propagate(grid, candidates),
+            SudokuVariant::GermanWhisper(gw) => gw.propagate(grid, candidates),
+            SudokuVariant::Shaded(s) => s.propagate(grid, candidates),
+            SudokuVariant::Nabner(n) => n.propagate(grid, candidates),
+            SudokuVariant::King(k) => k.propagate(grid, candidates),
+            SudokuVariant::Knight(n) => n.propagate(grid, candidates),
+            SudokuVariant::CellSum(sum) => sum.propagate(grid, candidates),
+        }
+    }
+
+    /// What is wrong with the digits placed in `grid` as far as the variant is concerned.
+    pub fn violations(&self, grid: &SudokuGrid) -> Vec<Breach> {
+        match self {
+            SudokuVariant::Diagonal(diag) => diag.violations(grid),
+            SudokuVariant::Killer(cage) => cage.violations(grid),
+            SudokuVariant::Kropki(dot) => dot.violations(grid),
+            SudokuVariant::QuadrupleCircles(circle) => circle.violations(grid),
+            SudokuVariant::Renban(ren) => ren.violations(grid),
+            SudokuVariant::Thermometer(therm) => therm.violations(grid),
+            SudokuVariant::Entropic(ent) => ent.violations(grid),
+            SudokuVariant::Arrow(arrow) => arrow.violations(grid),
+            SudokuVariant::RegionSum(rs) => rs.violations(grid),
+            SudokuVariant::XVDot(xv) => xv.violations(grid),
+            SudokuVariant::GermanWhisper(gw) => gw.violations(grid),
+            SudokuVariant::Shaded(s) => s.violations(grid),
+            SudokuVariant::Nabner(n) => n.violations(grid),
+            SudokuVariant::King(k) => k.violations(grid),
+            SudokuVariant::Knight(n) => n.violations(grid),
+            SudokuVariant::CellSum(sum) => sum.violations(grid),
+        }
+    }
+
+    /// Adds the rules of the variant not covered by `seen_cells` and `houses` to `cnf`.
+    pub fn encode(&self, cnf: &mut Cnf) {
+        match self {
+            SudokuVariant::Diagonal(diag) => diag.encode(cnf),
+            SudokuVariant::Killer(cage) => cage.encode(cnf),
+            SudokuVariant::Kropki(dot) => dot.encode(cnf),
+            SudokuVariant::QuadrupleCircles(circle) => circle.encode(cnf),
+            SudokuVariant::Renban(ren) => ren.encode(cnf),
+            SudokuVariant::Thermometer(therm) => therm.encode(cnf),
+            SudokuVariant::Entropic(ent) => ent.encode(cnf),
+            SudokuVariant::Arrow(arrow) => arrow.encode(cnf),
+            SudokuVariant::RegionSum(rs) => rs.encode(cnf),
+            SudokuVariant::XVDot(xv) => xv.encode(cnf),
+            SudokuVariant::GermanWhisper(gw) => gw.encode(cnf),
+            SudokuVariant::Shaded(s) => s.encode(cnf),
+            SudokuVariant::Nabner(n) => n.encode(cnf),
+            SudokuVariant::King(k) => k.encode(cnf),
+            SudokuVariant::Knight(n) => n.encode(cnf),
+            SudokuVariant::CellSum(sum) => sum.encode(cnf),
         }
     }
 }
@@ -158,38 +369,105 @@ impl std::fmt::Display for SudokuVariant {
             SudokuVariant::Nabner(n) => write!(f, "{n}"),
             SudokuVariant::King(k) => write!(f, "{k}"),
             SudokuVariant::Knight(n) => write!(f, "{n}"),
+            SudokuVariant::CellSum(sum) => write!(f, "{sum}"),
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+/// A grid of any `GridSize`, its digits in reading order, and the variants on it.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(try_from = "GridData", into = "GridData")]
 pub struct SudokuGrid {
-    cells: [[u8; 9]; 9],
+    size: GridSize,
+    cells: Vec<u8>,
     variants: Vec<SudokuVariant>,
 }
 
+/// How a `SudokuGrid` is (de)serialised: its cells as rows, so grids saved before sizes
+/// existed (without a `size`) still load as 9x9 ones.
+#[derive(Serialize, Deserialize)]
+struct GridData {
+    #[serde(default)]
+    size: Option<GridSize>,
+    cells: Vec<Vec<u8>>,
+    variants: Vec<SudokuVariant>,
+}
+
+impl TryFrom<GridData> for SudokuGrid {
+    type Error = String;
+
+    fn try_from(data: GridData) -> Result<Self, String> {
+        let size = match data.size {
+            Some(size) => size,
+            None => GridSize::for_side(data.cells.len())
+                .ok_or_else(|| format!("Unsupported grid width: {}", data.cells.len()))?,
+        };
+        let fits = data.cells.len() == size.side()
+            && data.cells.iter().flatten().all(|&v| v <= size.max_digit())
+            && data.cells.iter().all(|row| row.len() == size.side());
+        if !fits {
+            return Err(format!("Cells don't fit a {size} grid"));
+        }
+        Ok(SudokuGrid {
+            size,
+            cells: data.cells.concat(),
+            variants: data.variants,
+        })
+    }
+}
+
+impl From<SudokuGrid> for GridData {
+    fn from(grid: SudokuGrid) -> Self {
+        GridData {
+            size: Some(grid.size),
+            cells: grid.get_cells(),
+            variants: grid.variants,
+        }
+    }
+}
+
 impl SudokuGrid {
+    /// An empty classic 9x9 grid.
     pub fn empty() -> Self {
+        SudokuGrid::with_size(GridSize::CLASSIC)
+    }
+
+    /// An empty grid of the given size.
+    pub fn with_size(size: GridSize) -> Self {
         SudokuGrid {
-            cells: [[0; 9]; 9],
+            size,
+            cells: vec![0; size.side() * size.side()],
             variants: Vec::new(),
         }
     }
 
+    pub fn size(&self) -> GridSize {
+        self.size
+    }
+
     pub fn get_cell(&self, row: usize, col: usize) -> u8 {
-        self.cells[row][col]
+        self.cells[self.size.index((row, col))]
     }
 
-    pub fn get_cells(&self) -> [[u8; 9]; 9] {
+    /// The digits of each row, 0 for an empty cell.
+    pub fn get_cells(&self) -> Vec<Vec<u8>> {
         self.cells
+            .chunks(self.size.side())
+            .map(<[u8]>::to_vec)
+            .collect()
     }
 
     pub fn variants(&self) -> impl Iterator<Item = &SudokuVariant> {
         self.variants.iter()
     }
 
+    pub fn variant(&self, index: usize) -> Option<&SudokuVariant> {
+        self.variants.get(index)
+    }
+
     pub fn set_cell(&mut self, row: usize, col: usize, value: u8) {
-        self.cells[row][col] = value;
+        let index = self.size.index((row, col));
+        self.cells[index] = value;
     }
 
     pub fn add_variant(&mut self, variant: SudokuVariant) {
@@ -201,32 +479,25 @@ impl SudokuGrid {
         if self.get_cell(row, col) != 0 {
             return vec![];
         }
-        let mut possible: Vec<u8> = (1..=9).collect();
+        let side = self.size.side();
+        let mut possible: Vec<u8> = self.size.digits().to_vec();
         // Remove values from row and column
-        for i in 0..9 {
+        for i in 0..side {
             possible.retain(|&v| v != self.get_cell(row, i));
             possible.retain(|&v| v != self.get_cell(i, col));
         }
         // Remove values from box
-        let box_row = row / 3 * 3;
-        let box_col = col / 3 * 3;
-        for r in box_row..box_row + 3 {
-            for c in box_col..box_col + 3 {
-                possible.retain(|&v| v != self.get_cell(r, c));
-            }
+        for (r, c) in self.size.box_cells(self.size.box_index(row, col)) {
+            possible.retain(|&v| v != self.get_cell(r, c));
         }
         possible
     }
 
+    /// Prints the grid a row per line, writing digits above 9 as letters as in puzzle files.
     pub fn display(&self, show_variants: bool) {
-        for row in &self.cells {
+        for row in self.cells.chunks(self.size.side()) {
             for &cell in row {
-                let cell_str = if cell == 0 {
-                    ".".to_string()
-                } else {
-                    cell.to_string()
-                };
-                print!("{cell_str} ");
+                print!("{} ", file_parser::digit_char(cell));
             }
             println!();
         }
@@ -239,14 +510,9 @@ impl SudokuGrid {
     }
 
     pub fn find_empty_cell(&self) -> Option<(usize, usize)> {
-        for row in 0..9 {
-            for col in 0..9 {
-                if self.get_cell(row, col) == 0 {
-                    return Some((row, col));
-                }
-            }
-        }
-        None
+        self.size
+            .cells()
+            .find(|&(row, col)| self.get_cell(row, col) == 0)
     }
 
     pub fn read_from_file(path: &Path) -> Result<Self, Error> {
@@ -254,32 +520,18 @@ impl SudokuGrid {
     }
 
     fn used_in_col(&self, col: usize, num: u8) -> bool {
-        for row in 0..9 {
-            if self.get_cell(row, col) == num {
-                return true;
-            }
-        }
-        false
+        (0..self.size.side()).any(|row| self.get_cell(row, col) == num)
     }
 
     fn used_in_row(&self, row: usize, num: u8) -> bool {
-        for col in 0..9 {
-            if self.get_cell(row, col) == num {
-                return true;
-            }
-        }
-        false
+        (0..self.size.side()).any(|col| self.get_cell(row, col) == num)
     }
 
-    fn used_in_subgrid(&self, start_row: usize, start_col: usize, num: u8) -> bool {
-        for row in 0..3 {
-            for col in 0..3 {
-                if self.get_cell(row + start_row, col + start_col) == num {
-                    return true;
-                }
-            }
-        }
-        false
+    fn used_in_box(&self, b: usize, num: u8) -> bool {
+        self.size
+            .box_cells(b)
+            .into_iter()
+            .any(|(row, col)| self.get_cell(row, col) == num)
     }
 
     pub fn is_valid_move(&self, row: usize, col: usize, num: u8) -> bool {
@@ -292,36 +544,25 @@ impl SudokuGrid {
     }
 
     pub fn is_board_valid(&self) -> bool {
+        let side = self.size.side();
         // Check rows
-        for row in 0..9 {
-            if !Self::is_valid_group(&self.cells[row]) {
+        for row in 0..side {
+            if !self.is_valid_group((0..side).map(|col| (row, col))) {
                 return false;
             }
         }
 
         // Check columns
-        for col in 0..9 {
-            let mut column = [0u8; 9];
-            for (row, value) in column.iter_mut().enumerate() {
-                *value = self.cells[row][col];
-            }
-            if !Self::is_valid_group(&column) {
+        for col in 0..side {
+            if !self.is_valid_group((0..side).map(|row| (row, col))) {
                 return false;
             }
         }
 
-        // Check 3x3 boxes
-        for box_row in 0..3 {
-            for box_col in 0..3 {
-                let mut block = [0u8; 9];
-                for i in 0..3 {
-                    for j in 0..3 {
-                        block[i * 3 + j] = self.cells[box_row * 3 + i][box_col * 3 + j];
-                    }
-                }
-                if !Self::is_valid_group(&block) {
-                    return false;
-                }
+        // Check boxes
+        for b in 0..side {
+            if !self.is_valid_group(self.size.box_cells(b)) {
+                return false;
             }
         }
         true
@@ -330,13 +571,15 @@ impl SudokuGrid {
     fn is_classic_valid(&self, row: usize, col: usize, num: u8) -> bool {
         !self.used_in_row(row, num)
             && !self.used_in_col(col, num)
-            && !self.used_in_subgrid(row - row % 3, col - col % 3, num)
+            && !self.used_in_box(self.size.box_index(row, col), num)
     }
 
-    fn is_valid_group(group: &[u8; 9]) -> bool {
-        let mut seen = HashSet::with_capacity(9);
-        for &num in group {
-            if !(1..=9).contains(&num) || !seen.insert(num) {
+    /// Whether `cells` hold every digit once.
+    fn is_valid_group(&self, cells: impl IntoIterator<Item = (usize, usize)>) -> bool {
+        let mut seen = HashSet::with_capacity(self.size.side());
+        for (row, col) in cells {
+            let num = self.get_cell(row, col);
+            if !(1..=self.size.max_digit()).contains(&num) || !seen.insert(num) {
                 return false;
             }
         }
@@ -349,3 +592,32 @@ impl Default for SudokuGrid {
         SudokuGrid::empty()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialises_the_size_with_the_cells() {
+        let mut grid = SudokuGrid::with_size(GridSize::new(3, 2).unwrap());
+        grid.set_cell(5, 0, 6);
+
+        let json = serde_json::to_string(&grid).unwrap();
+        let read: SudokuGrid = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(read, grid);
+        assert!(json.contains("\"box_rows\":3"));
+    }
+
+    #[test]
+    fn reads_grids_saved_without_a_size() {
+        let rows = vec![vec![0u8; 9]; 9];
+        let json = serde_json::json!({ "cells": rows, "variants": [] }).to_string();
+
+        let grid: SudokuGrid = serde_json::from_str(&json).unwrap();
+        assert_eq!(grid, SudokuGrid::empty());
+
+        let json = serde_json::json!({ "cells": [[1, 2], [3, 4]], "variants": [] }).to_string();
+        assert!(serde_json::from_str::<SudokuGrid>(&json).is_err());
+    }
+}
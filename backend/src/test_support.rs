@@ -0,0 +1,34 @@
+//! Grids shared by the unit tests.
+
+use std::path::PathBuf;
+
+use crate::{SudokuGrid, get_examples_path};
+
+/// Reads a puzzle from the examples directory.
+pub(crate) fn example(name: &str) -> SudokuGrid {
+    let mut path = PathBuf::from(get_examples_path());
+    path.push(name);
+    SudokuGrid::read_from_file(&path).unwrap()
+}
+
+/// A valid classic solution: 1 to 9 in the top row, each band shifted along by a box.
+pub(crate) fn pattern() -> SudokuGrid {
+    let mut grid = SudokuGrid::empty();
+    for row in 0..9 {
+        for col in 0..9 {
+            grid.set_cell(row, col, ((row * 3 + row / 3 + col) % 9) as u8 + 1);
+        }
+    }
+    grid
+}
+
+/// Row 1 holds 1 to 7 and 8 is ruled out of its last two cells, so both need a 9.
+pub(crate) fn contradictory_grid() -> SudokuGrid {
+    let mut grid = SudokuGrid::empty();
+    for col in 0..7 {
+        grid.set_cell(0, col, col as u8 + 1);
+    }
+    grid.set_cell(3, 7, 8);
+    grid.set_cell(4, 8, 8);
+    grid
+}
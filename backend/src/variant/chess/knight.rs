@@ -3,13 +3,20 @@ use std::{collections::HashMap, fmt::Display};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    SudokuGrid, SudokuVariant,
-    variant::{ALL_POSSIBILITIES, Variant, chess::get_all_cells, error::PossibilityResult},
+    GridSize, SudokuGrid, SudokuVariant,
+    solver::cell_name,
+    variant::{Breach, Variant, chess::get_all_cells, error::PossibilityResult},
 };
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Knight {}
 
+impl Default for Knight {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Knight {
     const DIRECTIONS: [(isize, isize); 8] = [
         (-2, -1),
@@ -36,21 +43,13 @@ impl Variant for Knight {
         if value == 0 {
             return true;
         }
-        for &(dr, dc) in Self::DIRECTIONS.iter() {
-            let check_row = row as isize + dr;
-            let check_col = col as isize + dc;
-            if check_row < 0 || check_row > 8 || check_col < 0 || check_col > 8 {
-                continue;
-            }
-            if grid.get_cell(check_row as usize, check_col as usize) == value {
-                return false;
-            }
-        }
-        true
+        self.seen_cells(grid.size(), row, col)
+            .into_iter()
+            .all(|(r, c)| grid.get_cell(r, c) != value)
     }
 
     fn validate_solution(&self, grid: &SudokuGrid) -> bool {
-        for &(row, col) in self.constrained_cells().iter() {
+        for &(row, col) in self.constrained_cells(grid.size()).iter() {
             let value = grid.get_cell(row, col);
             if value == 0 {
                 continue;
@@ -62,25 +61,49 @@ impl Variant for Knight {
         true
     }
 
-    fn constrained_cells(&self) -> Vec<(usize, usize)> {
-        get_all_cells()
+    fn violations(&self, grid: &SudokuGrid) -> Vec<Breach> {
+        let mut breaches = Vec::new();
+        for (row, col) in get_all_cells(grid.size()) {
+            let value = grid.get_cell(row, col);
+            for (r, c) in self.seen_cells(grid.size(), row, col) {
+                if value != 0 && (row, col) < (r, c) && grid.get_cell(r, c) == value {
+                    let message = format!(
+                        "{} and {} are a knight's move apart and both hold {value}",
+                        cell_name((row, col)),
+                        cell_name((r, c))
+                    );
+                    breaches.push((vec![(row, col), (r, c)], message));
+                }
+            }
+        }
+        breaches
+    }
+
+    fn constrained_cells(&self, size: GridSize) -> Vec<(usize, usize)> {
+        get_all_cells(size)
+    }
+
+    fn seen_cells(&self, size: GridSize, row: usize, col: usize) -> Vec<(usize, usize)> {
+        let side = size.side() as isize;
+        Self::DIRECTIONS
+            .iter()
+            .map(|&(dr, dc)| (row as isize + dr, col as isize + dc))
+            .filter(|&(r, c)| (0..side).contains(&r) && (0..side).contains(&c))
+            .map(|(r, c)| (r as usize, c as usize))
+            .collect()
     }
 
     fn get_possibilities(&self, grid: &SudokuGrid) -> PossibilityResult {
         let mut possibilities = HashMap::new();
-        for &(row, col) in self.constrained_cells().iter() {
+        let size = grid.size();
+        for &(row, col) in self.constrained_cells(size).iter() {
             let value = grid.get_cell(row, col);
             if value != 0 {
                 possibilities.insert((row, col), vec![value]);
             } else {
-                let mut values = ALL_POSSIBILITIES.to_vec();
-                for &(dr, dc) in Self::DIRECTIONS.iter() {
-                    let check_row = row as isize + dr;
-                    let check_col = col as isize + dc;
-                    if check_row < 0 || check_row > 8 || check_col < 0 || check_col > 8 {
-                        continue;
-                    }
-                    values.retain(|&v| v != grid.get_cell(check_row as usize, check_col as usize));
+                let mut values = size.digits().to_vec();
+                for (r, c) in self.seen_cells(size, row, col) {
+                    values.retain(|&v| v != grid.get_cell(r, c));
                 }
                 possibilities.insert((row, col), values);
             }
@@ -234,7 +257,7 @@ mod tests {
     mod get_possibilities {
         use std::collections::HashMap;
 
-        use crate::variant::{ALL_POSSIBILITIES, chess::get_all_cells};
+        use crate::{GridSize, variant::chess::get_all_cells};
 
         use super::*;
 
@@ -242,9 +265,9 @@ mod tests {
         fn empty() {
             let grid = SudokuGrid::empty();
             let knight = Knight::new();
-            let expected = get_all_cells()
+            let expected = get_all_cells(GridSize::CLASSIC)
                 .iter()
-                .map(|&cell| (cell, ALL_POSSIBILITIES.to_vec()))
+                .map(|&cell| (cell, (1..=9).collect()))
                 .collect::<HashMap<_, _>>();
             assert_eq!(knight.get_possibilities(&grid), Ok(expected));
         }
@@ -281,9 +304,7 @@ mod tests {
             // Remaining cells have all possibilities
             for row in 0..9 {
                 for col in 0..9 {
-                    expected
-                        .entry((row, col))
-                        .or_insert(ALL_POSSIBILITIES.to_vec());
+                    expected.entry((row, col)).or_insert((1..=9).collect());
                 }
             }
             let result = knight.get_possibilities(&grid);
@@ -319,4 +340,19 @@ mod tests {
             assert_eq!(knight.get_possibilities(&grid), Ok(expected));
         }
     }
+
+    mod seen_cells {
+        use crate::GridSize;
+
+        use super::*;
+
+        #[test]
+        fn corner_and_centre() {
+            let knight = Knight::new();
+            let mut corner = knight.seen_cells(GridSize::CLASSIC, 0, 0);
+            corner.sort();
+            assert_eq!(corner, vec![(1, 2), (2, 1)]);
+            assert_eq!(knight.seen_cells(GridSize::CLASSIC, 4, 4).len(), 8);
+        }
+    }
 }
@@ -3,11 +3,15 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    SudokuGrid, SudokuVariant,
+    GridSize, SudokuGrid, SudokuVariant,
+    candidates::{Candidate, CandidateGrid},
+    cnf::Cnf,
     file_parser::parse_positions,
+    solver::cell_name,
     variant::{
-        ALL_POSSIBILITIES, Variant,
+        Breach, Variant,
         error::{PossibilityResult, VariantContradiction},
+        pair_weak_links,
     },
 };
 
@@ -45,6 +49,14 @@ impl KropkiDot {
         };
         Some(SudokuVariant::Kropki(KropkiDot::new(cells, colour)))
     }
+
+    /// Whether the two cells of the dot may hold `a` and `b`.
+    fn allows(&self, a: u8, b: u8) -> bool {
+        match self.colour {
+            KropkiColour::Black => a * 2 == b || b * 2 == a,
+            KropkiColour::White => a.abs_diff(b) == 1,
+        }
+    }
 }
 
 impl Variant for KropkiDot {
@@ -86,10 +98,38 @@ impl Variant for KropkiDot {
         }
     }
 
-    fn constrained_cells(&self) -> Vec<(usize, usize)> {
+    fn violations(&self, grid: &SudokuGrid) -> Vec<Breach> {
+        let [a, b] = self.cells;
+        let (val1, val2) = (grid.get_cell(a.0, a.1), grid.get_cell(b.0, b.1));
+        if val1 == 0 || val2 == 0 || self.validate_solution(grid) {
+            return Vec::new();
+        }
+        let colour = match self.colour {
+            KropkiColour::Black => "Black",
+            KropkiColour::White => "White",
+        };
+        let message = format!(
+            "{colour} Kropki between {} and {} holds {val1} and {val2}",
+            cell_name(a),
+            cell_name(b)
+        );
+        vec![(self.cells.to_vec(), message)]
+    }
+
+    fn constrained_cells(&self, _size: GridSize) -> Vec<(usize, usize)> {
         vec![self.cells[0], self.cells[1]]
     }
 
+    fn encode(&self, cnf: &mut Cnf) {
+        cnf.forbid_pairs(self.cells[0], self.cells[1], |a, b| self.allows(a, b));
+    }
+
+    fn weak_links(&self, candidates: &CandidateGrid) -> Vec<(Candidate, Candidate)> {
+        pair_weak_links(candidates, self.cells[0], self.cells[1], |a, b| {
+            self.allows(a, b)
+        })
+    }
+
     fn get_possibilities(&self, grid: &SudokuGrid) -> PossibilityResult {
         let [(r1, c1), (r2, c2)] = self.cells;
         let val1 = grid.get_cell(r1, c1);
@@ -97,8 +137,9 @@ impl Variant for KropkiDot {
         let mut possibilities = HashMap::new();
         // Neither value is known, so just return all possibilities for both
         if val1 == 0 && val2 == 0 {
-            possibilities.insert(self.cells[0], ALL_POSSIBILITIES.to_vec());
-            possibilities.insert(self.cells[1], ALL_POSSIBILITIES.to_vec());
+            let digits = grid.size().digits().to_vec();
+            possibilities.insert(self.cells[0], digits.clone());
+            possibilities.insert(self.cells[1], digits);
         }
         // If both are already known, then just return the known value vector
         else if val1 != 0 && val2 != 0 {
@@ -116,7 +157,7 @@ impl Variant for KropkiDot {
                     if known_value > 1 {
                         poss.push(known_value - 1);
                     }
-                    if known_value < 9 {
+                    if known_value < grid.size().max_digit() {
                         poss.push(known_value + 1);
                     }
                 }
@@ -124,7 +165,7 @@ impl Variant for KropkiDot {
                     if known_value % 2 == 0 {
                         poss.push(known_value / 2);
                     }
-                    if known_value * 2 < 9 {
+                    if known_value * 2 <= grid.size().max_digit() {
                         poss.push(known_value * 2);
                     }
                 }
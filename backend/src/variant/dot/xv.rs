@@ -3,11 +3,15 @@ use std::{collections::HashMap, fmt};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    SudokuGrid, SudokuVariant,
+    GridSize, SudokuGrid, SudokuVariant,
+    candidates::{Candidate, CandidateGrid},
+    cnf::Cnf,
     file_parser::parse_positions,
+    solver::cell_name,
     variant::{
-        ALL_POSSIBILITIES, Variant,
+        Breach, Variant,
         error::{PossibilityResult, VariantContradiction},
+        pair_weak_links,
     },
 };
 
@@ -45,6 +49,14 @@ impl XVDot {
         };
         Some(SudokuVariant::XVDot(XVDot::new(cells, flavour)))
     }
+
+    /// Whether the two cells of the dot may hold `a` and `b`.
+    fn allows(&self, a: u8, b: u8) -> bool {
+        match self.flavour {
+            XVFlavour::X => a + b == 10,
+            XVFlavour::V => a + b == 5,
+        }
+    }
 }
 
 impl Variant for XVDot {
@@ -86,10 +98,40 @@ impl Variant for XVDot {
         }
     }
 
-    fn constrained_cells(&self) -> Vec<(usize, usize)> {
+    fn violations(&self, grid: &crate::SudokuGrid) -> Vec<Breach> {
+        let [a, b] = self.cells;
+        let (val1, val2) = (grid.get_cell(a.0, a.1), grid.get_cell(b.0, b.1));
+        if val1 == 0 || val2 == 0 || self.validate_solution(grid) {
+            return Vec::new();
+        }
+        let expected = match self.flavour {
+            XVFlavour::X => 10,
+            XVFlavour::V => 5,
+        };
+        let message = format!(
+            "{} between {} and {} sums to {}, expected {expected}",
+            self.flavour,
+            cell_name(a),
+            cell_name(b),
+            val1 + val2
+        );
+        vec![(self.cells.to_vec(), message)]
+    }
+
+    fn constrained_cells(&self, _size: GridSize) -> Vec<(usize, usize)> {
         vec![self.cells[0], self.cells[1]]
     }
 
+    fn encode(&self, cnf: &mut Cnf) {
+        cnf.forbid_pairs(self.cells[0], self.cells[1], |a, b| self.allows(a, b));
+    }
+
+    fn weak_links(&self, candidates: &CandidateGrid) -> Vec<(Candidate, Candidate)> {
+        pair_weak_links(candidates, self.cells[0], self.cells[1], |a, b| {
+            self.allows(a, b)
+        })
+    }
+
     fn get_possibilities(&self, grid: &SudokuGrid) -> PossibilityResult {
         let [(r1, c1), (r2, c2)] = self.cells;
         let val1 = grid.get_cell(r1, c1);
@@ -99,8 +141,10 @@ impl Variant for XVDot {
         if val1 == 0 && val2 == 0 {
             match self.flavour {
                 XVFlavour::X => {
-                    possibilities.insert(self.cells[0], ALL_POSSIBILITIES.to_vec());
-                    possibilities.insert(self.cells[1], ALL_POSSIBILITIES.to_vec());
+                    // Digits from 10 up have no partner
+                    let digits: Vec<u8> = (1..=grid.size().max_digit().min(9)).collect();
+                    possibilities.insert(self.cells[0], digits.clone());
+                    possibilities.insert(self.cells[1], digits);
                 }
                 XVFlavour::V => {
                     possibilities.insert(self.cells[0], vec![1, 2, 3, 4]);
@@ -133,13 +177,14 @@ impl Variant for XVDot {
                     possibilities.insert(self.cells[(known_index + 1) % 2], vec![5 - known_value]);
                 }
                 XVFlavour::X => {
-                    if known_value == 5 {
+                    if known_value == 5 || known_value >= 10 {
+                        let reason = format!(
+                            "No possible values for X dot based on other cell value of {known_value}"
+                        );
                         return Err(VariantContradiction::NoPossibilities {
                             cell: self.cells[(known_index + 1) % 2],
                             variant: "XVDot:X",
-                            reason: String::from(
-                                "No possible values for X dot based on other cell value of 5",
-                            ),
+                            reason,
                         });
                     }
                     possibilities.insert(self.cells[(known_index + 1) % 2], vec![10 - known_value]);
@@ -1,10 +1,12 @@
 use std::collections::HashMap;
 use std::fmt;
 
+use serde::Serialize;
+
 pub type PossibilityMap = HashMap<(usize, usize), Vec<u8>>;
 pub type PossibilityResult = Result<PossibilityMap, VariantContradiction>;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub enum VariantContradiction {
     // A specific cell ended up with no valid digits due to this variant
     NoPossibilities {
@@ -3,11 +3,15 @@ use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 
 use crate::{
-    SudokuGrid, SudokuVariant,
+    GridSize, SudokuGrid, SudokuVariant,
+    candidates::{CandidateGrid, DigitSet},
+    cnf::Cnf,
     file_parser::parse_positions,
+    solver::cell_name,
     variant::{
-        Variant,
+        Breach, Eliminations, Variant, domain,
         error::{PossibilityResult, VariantContradiction},
+        unsupported,
     },
 };
 
@@ -50,12 +54,12 @@ impl Variant for Arrow {
         let known_sum: u8 = body_values.iter().sum();
         let unknown_count = body_values.iter().filter(|&&v| v == 0).count();
 
-        // If the head cell is 0 (unknown), we can only check whether the body can *possibly* sum to a valid head (<=9)
+        // If the head cell is 0 (unknown), we can only check whether the body can *possibly* sum to a valid head (at most the largest digit)
         if head_value == 0 {
             // If body is fully filled but head is unknown, we can't validate yet
             if unknown_count == 0 {
                 // Head must be equal to the known body sum and nonzero
-                return known_sum <= 9;
+                return known_sum <= grid.size().max_digit();
             }
             // Otherwise, just check that the body sum is still in the realm of possibility
             // (realistically not needed unless you want to prune impossible sums)
@@ -104,12 +108,46 @@ impl Variant for Arrow {
         body_values.iter().sum::<u8>() == head_value
     }
 
-    fn constrained_cells(&self) -> Vec<(usize, usize)> {
+    fn violations(&self, grid: &crate::SudokuGrid) -> Vec<Breach> {
+        let values: Vec<u8> = self
+            .cells
+            .iter()
+            .map(|&(r, c)| grid.get_cell(r, c))
+            .collect();
+        let Some((&head, body)) = values.split_first() else {
+            return Vec::new();
+        };
+        let sum: u8 = body.iter().sum();
+        if values.contains(&0) || sum == head {
+            return Vec::new();
+        }
+        let message = format!(
+            "Arrow from {} sums to {sum}, but its circle holds {head}",
+            cell_name(self.cells[0])
+        );
+        vec![(self.cells.clone(), message)]
+    }
+
+    fn constrained_cells(&self, _size: GridSize) -> Vec<(usize, usize)> {
         self.cells.clone()
     }
 
+    /// The body's sum is encoded with a unary adder capped at the largest digit, and each sum
+    /// implies the same digit in the head.
+    fn encode(&self, cnf: &mut Cnf) {
+        let Some((&head, body)) = self.cells.split_first() else {
+            return;
+        };
+        let sums = cnf.sum(body, cnf.size().side());
+        for (total, &literal) in sums.iter().enumerate().skip(1) {
+            if let Some(literal) = literal {
+                cnf.add_clause([-literal, cnf.cell(head, total as u8)]);
+            }
+        }
+    }
+
     fn get_possibilities(&self, grid: &SudokuGrid) -> PossibilityResult {
-        // For each unknown cell on the arrow, return all values (1..=9) that can participate in at least one valid assignment (with the other unknowns) that satisfies the arrow sum, given the current grid state. No uniqueness filtering is applied.
+        // For each unknown cell on the arrow, return all values (1 to the largest digit) that can participate in at least one valid assignment (with the other unknowns) that satisfies the arrow sum, given the current grid state. No uniqueness filtering is applied.
         let mut possibilities: HashMap<(usize, usize), Vec<u8>> = HashMap::new();
 
         // Gather current values for all cells on the arrow
@@ -131,14 +169,14 @@ impl Variant for Arrow {
         if unknowns.is_empty() {
             // All cells are known, so just return them as a map
             for &(r, c) in &self.cells {
-                let val = grid.get_cell(r, c);
-                possibilities.insert((r, c), vec![val]);
-                return Ok(possibilities);
+                possibilities.insert((r, c), vec![grid.get_cell(r, c)]);
             }
+            return Ok(possibilities);
         }
 
-        // For each unknown, domain is simply 1..=9 (no uniqueness filtering)
-        let domains: Vec<Vec<u8>> = vec![(1..=9).collect(); unknowns.len()];
+        // For each unknown, domain is simply every digit (no uniqueness filtering)
+        let max_digit = grid.size().max_digit();
+        let domains: Vec<Vec<u8>> = vec![(1..=max_digit).collect(); unknowns.len()];
 
         // For each possible assignment to the unknowns, check if it satisfies the arrow constraint
         let mut cell_poss: HashMap<(usize, usize), HashSet<u8>> = HashMap::new();
@@ -156,7 +194,7 @@ impl Variant for Arrow {
             if head_value != 0 && body_sum != head_value {
                 continue;
             }
-            if head_value == 0 && body_sum > 9 {
+            if head_value == 0 && body_sum > max_digit {
                 continue;
             }
 
@@ -190,6 +228,63 @@ impl Variant for Arrow {
 
         Ok(possibilities)
     }
+
+    /// Keeps the candidates that take part in some sum of the body's candidates equal to a
+    /// candidate of the head.
+    fn propagate(
+        &self,
+        grid: &SudokuGrid,
+        candidates: &CandidateGrid,
+    ) -> Result<Eliminations, VariantContradiction> {
+        let domains: Vec<DigitSet> = self
+            .cells
+            .iter()
+            .map(|&cell| domain(grid, candidates, cell))
+            .collect();
+        let (head, body) = domains
+            .split_first()
+            .ok_or(VariantContradiction::Inconsistent {
+                variant: "Arrow",
+                reason: String::from("Arrow has no cells"),
+            })?;
+
+        // The sums the body can reach before and after each cell, as bitmasks (bit `s` for a
+        // sum of `s`); anything over the largest digit can't match the head
+        let max_digit = grid.size().max_digit();
+        let in_range = (1u32 << (max_digit + 1)) - 1;
+        let add = |sums: u32, digits: DigitSet| {
+            digits.iter().fold(0, |acc, d| acc | (sums << d)) & in_range
+        };
+        let mut before = vec![1u32; body.len() + 1];
+        for (i, &digits) in body.iter().enumerate() {
+            before[i + 1] = add(before[i], digits);
+        }
+        let mut after = vec![1u32; body.len() + 1];
+        for (i, &digits) in body.iter().enumerate().rev() {
+            after[i] = add(after[i + 1], digits);
+        }
+        let reaches_head = |sums: u32| head.iter().any(|h| sums & (1 << h) != 0);
+
+        let mut supported = vec![
+            head.iter()
+                .filter(|&h| before[body.len()] & (1 << h) != 0)
+                .collect(),
+        ];
+        for (i, &digits) in body.iter().enumerate() {
+            // The sums of every other body cell
+            let around = (0..=max_digit)
+                .filter(|&s| before[i] & (1 << s) != 0)
+                .fold(0, |acc, s| acc | (after[i + 1] << s))
+                & in_range;
+            supported.push(
+                digits
+                    .iter()
+                    .filter(|&d| reaches_head(around << d))
+                    .collect(),
+            );
+        }
+        unsupported(grid, candidates, &self.cells, &supported, "Arrow")
+    }
 }
 
 impl std::fmt::Display for Arrow {
@@ -224,7 +319,10 @@ mod tests {
     #[test]
     fn test_constrained_cells() {
         let arrow = setup_arrow();
-        assert_eq!(arrow.constrained_cells(), vec![(0, 0), (0, 1), (0, 2)]);
+        assert_eq!(
+            arrow.constrained_cells(GridSize::CLASSIC),
+            vec![(0, 0), (0, 1), (0, 2)]
+        );
     }
 
     #[test]
@@ -509,4 +607,21 @@ mod tests {
             assert_eq!(result.get(cell).unwrap(), &(1..=8).collect::<Vec<u8>>());
         }
     }
+
+    #[test]
+    fn test_arrow_propagate_uses_candidates() {
+        let arrow = setup_arrow();
+        let grid = SudokuGrid::empty();
+        let mut candidates = CandidateGrid::new();
+        candidates.set(0, 0, DigitSet::from_digits(&[3, 4, 9]));
+        candidates.set(0, 1, DigitSet::from_digits(&[1, 2]));
+        candidates.set(0, 2, GridSize::CLASSIC.digits());
+
+        let mut eliminations = arrow.propagate(&grid, &candidates).unwrap();
+        eliminations.sort_unstable();
+
+        // Every head candidate can be made, but r1c3 must add 1 or 2 up to 3, 4 or 9
+        let expected: Vec<_> = [4, 5, 6, 9].into_iter().map(|d| ((0, 2), d)).collect();
+        assert_eq!(eliminations, expected);
+    }
 }
@@ -3,23 +3,32 @@ use std::collections::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    SudokuGrid, SudokuVariant,
-    variant::{ALL_POSSIBILITIES, Variant, error::PossibilityResult},
+    GridSize, SudokuGrid, SudokuVariant,
+    variant::{Breach, Variant, error::PossibilityResult, repeats},
 };
 
+/// A main diagonal whose cells hold every digit once: the positive one rises from the bottom
+/// left corner, the negative one falls from the top left.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Diagonal {
-    cells: Vec<(usize, usize)>,
+    positive: bool,
 }
 
 impl Diagonal {
     pub fn new(is_positive_diagonal: bool) -> Self {
-        let cells = if is_positive_diagonal {
-            (0..9).map(|i| (8 - i, i)).collect()
+        Diagonal {
+            positive: is_positive_diagonal,
+        }
+    }
+
+    /// The diagonal's cells on a grid of the given `size`, from left to right.
+    fn cells(&self, size: GridSize) -> Vec<(usize, usize)> {
+        let side = size.side();
+        if self.positive {
+            (0..side).map(|i| (side - 1 - i, i)).collect()
         } else {
-            (0..9).map(|i| (i, i)).collect()
-        };
-        Diagonal { cells }
+            (0..side).map(|i| (i, i)).collect()
+        }
     }
 
     pub fn parse(data: &str) -> Option<SudokuVariant> {
@@ -33,10 +42,11 @@ impl Diagonal {
 
 impl Variant for Diagonal {
     fn is_valid(&self, grid: &crate::SudokuGrid, row: usize, col: usize, value: u8) -> bool {
-        if !self.cells.contains(&(row, col)) {
+        let cells = self.cells(grid.size());
+        if !cells.contains(&(row, col)) {
             return true;
         }
-        for &(r, c) in &self.cells {
+        for &(r, c) in &cells {
             if grid.get_cell(r, c) == value {
                 return false;
             }
@@ -46,7 +56,7 @@ impl Variant for Diagonal {
 
     fn validate_solution(&self, grid: &crate::SudokuGrid) -> bool {
         let values: Vec<u8> = self
-            .cells
+            .cells(grid.size())
             .iter()
             .map(|&(r, c)| grid.get_cell(r, c))
             .collect();
@@ -61,13 +71,32 @@ impl Variant for Diagonal {
         values.iter().all(|&v| seen.insert(v))
     }
 
-    fn constrained_cells(&self) -> Vec<(usize, usize)> {
-        self.cells.clone()
+    fn violations(&self, grid: &crate::SudokuGrid) -> Vec<Breach> {
+        repeats(grid, &self.cells(grid.size()), &self.to_string())
+    }
+
+    fn constrained_cells(&self, size: GridSize) -> Vec<(usize, usize)> {
+        self.cells(size)
+    }
+
+    fn seen_cells(&self, size: GridSize, row: usize, col: usize) -> Vec<(usize, usize)> {
+        let cells = self.cells(size);
+        if !cells.contains(&(row, col)) {
+            return Vec::new();
+        }
+        cells
+            .into_iter()
+            .filter(|&cell| cell != (row, col))
+            .collect()
+    }
+
+    fn houses(&self, size: GridSize) -> Vec<Vec<(usize, usize)>> {
+        vec![self.cells(size)]
     }
 
     fn get_possibilities(&self, grid: &SudokuGrid) -> PossibilityResult {
-        let known_cells: HashMap<(usize, usize), u8> = self
-            .cells
+        let cells = self.cells(grid.size());
+        let known_cells: HashMap<(usize, usize), u8> = cells
             .iter()
             .filter_map(|&(row, col)| {
                 let val = grid.get_cell(row, col);
@@ -76,14 +105,14 @@ impl Variant for Diagonal {
             .collect();
         let used: HashSet<u8> = known_cells.values().copied().collect();
 
-        let poss: Vec<u8> = ALL_POSSIBILITIES
+        let poss: Vec<u8> = grid
+            .size()
+            .digits()
             .iter()
-            .copied()
             .filter(|v| !used.contains(v))
             .collect();
 
-        Ok(self
-            .cells
+        Ok(cells
             .iter()
             .map(|&cell| {
                 if let Some(&v) = known_cells.get(&cell) {
@@ -98,10 +127,10 @@ impl Variant for Diagonal {
 
 impl std::fmt::Display for Diagonal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self.cells[0] == (0, 0) {
-            write!(f, "Negative Diagonal")
-        } else {
+        if self.positive {
             write!(f, "Positive Diagonal")
+        } else {
+            write!(f, "Negative Diagonal")
         }
     }
 }
@@ -3,10 +3,12 @@ use std::collections::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    SudokuGrid, SudokuVariant,
+    GridSize, SudokuGrid, SudokuVariant,
+    cnf::Cnf,
     file_parser::parse_positions,
+    solver::cell_name,
     variant::{
-        Variant,
+        Breach, Variant,
         error::{PossibilityResult, VariantContradiction},
     },
 };
@@ -25,6 +27,12 @@ impl Entropic {
         let cells = parse_positions(data).ok()?;
         Some(SudokuVariant::Entropic(Entropic::new(cells)))
     }
+
+    /// Whether the digits of a grid of the given `size` split into equal low, middle and high
+    /// bands, i.e. whether its side is a multiple of 3.
+    pub fn supports(size: GridSize) -> bool {
+        size.side().is_multiple_of(3)
+    }
 }
 
 impl Variant for Entropic {
@@ -48,7 +56,10 @@ impl Variant for Entropic {
 
         // Now run the windows entropic checks
         for window in values.windows(3) {
-            let bands = window.iter().map(|&v| to_entropy(v)).collect::<Vec<_>>();
+            let bands = window
+                .iter()
+                .map(|&v| to_entropy(v, grid.size()))
+                .collect::<Vec<_>>();
 
             let filled = bands.iter().filter_map(|&b| b).collect::<Vec<_>>();
             let unique = filled.iter().cloned().collect::<HashSet<Entropy>>();
@@ -81,7 +92,7 @@ impl Variant for Entropic {
             let mut has_high = false;
 
             for &val in window {
-                match to_entropy(val) {
+                match to_entropy(val, grid.size()) {
                     Some(Entropy::Low) => has_low = true,
                     Some(Entropy::Medium) => has_mid = true,
                     Some(Entropy::High) => has_high = true,
@@ -97,11 +108,58 @@ impl Variant for Entropic {
         true
     }
 
-    fn constrained_cells(&self) -> Vec<(usize, usize)> {
+    fn violations(&self, grid: &crate::SudokuGrid) -> Vec<Breach> {
+        let mut breaches = Vec::new();
+        for (i, &a) in self.cells.iter().enumerate() {
+            for &b in self.cells.iter().skip(i + 1).take(2) {
+                let (val1, val2) = (grid.get_cell(a.0, a.1), grid.get_cell(b.0, b.1));
+                let band = match to_entropy(val1, grid.size()) {
+                    Some(Entropy::Low) => "low",
+                    Some(Entropy::Medium) => "middle",
+                    Some(Entropy::High) => "high",
+                    None => continue,
+                };
+                if to_entropy(val1, grid.size()) == to_entropy(val2, grid.size()) {
+                    let message = format!(
+                        "Entropic line holds {val1} and {val2}, both {band}, at {} and {}",
+                        cell_name(a),
+                        cell_name(b)
+                    );
+                    breaches.push((vec![a, b], message));
+                }
+            }
+        }
+        breaches
+    }
+
+    fn constrained_cells(&self, _size: GridSize) -> Vec<(usize, usize)> {
         self.cells.clone()
     }
 
+    /// Any three cells in a row on the line hold one low (1-3), one middle (4-6) and one high
+    /// (7-9) digit, or the same thirds of a larger grid's digits, so no two of them share a
+    /// band. On a grid whose digits don't split into thirds no digit has a band, so every pair
+    /// is forbidden.
+    fn encode(&self, cnf: &mut Cnf) {
+        let size = cnf.size();
+        for window in self.cells.windows(3) {
+            for (i, j) in [(0, 1), (0, 2), (1, 2)] {
+                cnf.forbid_pairs(window[i], window[j], |a, b| {
+                    let band = to_entropy(a, size);
+                    band.is_some() && band != to_entropy(b, size)
+                });
+            }
+        }
+    }
+
     fn get_possibilities(&self, grid: &SudokuGrid) -> PossibilityResult {
+        let size = grid.size();
+        if !Entropic::supports(size) {
+            return Err(VariantContradiction::Inconsistent {
+                variant: "Entropic",
+                reason: format!("The digits of a {size} grid don't split into three bands"),
+            });
+        }
         // Step 1: For each mod-3 group, determine if any cell is set, and if so, which entropy
         let mut group_entropy: [Option<Entropy>; 3] = [None, None, None];
         for (i, &(r, c)) in self.cells.iter().enumerate() {
@@ -109,7 +167,7 @@ impl Variant for Entropic {
             if val == 0 {
                 continue;
             }
-            if let Some(entropy) = to_entropy(val) {
+            if let Some(entropy) = to_entropy(val, size) {
                 let group = i % 3;
                 if let Some(existing) = group_entropy[group] {
                     if existing != entropy {
@@ -146,12 +204,12 @@ impl Variant for Entropic {
             }
             let group = i % 3;
             if let Some(entropy) = group_entropy[group] {
-                possibilities.insert((r, c), entropy.digit_range());
+                possibilities.insert((r, c), entropy.digit_range(size));
             } else {
                 // Union of all unused entropies
                 let mut digits = Vec::new();
                 for e in &unused {
-                    digits.extend(e.digit_range());
+                    digits.extend(e.digit_range(size));
                 }
                 digits.sort();
                 digits.dedup();
@@ -191,21 +249,29 @@ enum Entropy {
 }
 
 impl Entropy {
-    fn digit_range(&self) -> Vec<u8> {
-        match self {
-            Entropy::Low => vec![1, 2, 3],
-            Entropy::Medium => vec![4, 5, 6],
-            Entropy::High => vec![7, 8, 9],
-        }
+    /// The digits in the band on a grid of the given `size`, e.g. 4 to 6 for `Medium` on a
+    /// classic grid.
+    fn digit_range(&self, size: GridSize) -> Vec<u8> {
+        let width = size.max_digit() / 3;
+        let first = match self {
+            Entropy::Low => 1,
+            Entropy::Medium => width + 1,
+            Entropy::High => 2 * width + 1,
+        };
+        (first..first + width).collect()
     }
 }
 
-fn to_entropy(value: u8) -> Option<Entropy> {
-    match value {
-        1..=3 => Some(Entropy::Low),
-        4..=6 => Some(Entropy::Medium),
-        7..=9 => Some(Entropy::High),
-        _ => None,
+/// The band of `value` on a grid of the given `size`, or `None` if it isn't a digit or the
+/// grid's digits don't split into bands.
+fn to_entropy(value: u8, size: GridSize) -> Option<Entropy> {
+    if !Entropic::supports(size) || !(1..=size.max_digit()).contains(&value) {
+        return None;
+    }
+    match (value - 1) / (size.max_digit() / 3) {
+        0 => Some(Entropy::Low),
+        1 => Some(Entropy::Medium),
+        _ => Some(Entropy::High),
     }
 }
 
@@ -213,7 +279,7 @@ fn to_entropy(value: u8) -> Option<Entropy> {
 mod tests {
     use super::Entropic;
 
-    use crate::{SudokuGrid, variant::Variant};
+    use crate::{GridSize, SudokuGrid, variant::Variant};
 
     #[test]
     fn test_solution_valid() {
@@ -357,4 +423,26 @@ mod tests {
         assert_eq!(result.get(&(1, 1)), Some(&vec![1]));
         assert_eq!(result.get(&(1, 3)), Some(&vec![6]));
     }
+    #[test]
+    fn test_bands_follow_the_grid_size() {
+        let line = Entropic::new(vec![(0, 0), (0, 1), (0, 2)]);
+        let mut grid = SudokuGrid::with_size(GridSize::new(3, 4).unwrap());
+        grid.set_cell(0, 0, 4); // Low
+        grid.set_cell(0, 1, 12); // High
+        assert!(line.is_valid(&grid, 0, 2, 5), "Middle digit - valid");
+        assert!(
+            !line.is_valid(&grid, 0, 2, 9),
+            "Second high digit - invalid"
+        );
+        let result = line.get_possibilities(&grid).unwrap();
+        assert_eq!(result.get(&(0, 2)).unwrap(), &vec![5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_unsupported_grid_size() {
+        let line = Entropic::new(vec![(0, 0), (0, 1), (0, 2)]);
+        let grid = SudokuGrid::with_size(GridSize::for_side(16).unwrap());
+        assert!(!Entropic::supports(grid.size()));
+        assert!(line.get_possibilities(&grid).is_err());
+    }
 }
@@ -3,11 +3,15 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    SudokuGrid, SudokuVariant,
+    GridSize, SudokuGrid, SudokuVariant,
+    candidates::{Candidate, CandidateGrid},
+    cnf::Cnf,
     file_parser::parse_positions,
+    solver::cell_name,
     variant::{
-        ALL_POSSIBILITIES, Variant,
+        Breach, Variant,
         error::{PossibilityResult, VariantContradiction},
+        pair_weak_links,
     },
 };
 
@@ -38,6 +42,36 @@ impl GermanWhisper {
             None
         }
     }
+
+    /// The digits each cell can hold given only its direct neighbours, for grids whose digits
+    /// don't split into a high and a low half around 5.
+    fn neighbour_possibilities(&self, grid: &SudokuGrid) -> HashMap<(usize, usize), Vec<u8>> {
+        let max_digit = grid.size().max_digit();
+        let n = self.cells.len();
+        let adjacent = |i: usize, j: usize| {
+            i.abs_diff(j) == 1 || (self.is_circular && n > 2 && i.abs_diff(j) == n - 1)
+        };
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(i, &(r, c))| {
+                let value = grid.get_cell(r, c);
+                if value != 0 {
+                    return ((r, c), vec![value]);
+                }
+                let known: Vec<u8> = (0..n)
+                    .filter(|&j| adjacent(i, j))
+                    .map(|j| grid.get_cell(self.cells[j].0, self.cells[j].1))
+                    .filter(|&v| v != 0)
+                    .collect();
+                let values = (1..=max_digit)
+                    .filter(|&v| (1..=max_digit).any(|w| v.abs_diff(w) >= 5))
+                    .filter(|&v| known.iter().all(|&k| v.abs_diff(k) >= 5))
+                    .collect();
+                ((r, c), values)
+            })
+            .collect()
+    }
 }
 
 impl Variant for GermanWhisper {
@@ -75,10 +109,21 @@ impl Variant for GermanWhisper {
         true
     }
 
-    fn constrained_cells(&self) -> Vec<(usize, usize)> {
+    fn constrained_cells(&self, _size: GridSize) -> Vec<(usize, usize)> {
         self.cells.clone()
     }
 
+    fn encode(&self, cnf: &mut Cnf) {
+        let mut neighbours: Vec<((usize, usize), (usize, usize))> =
+            self.cells.windows(2).map(|w| (w[0], w[1])).collect();
+        if self.is_circular && self.cells.len() > 1 {
+            neighbours.push((self.cells[0], self.cells[self.cells.len() - 1]));
+        }
+        for (a, b) in neighbours {
+            cnf.forbid_pairs(a, b, |x, y| x.abs_diff(y) >= 5);
+        }
+    }
+
     fn validate_solution(&self, grid: &crate::SudokuGrid) -> bool {
         for window in self.cells.windows(2) {
             let val0 = grid.get_cell(window[0].0, window[0].1) as i8;
@@ -102,10 +147,48 @@ impl Variant for GermanWhisper {
         true
     }
 
+    fn violations(&self, grid: &crate::SudokuGrid) -> Vec<Breach> {
+        let mut neighbours: Vec<((usize, usize), (usize, usize))> =
+            self.cells.windows(2).map(|w| (w[0], w[1])).collect();
+        if self.is_circular && self.cells.len() > 2 {
+            neighbours.push((self.cells[self.cells.len() - 1], self.cells[0]));
+        }
+        let mut breaches = Vec::new();
+        for (a, b) in neighbours {
+            let (val1, val2) = (grid.get_cell(a.0, a.1), grid.get_cell(b.0, b.1));
+            if val1 != 0 && val2 != 0 && val1.abs_diff(val2) < 5 {
+                let message = format!(
+                    "German whisper between {} and {} holds {val1} and {val2}",
+                    cell_name(a),
+                    cell_name(b)
+                );
+                breaches.push((vec![a, b], message));
+            }
+        }
+        breaches
+    }
+
+    fn weak_links(&self, candidates: &CandidateGrid) -> Vec<(Candidate, Candidate)> {
+        let mut neighbours: Vec<((usize, usize), (usize, usize))> =
+            self.cells.windows(2).map(|w| (w[0], w[1])).collect();
+        if self.is_circular && self.cells.len() > 2 {
+            neighbours.push((self.cells[self.cells.len() - 1], self.cells[0]));
+        }
+        neighbours
+            .into_iter()
+            .flat_map(|(a, b)| pair_weak_links(candidates, a, b, |x, y| x.abs_diff(y) >= 5))
+            .collect()
+    }
+
     fn get_possibilities(&self, grid: &SudokuGrid) -> PossibilityResult {
         const HIGH_VALUES: &[u8] = &[6, 7, 8, 9];
         const LOW_VALUES: &[u8] = &[1, 2, 3, 4];
 
+        // The high/low alternation only holds for 1 to 9
+        if grid.size().max_digit() != 9 {
+            return Ok(self.neighbour_possibilities(grid));
+        }
+
         let known_idx = self
             .cells
             .iter()
@@ -126,11 +209,7 @@ impl Variant for GermanWhisper {
         let n = self.cells.len();
 
         if known_idx.is_none() {
-            let all_except_5: Vec<u8> = ALL_POSSIBILITIES
-                .iter()
-                .filter(|&v| *v != 5)
-                .cloned()
-                .collect();
+            let all_except_5: Vec<u8> = (1..=9).filter(|&v| v != 5).collect();
             for &(r, c) in &self.cells {
                 possibilities.insert((r, c), all_except_5.clone());
             }
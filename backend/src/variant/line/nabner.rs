@@ -3,10 +3,12 @@ use std::collections::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    SudokuGrid, SudokuVariant,
+    GridSize, SudokuGrid, SudokuVariant,
+    cnf::Cnf,
     file_parser::parse_positions,
+    solver::cell_name,
     variant::{
-        Variant,
+        Breach, Variant,
         error::{PossibilityResult, VariantContradiction},
     },
 };
@@ -89,10 +91,66 @@ impl Variant for Nabner {
             .all(|vals| vals[1].abs_diff(vals[0]) >= 2)
     }
 
-    fn constrained_cells(&self) -> Vec<(usize, usize)> {
+    fn violations(&self, grid: &crate::SudokuGrid) -> Vec<Breach> {
+        let mut breaches = Vec::new();
+        for (i, &a) in self.cells.iter().enumerate() {
+            for &b in &self.cells[i + 1..] {
+                let (val1, val2) = (grid.get_cell(a.0, a.1), grid.get_cell(b.0, b.1));
+                if val1 != 0 && val2 != 0 && val1.abs_diff(val2) < 2 {
+                    let message = format!(
+                        "Nabner line holds {val1} and {val2} at {} and {}",
+                        cell_name(a),
+                        cell_name(b)
+                    );
+                    breaches.push((vec![a, b], message));
+                }
+            }
+        }
+        breaches
+    }
+
+    fn constrained_cells(&self, _size: GridSize) -> Vec<(usize, usize)> {
         self.cells.clone()
     }
 
+    /// No two digits on the line are equal or consecutive.
+    fn encode(&self, cnf: &mut Cnf) {
+        for (i, &a) in self.cells.iter().enumerate() {
+            for &b in &self.cells[i + 1..] {
+                cnf.forbid_pairs(a, b, |x, y| x.abs_diff(y) >= 2);
+            }
+        }
+    }
+
+    fn seen_cells(&self, _size: GridSize, row: usize, col: usize) -> Vec<(usize, usize)> {
+        if !self.cells.contains(&(row, col)) {
+            return Vec::new();
+        }
+        self.cells
+            .iter()
+            .copied()
+            .filter(|&cell| cell != (row, col))
+            .collect()
+    }
+
+    /// Nabner digits never repeat, so a line as long as the grid is wide holds each digit once;
+    /// shorter lines are left to `all_different_groups`.
+    fn houses(&self, size: GridSize) -> Vec<Vec<(usize, usize)>> {
+        if self.cells.len() == size.side() {
+            vec![self.cells.clone()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn all_different_groups(&self, size: GridSize) -> Vec<Vec<(usize, usize)>> {
+        if self.cells.len() < size.side() {
+            vec![self.cells.clone()]
+        } else {
+            Vec::new()
+        }
+    }
+
     fn get_possibilities(&self, grid: &SudokuGrid) -> PossibilityResult {
         let known_values: HashSet<u8> = self
             .cells
@@ -104,7 +162,7 @@ impl Variant for Nabner {
             .collect();
         let line_len = self.cells.len();
 
-        // Helper function to generate all combinations of digits (1..=9) of length `line_len`
+        // Helper function to generate all combinations of the grid's digits of length `line_len`
         fn gen_combinations(
             digits: &[u8],
             k: usize,
@@ -140,7 +198,7 @@ impl Variant for Nabner {
         }
 
         let mut valid_sets = Vec::new();
-        let digits: Vec<u8> = (1..=9).collect();
+        let digits: Vec<u8> = grid.size().digits().to_vec();
         gen_combinations(
             &digits,
             line_len,
@@ -3,38 +3,30 @@ Region sum lines: box borders divide each blue line into segments with the same
 */
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     fmt::Display,
 };
 
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    SudokuGrid, SudokuVariant,
+    GridSize, SudokuGrid, SudokuVariant,
+    cnf::Cnf,
     file_parser::parse_positions,
     variant::{
-        Variant,
+        Breach, Variant,
         error::{PossibilityResult, VariantContradiction},
     },
 };
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct RegionSum {
-    // box_cells: box number: Vec<(row, col)>
-    box_cells: HashMap<usize, Vec<(usize, usize)>>,
+    cells: Vec<(usize, usize)>,
 }
 
 impl RegionSum {
     pub fn new(cells: Vec<(usize, usize)>) -> Self {
-        let mut box_cells = HashMap::new();
-        for &(row, col) in &cells {
-            let box_number = Self::get_box_number(row, col);
-            box_cells
-                .entry(box_number)
-                .or_insert_with(Vec::new)
-                .push((row, col));
-        }
-        RegionSum { box_cells }
+        RegionSum { cells }
     }
 
     pub fn parse(data: &str) -> Option<SudokuVariant> {
@@ -42,28 +34,38 @@ impl RegionSum {
         Some(SudokuVariant::RegionSum(RegionSum::new(cells)))
     }
 
-    fn get_box_number(row: usize, col: usize) -> usize {
-        (row / 3) * 3 + (col / 3)
+    /// The segments the boxes of a grid of the given `size` cut the line into, by box number.
+    fn segments(&self, size: GridSize) -> BTreeMap<usize, Vec<(usize, usize)>> {
+        let mut box_cells = BTreeMap::new();
+        for &(row, col) in &self.cells {
+            box_cells
+                .entry(size.box_index(row, col))
+                .or_insert_with(Vec::new)
+                .push((row, col));
+        }
+        box_cells
     }
 
-    fn min_possible_sum(current_sum: u8, unknowns: usize) -> u8 {
-        current_sum + (1..=9).take(unknowns).sum::<u8>()
+    fn min_possible_sum(current_sum: u8, unknowns: usize, max_digit: u8) -> u8 {
+        current_sum + (1..=max_digit).take(unknowns).sum::<u8>()
     }
 
-    fn max_possible_sum(current_sum: u8, unknowns: usize) -> u8 {
-        current_sum + (1..=9).rev().take(unknowns).sum::<u8>()
+    fn max_possible_sum(current_sum: u8, unknowns: usize, max_digit: u8) -> u8 {
+        current_sum + (1..=max_digit).rev().take(unknowns).sum::<u8>()
     }
 }
 
 impl Variant for RegionSum {
     fn is_valid(&self, grid: &crate::SudokuGrid, row: usize, col: usize, value: u8) -> bool {
         // If the proposed cell is not on this region sum line, then continue
-        if !self.constrained_cells().contains(&(row, col)) {
+        if !self.cells.contains(&(row, col)) {
             return true;
         }
         // Find which box this cell belongs to
-        let current_box = Self::get_box_number(row, col);
-        let current_segment = match self.box_cells.get(&current_box) {
+        let size = grid.size();
+        let box_cells = self.segments(size);
+        let current_box = size.box_index(row, col);
+        let current_segment = match box_cells.get(&current_box) {
             Some(cells) => cells,
             // None = cell not on the region sum line
             None => return true,
@@ -82,8 +84,7 @@ impl Variant for RegionSum {
             .collect();
 
         // Find a target sum from any fully filled segment (excluding current)
-        let target_sum_opt = self
-            .box_cells
+        let target_sum_opt = box_cells
             .iter()
             .filter(|(b, _)| **b != current_box)
             .map(|(_, cells)| {
@@ -111,11 +112,12 @@ impl Variant for RegionSum {
 
         let current_known_sum: u8 = current_values.iter().sum();
         let current_unknowns = current_values.iter().filter(|&&v| v == 0).count();
-        let current_min = Self::min_possible_sum(current_known_sum, current_unknowns);
-        let current_max = Self::max_possible_sum(current_known_sum, current_unknowns);
+        let max_digit = size.max_digit();
+        let current_min = Self::min_possible_sum(current_known_sum, current_unknowns, max_digit);
+        let current_max = Self::max_possible_sum(current_known_sum, current_unknowns, max_digit);
 
         // Now check if this overlaps with all other segment ranges
-        for (&box_num, segment) in self.box_cells.iter() {
+        for (&box_num, segment) in box_cells.iter() {
             if box_num == current_box {
                 continue;
             }
@@ -129,8 +131,8 @@ impl Variant for RegionSum {
                 continue;
             }
 
-            let min = Self::min_possible_sum(known_sum, unknowns);
-            let max = Self::max_possible_sum(known_sum, unknowns);
+            let min = Self::min_possible_sum(known_sum, unknowns, max_digit);
+            let max = Self::max_possible_sum(known_sum, unknowns, max_digit);
 
             // If ranges do not overlap, this is invalid
             if current_max < min || current_min > max {
@@ -144,17 +146,13 @@ impl Variant for RegionSum {
 
     fn validate_solution(&self, grid: &crate::SudokuGrid) -> bool {
         // If any of the cells do not have a value set, then invalid
-        if self
-            .box_cells
-            .values()
-            .flat_map(|cells| cells.iter())
-            .any(|&(r, c)| grid.get_cell(r, c) == 0)
-        {
+        if self.cells.iter().any(|&(r, c)| grid.get_cell(r, c) == 0) {
             return false;
         }
 
         // Get the sum of the first box-segment as the target sum
-        let mut iter = self.box_cells.values();
+        let box_cells = self.segments(grid.size());
+        let mut iter = box_cells.values();
         let first_sum = if let Some(sum) = iter
             .next()
             .map(|cells| cells.iter().map(|&(r, c)| grid.get_cell(r, c)).sum::<u8>())
@@ -176,19 +174,69 @@ impl Variant for RegionSum {
         true
     }
 
-    fn constrained_cells(&self) -> Vec<(usize, usize)> {
-        self.box_cells
+    fn violations(&self, grid: &crate::SudokuGrid) -> Vec<Breach> {
+        if self.cells.iter().any(|&(r, c)| grid.get_cell(r, c) == 0) {
+            return Vec::new();
+        }
+        let sums: Vec<(usize, u8)> = self
+            .segments(grid.size())
+            .iter()
+            .map(|(&box_num, cells)| {
+                (
+                    box_num,
+                    cells.iter().map(|&(r, c)| grid.get_cell(r, c)).sum(),
+                )
+            })
+            .collect();
+        if sums.iter().all(|&(_, sum)| sum == sums[0].1) {
+            return Vec::new();
+        }
+        let sums = sums
+            .iter()
+            .map(|(box_num, sum)| format!("{sum} in box {}", box_num + 1))
+            .collect::<Vec<_>>()
+            .join(", ");
+        vec![(
+            self.cells.clone(),
+            format!("Region sum line segments differ: {sums}"),
+        )]
+    }
+
+    fn constrained_cells(&self, _size: GridSize) -> Vec<(usize, usize)> {
+        self.cells.clone()
+    }
+
+    /// Each segment's sum is encoded separately, and the first segment summing to some total
+    /// implies every other segment does too.
+    fn encode(&self, cnf: &mut Cnf) {
+        let house_sum = cnf.size().house_sum();
+        let sums: Vec<Vec<Option<i32>>> = self
+            .segments(cnf.size())
             .values()
-            .flat_map(|cells| cells.clone())
-            .collect()
+            .map(|cells| cnf.sum(cells, house_sum))
+            .collect();
+        let Some((first, rest)) = sums.split_first() else {
+            return;
+        };
+        for other in rest {
+            for (total, &literal) in first.iter().enumerate() {
+                let Some(literal) = literal else { continue };
+                match other[total] {
+                    Some(same) => cnf.add_clause([-literal, same]),
+                    None => cnf.add_clause([-literal]),
+                }
+            }
+        }
     }
 
     fn get_possibilities(&self, grid: &SudokuGrid) -> PossibilityResult {
         let mut possibilities = HashMap::new();
         let mut target_sum: Option<u8> = None;
+        let box_cells = self.segments(grid.size());
+        let max_digit = grid.size().max_digit();
 
         // 1: Try to find a target sum for any box with fully known values
-        for cells in self.box_cells.values() {
+        for cells in box_cells.values() {
             let known_vals: Vec<u8> = cells
                 .iter()
                 .map(|&(r, c)| grid.get_cell(r, c))
@@ -205,7 +253,7 @@ impl Variant for RegionSum {
         let mut candidate_sums: HashSet<u8> = HashSet::new();
         if target_sum.is_none() {
             let mut sets_per_box = Vec::new();
-            for cells in self.box_cells.values() {
+            for cells in box_cells.values() {
                 let known_vals: Vec<u8> = cells
                     .iter()
                     .map(|&(r, c)| grid.get_cell(r, c))
@@ -219,7 +267,7 @@ impl Variant for RegionSum {
                 }
                 let known_sum: u8 = known_vals.iter().sum();
                 let min_possible_sum = known_sum + unknown_count as u8; // All 1s
-                let max_possible_sum = known_sum + (9 * unknown_count) as u8; // All 9s
+                let max_possible_sum = known_sum + max_digit * unknown_count as u8; // All the largest digit
 
                 sets_per_box.push((min_possible_sum..=max_possible_sum).collect::<HashSet<_>>());
             }
@@ -234,7 +282,7 @@ impl Variant for RegionSum {
 
             // No valid common target
             if candidate_sums.is_empty() {
-                for cells in self.box_cells.values() {
+                for cells in box_cells.values() {
                     for &(r, c) in cells {
                         let val = grid.get_cell(r, c);
                         if val != 0 {
@@ -249,7 +297,7 @@ impl Variant for RegionSum {
         }
 
         // 3: For each box, determine possible values for unknowns
-        for cells in self.box_cells.values() {
+        for cells in box_cells.values() {
             let known_vals: Vec<u8> = cells
                 .iter()
                 .map(|&(r, c)| grid.get_cell(r, c))
@@ -288,9 +336,10 @@ impl Variant for RegionSum {
                     }
 
                     let remaining_sum = sum - known_sum;
-                    let min_val =
-                        1.max(remaining_sum.saturating_sub((remaining_cells - 1) as u8 * 9));
-                    let max_val = 9.min(remaining_sum.saturating_sub((remaining_cells - 1) as u8));
+                    let min_val = 1
+                        .max(remaining_sum.saturating_sub((remaining_cells - 1) as u8 * max_digit));
+                    let max_val =
+                        max_digit.min(remaining_sum.saturating_sub((remaining_cells - 1) as u8));
                     for v in min_val..=max_val {
                         range.insert(v);
                     }
@@ -313,13 +362,11 @@ impl Variant for RegionSum {
     }
 }
 
+/// Numbers the segments by the boxes of a classic grid, as the line doesn't know its grid.
 impl Display for RegionSum {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut box_cell_entries: Vec<(&usize, &Vec<(usize, usize)>)> =
-            self.box_cells.iter().collect();
-        box_cell_entries.sort_by_key(|(box_num, _)| *box_num);
         let mut output = String::from("Region Sum Line:");
-        for (box_num, cells) in box_cell_entries.iter() {
+        for (box_num, cells) in self.segments(GridSize::CLASSIC).iter() {
             output.push_str(&format!(" region {box_num}: ["));
             output.push_str(
                 cells
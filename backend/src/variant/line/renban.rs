@@ -6,11 +6,14 @@ use std::{
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    SudokuGrid, SudokuVariant,
+    GridSize, SudokuGrid, SudokuVariant,
+    candidates::{CandidateGrid, DigitSet},
+    cnf::Cnf,
     file_parser::parse_positions,
     variant::{
-        Variant,
+        Breach, Eliminations, Variant, domain,
         error::{PossibilityResult, VariantContradiction},
+        repeats, unsupported,
     },
 };
 
@@ -72,20 +75,40 @@ impl Variant for Renban {
         if span > n {
             return false;
         }
-        if max(1, max_current - n + 1) > min(9 - n + 1, min_current) {
+        let max_digit = grid.size().max_digit() as i8;
+        if max(1, max_current - n + 1) > min(max_digit - n + 1, min_current) {
             return false;
         }
 
         true
     }
 
-    fn constrained_cells(&self) -> Vec<(usize, usize)> {
+    fn constrained_cells(&self, _size: GridSize) -> Vec<(usize, usize)> {
         self.cells.clone()
     }
 
+    /// One helper variable per run of consecutive digits the line could hold: some run is
+    /// picked, and a picked run rules out every digit outside it. The digits are kept apart by
+    /// `seen_cells`.
+    fn encode(&self, cnf: &mut Cnf) {
+        let line_len = self.cells.len() as u8;
+        let max_digit = cnf.size().max_digit();
+        let mut runs = Vec::new();
+        for start in 1..=(max_digit + 1).saturating_sub(line_len) {
+            let run = cnf.new_variable();
+            for &cell in &self.cells {
+                for digit in (1..=max_digit).filter(|d| !(start..start + line_len).contains(d)) {
+                    cnf.add_clause([-run, -cnf.cell(cell, digit)]);
+                }
+            }
+            runs.push(run);
+        }
+        cnf.add_clause(runs);
+    }
+
     fn validate_solution(&self, grid: &crate::SudokuGrid) -> bool {
         let mut values = HashSet::new();
-        let mut min_val = 9;
+        let mut min_val = u8::MAX;
         let mut max_val = 1;
 
         for &(row, col) in &self.cells {
@@ -106,6 +129,56 @@ impl Variant for Renban {
         HashSet::from_iter(min_val..=max_val) == values
     }
 
+    fn violations(&self, grid: &crate::SudokuGrid) -> Vec<Breach> {
+        let mut breaches = repeats(grid, &self.cells, "Renban line");
+        let mut values: Vec<u8> = self
+            .cells
+            .iter()
+            .map(|&(r, c)| grid.get_cell(r, c))
+            .collect();
+        values.sort_unstable();
+        let consecutive = values.windows(2).all(|w| w[0] + 1 == w[1]);
+        if breaches.is_empty() && !values.contains(&0) && !consecutive {
+            let values = values
+                .iter()
+                .map(u8::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            let message = format!("Renban line holds {values}, which aren't consecutive");
+            breaches.push((self.cells.clone(), message));
+        }
+        breaches
+    }
+
+    fn seen_cells(&self, _size: GridSize, row: usize, col: usize) -> Vec<(usize, usize)> {
+        if !self.cells.contains(&(row, col)) {
+            return Vec::new();
+        }
+        self.cells
+            .iter()
+            .copied()
+            .filter(|&cell| cell != (row, col))
+            .collect()
+    }
+
+    /// A renban as long as the grid is wide is a run of every digit, so it holds each once;
+    /// shorter runs are left to `all_different_groups`.
+    fn houses(&self, size: GridSize) -> Vec<Vec<(usize, usize)>> {
+        if self.cells.len() == size.side() {
+            vec![self.cells.clone()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn all_different_groups(&self, size: GridSize) -> Vec<Vec<(usize, usize)>> {
+        if self.cells.len() < size.side() {
+            vec![self.cells.clone()]
+        } else {
+            Vec::new()
+        }
+    }
+
     fn get_possibilities(&self, grid: &SudokuGrid) -> PossibilityResult {
         let used: HashSet<u8> = self
             .cells
@@ -131,7 +204,7 @@ impl Variant for Renban {
 
         // Generate all valid renban ranges of required length
         let mut valid_sets: Vec<HashSet<u8>> = Vec::new();
-        for start in 1..=(10 - line_len) {
+        for start in 1..=(grid.size().max_digit() + 1).saturating_sub(line_len) {
             let candidate: HashSet<u8> = (start..start + line_len).collect();
             if used.is_subset(&candidate) {
                 valid_sets.push(candidate);
@@ -170,6 +243,35 @@ impl Variant for Renban {
         }
         Ok(possibilities)
     }
+
+    /// Keeps the candidates inside a run of consecutive digits that every cell has a candidate
+    /// in, and whose every digit is a candidate of some cell.
+    fn propagate(
+        &self,
+        grid: &SudokuGrid,
+        candidates: &CandidateGrid,
+    ) -> Result<Eliminations, VariantContradiction> {
+        let domains: Vec<DigitSet> = self
+            .cells
+            .iter()
+            .map(|&cell| domain(grid, candidates, cell))
+            .collect();
+        let all = domains
+            .iter()
+            .fold(DigitSet::EMPTY, |acc, &digits| acc | digits);
+        let line_len = self.cells.len() as u8;
+
+        let mut runs = DigitSet::EMPTY;
+        for start in 1..=(grid.size().max_digit() + 1).saturating_sub(line_len) {
+            let run: DigitSet = (start..start + line_len).collect();
+            if run.is_subset(all) && domains.iter().all(|&digits| !(digits & run).is_empty()) {
+                runs |= run;
+            }
+        }
+
+        let supported: Vec<DigitSet> = domains.iter().map(|&digits| digits & runs).collect();
+        unsupported(grid, candidates, &self.cells, &supported, "Renban")
+    }
 }
 
 impl std::fmt::Display for Renban {
@@ -186,7 +288,8 @@ impl std::fmt::Display for Renban {
 
 #[cfg(test)]
 mod tests {
-    use crate::{SudokuGrid, variant::Variant};
+    use crate::candidates::{CandidateGrid, DigitSet};
+    use crate::{GridSize, SudokuGrid, variant::Variant};
 
     use super::Renban;
 
@@ -373,6 +476,19 @@ mod tests {
         assert!(renban.is_valid(&grid, 0, 3, 2), "Should be valid proposal");
     }
 
+    #[test]
+    fn test_valid_proposal_on_a_16x16_grid() {
+        let renban = Renban::new(vec![(0, 0), (0, 1), (0, 2), (0, 3)]);
+        let mut grid = SudokuGrid::with_size(GridSize::for_side(16).unwrap());
+        grid.set_cell(0, 1, 14);
+        assert!(renban.is_valid(&grid, 0, 0, 16), "Should be valid proposal");
+        assert!(renban.is_valid(&grid, 0, 2, 13), "Should be valid proposal");
+        assert!(
+            !renban.is_valid(&grid, 0, 2, 10),
+            "Should be invalid proposal"
+        );
+    }
+
     #[test]
     fn test_invalid_proposal_incomplete() {
         let renban = Renban::new(vec![(0, 0), (0, 1), (0, 2), (0, 3)]);
@@ -461,4 +577,22 @@ mod tests {
 
         assert!(renban.is_valid(&grid, 0, 2, 2));
     }
+
+    #[test]
+    fn test_propagate_uses_candidates() {
+        let renban = Renban::new(vec![(0, 0), (0, 1), (0, 2)]);
+        let grid = SudokuGrid::empty();
+        let mut candidates = CandidateGrid::new();
+        candidates.set(0, 0, DigitSet::from_digits(&[1, 2]));
+        candidates.set(0, 1, GridSize::CLASSIC.digits());
+        candidates.set(0, 2, DigitSet::from_digits(&[2, 3, 4, 9]));
+
+        let mut eliminations = renban.propagate(&grid, &candidates).unwrap();
+        eliminations.sort_unstable();
+
+        // Only the runs 1-3 and 2-4 fit every cell
+        let mut expected: Vec<_> = (5..=9).map(|d| ((0, 1), d)).collect();
+        expected.push(((0, 2), 9));
+        assert_eq!(eliminations, expected);
+    }
 }
@@ -3,11 +3,15 @@ use std::collections::{BTreeMap, HashMap};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    SudokuGrid, SudokuVariant,
+    GridSize, SudokuGrid, SudokuVariant,
+    candidates::{CandidateGrid, DigitSet},
+    cnf::Cnf,
     file_parser::parse_positions,
+    solver::cell_name,
     variant::{
-        Variant,
+        Breach, Eliminations, Variant, domain,
         error::{PossibilityResult, VariantContradiction},
+        unsupported,
     },
 };
 
@@ -39,7 +43,10 @@ impl Variant for Thermometer {
             None => return true, // If (row, col) is not on the thermometer, just pass
         };
         let min_val = (idx + 1) as u8;
-        let max_val = (9 - (self.length - 1 - idx)) as u8;
+        let max_val = grid
+            .size()
+            .max_digit()
+            .saturating_sub((self.length - 1 - idx) as u8);
 
         if value < min_val || value > max_val {
             return false;
@@ -78,10 +85,33 @@ impl Variant for Thermometer {
         values.windows(2).all(|w| w[0] < w[1])
     }
 
-    fn constrained_cells(&self) -> Vec<(usize, usize)> {
+    fn violations(&self, grid: &crate::SudokuGrid) -> Vec<Breach> {
+        let mut breaches = Vec::new();
+        for window in self.cells.windows(2) {
+            let (a, b) = (window[0], window[1]);
+            let (val1, val2) = (grid.get_cell(a.0, a.1), grid.get_cell(b.0, b.1));
+            if val1 != 0 && val2 != 0 && val1 >= val2 {
+                let message = format!(
+                    "Thermometer holds {val1} at {} then {val2} at {}",
+                    cell_name(a),
+                    cell_name(b)
+                );
+                breaches.push((vec![a, b], message));
+            }
+        }
+        breaches
+    }
+
+    fn constrained_cells(&self, _size: GridSize) -> Vec<(usize, usize)> {
         self.cells.clone()
     }
 
+    fn encode(&self, cnf: &mut Cnf) {
+        for window in self.cells.windows(2) {
+            cnf.forbid_pairs(window[0], window[1], |a, b| a < b);
+        }
+    }
+
     fn get_possibilities(&self, grid: &SudokuGrid) -> PossibilityResult {
         // Gather known values with their positions (index on the thermometer)
         let mut known_cells = BTreeMap::new();
@@ -115,7 +145,11 @@ impl Variant for Thermometer {
                 .range(i + 1..) // all after i
                 .next()
                 .map(|(&idx, &val)| val - (idx - i) as u8)
-                .unwrap_or(9 - (self.length - i - 1) as u8);
+                .unwrap_or(
+                    grid.size()
+                        .max_digit()
+                        .saturating_sub((self.length - i - 1) as u8),
+                );
 
             let vals = if min_val <= max_val {
                 (min_val..=max_val).collect()
@@ -130,6 +164,45 @@ impl Variant for Thermometer {
         }
         Ok(possibilities)
     }
+
+    /// Each cell must be above the smallest digit the cell before it can hold, and below the
+    /// largest digit the cell after it can hold.
+    fn propagate(
+        &self,
+        grid: &SudokuGrid,
+        candidates: &CandidateGrid,
+    ) -> Result<Eliminations, VariantContradiction> {
+        let domains: Vec<DigitSet> = self
+            .cells
+            .iter()
+            .map(|&cell| domain(grid, candidates, cell))
+            .collect();
+        let mut lowest = vec![None; domains.len()];
+        let mut floor = 0;
+        for (i, digits) in domains.iter().enumerate() {
+            lowest[i] = digits.iter().find(|&d| d > floor);
+            floor = lowest[i].unwrap_or(u8::MAX);
+        }
+        let mut highest = vec![None; domains.len()];
+        let mut ceiling = u8::MAX;
+        for (i, digits) in domains.iter().enumerate().rev() {
+            highest[i] = digits.iter().filter(|&d| d < ceiling).last();
+            ceiling = highest[i].unwrap_or(0);
+        }
+
+        let supported: Vec<DigitSet> = domains
+            .iter()
+            .zip(lowest.iter().zip(&highest))
+            .map(|(digits, bounds)| match bounds {
+                (Some(low), Some(high)) => digits
+                    .iter()
+                    .filter(|d| (*low..=*high).contains(d))
+                    .collect(),
+                _ => DigitSet::EMPTY,
+            })
+            .collect();
+        unsupported(grid, candidates, &self.cells, &supported, "Thermometer")
+    }
 }
 
 impl std::fmt::Display for Thermometer {
@@ -233,7 +306,45 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_is_valid_on_a_16x16_grid() {
+        let grid = SudokuGrid::with_size(GridSize::for_side(16).unwrap());
+        let thermometer = Thermometer::new((0..12).map(|col| (0, col)).collect());
+        assert!(thermometer.is_valid(&grid, 0, 0, 5));
+        assert!(!thermometer.is_valid(&grid, 0, 0, 6));
+        assert!(thermometer.is_valid(&grid, 0, 11, 16));
+        assert!(!thermometer.is_valid(&grid, 0, 11, 11));
+
+        // Too long to fit the digits of a classic grid
+        assert!(!thermometer.is_valid(&SudokuGrid::empty(), 0, 0, 1));
+    }
+
     fn create_thermometer() -> Thermometer {
         Thermometer::new(vec![(0, 1), (0, 2), (0, 3), (0, 4)])
     }
+
+    #[test]
+    fn test_propagate_uses_candidates() {
+        let grid = SudokuGrid::empty();
+        let thermometer = create_thermometer();
+        let mut candidates = CandidateGrid::new();
+        for col in 1..=4 {
+            candidates.set(0, col, GridSize::CLASSIC.digits());
+        }
+        candidates.set(0, 2, DigitSet::from_digits(&[5, 6]));
+
+        let eliminations = thermometer.propagate(&grid, &candidates).unwrap();
+
+        let kept = |col: usize| -> Vec<u8> {
+            candidates
+                .get(0, col)
+                .iter()
+                .filter(|&d| !eliminations.contains(&((0, col), d)))
+                .collect()
+        };
+        assert_eq!(kept(1), vec![1, 2, 3, 4, 5]);
+        assert_eq!(kept(2), vec![5, 6]);
+        assert_eq!(kept(3), vec![6, 7, 8]);
+        assert_eq!(kept(4), vec![7, 8, 9]);
+    }
 }
@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    GridSize, SudokuGrid, SudokuVariant,
+    candidates::{CandidateGrid, DigitSet},
+    cnf::Cnf,
+    file_parser::parse_positions,
+    solver::cell_list,
+    variant::{
+        Breach, Eliminations, Variant, VariantContradiction, domain, error::PossibilityResult,
+        unsupported,
+    },
+};
+
+/// Cells that must sum to a given number. Unlike a Killer cage, digits may repeat, unless the
+/// cells share a house or another variant keeps them apart.
+///
+/// The solver derives these from Killer cages by the rule of 45, when the cells left over don't
+/// all see each other.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CellSum {
+    cells: Vec<(usize, usize)>,
+    total: u8,
+}
+
+impl CellSum {
+    pub fn new(cells: Vec<(usize, usize)>, sum: u8) -> Self {
+        CellSum { cells, total: sum }
+    }
+
+    pub fn cells(&self) -> &[(usize, usize)] {
+        &self.cells
+    }
+
+    /// The value the cells sum to.
+    pub fn sum(&self) -> u8 {
+        self.total
+    }
+
+    /// Parses a string into a `CellSum` `SudokuVariant`, of the same form as a Killer cage:
+    /// Sum: ([cells]): sum
+    /// e.g. "Sum: ((0, 8), (1, 0)): 11"
+    pub fn parse(data: &str) -> Option<SudokuVariant> {
+        let parts: Vec<&str> = data.split(':').collect();
+        if parts.len() != 2 {
+            return None;
+        }
+        let cells = parse_positions(parts[0].trim()).ok()?;
+        let sum = parts[1].trim().parse().ok()?;
+        Some(SudokuVariant::CellSum(CellSum::new(cells, sum)))
+    }
+}
+
+impl Variant for CellSum {
+    fn is_valid(&self, grid: &SudokuGrid, row: usize, col: usize, value: u8) -> bool {
+        if !self.cells.contains(&(row, col)) {
+            return true;
+        }
+        let (sum, empty) = tally(self.cells.iter().map(|&(r, c)| {
+            if (r, c) == (row, col) {
+                value
+            } else {
+                grid.get_cell(r, c)
+            }
+        }));
+        let max_digit = usize::from(grid.size().max_digit());
+        let total = usize::from(self.total);
+        // The empty cells hold at least 1 and at most the largest digit each
+        sum + empty <= total && total <= sum + empty * max_digit
+    }
+
+    fn validate_solution(&self, grid: &SudokuGrid) -> bool {
+        let (sum, empty) = tally(self.cells.iter().map(|&(r, c)| grid.get_cell(r, c)));
+        empty == 0 && sum == usize::from(self.total)
+    }
+
+    fn violations(&self, grid: &SudokuGrid) -> Vec<Breach> {
+        let (sum, empty) = tally(self.cells.iter().map(|&(r, c)| grid.get_cell(r, c)));
+        if empty > 0 || sum == usize::from(self.total) {
+            return Vec::new();
+        }
+        let message = format!(
+            "{} sum to {sum}, expected {}",
+            cell_list(&self.cells),
+            self.total
+        );
+        vec![(self.cells.clone(), message)]
+    }
+
+    fn constrained_cells(&self, _size: GridSize) -> Vec<(usize, usize)> {
+        self.cells.clone()
+    }
+
+    fn encode(&self, cnf: &mut Cnf) {
+        let sums = cnf.sum(&self.cells, usize::from(self.total));
+        for (total, &literal) in sums.iter().enumerate() {
+            if let Some(literal) = literal
+                && total != usize::from(self.total)
+            {
+                cnf.add_clause([-literal]);
+            }
+        }
+    }
+
+    fn get_possibilities(&self, grid: &SudokuGrid) -> PossibilityResult {
+        let (sum, empty) = tally(self.cells.iter().map(|&(r, c)| grid.get_cell(r, c)));
+        let max_digit = grid.size().max_digit();
+        let mut possibilities = HashMap::new();
+        for &(r, c) in &self.cells {
+            let values = match grid.get_cell(r, c) {
+                // Whatever is left once this cell holds `digit` must fit in the other empty cells
+                0 => (1..=max_digit)
+                    .filter(|&digit| {
+                        let rest = usize::from(self.total).checked_sub(sum + usize::from(digit));
+                        rest.is_some_and(|rest| {
+                            empty - 1 <= rest && rest <= (empty - 1) * usize::from(max_digit)
+                        })
+                    })
+                    .collect(),
+                value => vec![value],
+            };
+            possibilities.insert((r, c), values);
+        }
+        Ok(possibilities)
+    }
+
+    /// Keeps the candidates used by some way of filling the cells, each from its candidates,
+    /// that adds up to the total.
+    fn propagate(
+        &self,
+        grid: &SudokuGrid,
+        candidates: &CandidateGrid,
+    ) -> Result<Eliminations, VariantContradiction> {
+        let domains: Vec<DigitSet> = self
+            .cells
+            .iter()
+            .map(|&cell| domain(grid, candidates, cell))
+            .collect();
+        let total = usize::from(self.total);
+
+        // Which sums the first `i` cells can reach
+        let mut reachable = vec![vec![false; total + 1]];
+        reachable[0][0] = true;
+        for digits in &domains {
+            let mut next = vec![false; total + 1];
+            for sum in (0..=total).filter(|&sum| reachable[reachable.len() - 1][sum]) {
+                for digit in digits.iter() {
+                    if let Some(slot) = next.get_mut(sum + usize::from(digit)) {
+                        *slot = true;
+                    }
+                }
+            }
+            reachable.push(next);
+        }
+
+        // Walking back from the total, which of those sums can be completed to it, and the
+        // digits that do so
+        let mut completable = vec![false; total + 1];
+        completable[total] = true;
+        let mut supported = vec![DigitSet::EMPTY; self.cells.len()];
+        for (i, digits) in domains.iter().enumerate().rev() {
+            let mut before = vec![false; total + 1];
+            for sum in (0..=total).filter(|&sum| reachable[i][sum]) {
+                for digit in digits.iter() {
+                    if completable.get(sum + usize::from(digit)) == Some(&true) {
+                        before[sum] = true;
+                        supported[i].insert(digit);
+                    }
+                }
+            }
+            completable = before;
+        }
+        unsupported(grid, candidates, &self.cells, &supported, "CellSum")
+    }
+}
+
+/// The sum of the digits among `values` and how many of them are empty.
+fn tally(values: impl Iterator<Item = u8>) -> (usize, usize) {
+    values.fold((0, 0), |(sum, empty), value| match value {
+        0 => (sum, empty + 1),
+        digit => (sum + usize::from(digit), empty),
+    })
+}
+
+impl std::fmt::Display for CellSum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cell_str = self
+            .cells
+            .iter()
+            .map(|&(r, c)| format!("({r}, {c})"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, "Cell Sum [{cell_str}] Sum = {}", self.total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digits_may_repeat() {
+        let mut grid = SudokuGrid::empty();
+        grid.set_cell(0, 8, 9);
+        let sum = CellSum::new(vec![(0, 8), (1, 0)], 18);
+
+        assert!(sum.is_valid(&grid, 1, 0, 9));
+        assert!(!sum.is_valid(&grid, 1, 0, 8));
+        grid.set_cell(1, 0, 9);
+        assert!(sum.validate_solution(&grid));
+        assert!(sum.violations(&grid).is_empty());
+    }
+
+    #[test]
+    fn reports_the_wrong_sum() {
+        let mut grid = SudokuGrid::empty();
+        grid.set_cell(0, 8, 4);
+        grid.set_cell(1, 0, 4);
+        let sum = CellSum::new(vec![(0, 8), (1, 0)], 9);
+
+        assert!(!sum.validate_solution(&grid));
+        assert_eq!(
+            sum.violations(&grid),
+            vec![(
+                vec![(0, 8), (1, 0)],
+                String::from("r1c9 and r2c1 sum to 8, expected 9")
+            )]
+        );
+    }
+
+    #[test]
+    fn possibilities_fit_the_total() {
+        let mut grid = SudokuGrid::empty();
+        grid.set_cell(0, 0, 2);
+        let sum = CellSum::new(vec![(0, 0), (4, 4), (8, 8)], 6);
+
+        let possibilities = sum.get_possibilities(&grid).unwrap();
+
+        assert_eq!(possibilities[&(0, 0)], vec![2]);
+        assert_eq!(possibilities[&(4, 4)], vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn propagates_through_partner_candidates() {
+        let grid = SudokuGrid::empty();
+        let sum = CellSum::new(vec![(0, 8), (1, 0)], 10);
+        let mut candidates = CandidateGrid::new();
+        candidates.set(0, 8, DigitSet::from_digits(&[1, 5]));
+        candidates.set(1, 0, GridSize::CLASSIC.digits());
+
+        let eliminations = sum.propagate(&grid, &candidates).unwrap();
+
+        // r2c1 needs a 9 or, repeating the digit, a 5
+        let kept: Vec<u8> = (1..=9)
+            .filter(|&d| !eliminations.contains(&((1, 0), d)))
+            .collect();
+        assert_eq!(kept, vec![5, 9]);
+        assert!(eliminations.iter().all(|&(cell, _)| cell == (1, 0)));
+    }
+
+    #[test]
+    fn unreachable_total_is_a_contradiction() {
+        let grid = SudokuGrid::empty();
+        let sum = CellSum::new(vec![(0, 8), (1, 0)], 19);
+        let mut candidates = CandidateGrid::new();
+        candidates.set(0, 8, GridSize::CLASSIC.digits());
+        candidates.set(1, 0, GridSize::CLASSIC.digits());
+
+        assert!(sum.propagate(&grid, &candidates).is_err());
+    }
+
+    #[test]
+    fn parses_like_a_killer_cage() {
+        assert_eq!(
+            SudokuVariant::parse("Sum: ((0, 8), (1, 0)): 11"),
+            Some(SudokuVariant::CellSum(CellSum::new(
+                vec![(0, 8), (1, 0)],
+                11
+            )))
+        );
+    }
+}
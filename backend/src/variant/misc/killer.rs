@@ -4,9 +4,14 @@ use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    SudokuGrid, SudokuVariant,
+    GridSize, SudokuGrid, SudokuVariant,
+    candidates::{Candidate, CandidateGrid, DigitSet},
+    cnf::Cnf,
     file_parser::parse_positions,
-    variant::{Variant, error::PossibilityResult},
+    variant::{
+        Breach, Eliminations, Variant, VariantContradiction, domain, error::PossibilityResult,
+        repeats, unsupported,
+    },
 };
 
 /// A Killer cage where a number of cells must sum to a given number, and there must be no repeated values in the cage.
@@ -14,19 +19,21 @@ use crate::{
 pub struct KillerCage {
     cells: Vec<(usize, usize)>,
     total: u8,
-    possible_values: HashSet<u8>,
 }
 
 impl KillerCage {
     ///  Creates a Killer Cage comprising the given cells, and summing to the given value.
     pub fn new(cells: Vec<(usize, usize)>, sum: u8) -> Self {
-        let mut cage = KillerCage {
-            cells,
-            total: sum,
-            possible_values: HashSet::new(),
-        };
-        cage.set_possible_values();
-        cage
+        KillerCage { cells, total: sum }
+    }
+
+    pub fn cells(&self) -> &[(usize, usize)] {
+        &self.cells
+    }
+
+    /// The value the cage sums to.
+    pub fn sum(&self) -> u8 {
+        self.total
     }
 
     /// Parses a string into an `Killer` `SudokuVariant`.
@@ -50,9 +57,9 @@ impl KillerCage {
         Some(SudokuVariant::Killer(KillerCage::new(cells, sum)))
     }
 
-    // Calculates the possible values for the given killer cage
-    fn set_possible_values(&mut self) {
-        let digits = (1u8..=9).collect::<Vec<_>>();
+    /// The digits up to `max_digit` used by some set of distinct digits filling the cage.
+    fn possible_values(&self, max_digit: u8) -> HashSet<u8> {
+        let digits = (1..=max_digit).collect::<Vec<_>>();
         let mut result = HashSet::new();
 
         // Recursive helper to generate combinations
@@ -100,7 +107,12 @@ impl KillerCage {
             &mut Vec::new(),
             &mut result,
         );
-        self.possible_values = result;
+        result
+    }
+
+    /// Whether the cage holds every digit of a grid of the given `size`.
+    fn is_house(&self, size: GridSize) -> bool {
+        self.cells.len() == size.side() && usize::from(self.total) == size.house_sum()
     }
 
     fn convert_hashmaps(
@@ -126,7 +138,10 @@ impl Variant for KillerCage {
             return true;
         }
 
-        if !self.possible_values.contains(&value) {
+        if !self
+            .possible_values(grid.size().max_digit())
+            .contains(&value)
+        {
             return false;
         }
 
@@ -172,10 +187,87 @@ impl Variant for KillerCage {
         sum == self.total
     }
 
-    fn constrained_cells(&self) -> Vec<(usize, usize)> {
+    fn violations(&self, grid: &crate::SudokuGrid) -> Vec<Breach> {
+        let mut breaches = repeats(grid, &self.cells, "Killer cage");
+        let values: Vec<u8> = self
+            .cells
+            .iter()
+            .map(|&(r, c)| grid.get_cell(r, c))
+            .collect();
+        let sum: u8 = values.iter().sum();
+        if !values.contains(&0) && sum != self.total {
+            let message = format!("Killer cage sums to {sum}, expected {}", self.total);
+            breaches.push((self.cells.clone(), message));
+        }
+        breaches
+    }
+
+    fn constrained_cells(&self, _size: GridSize) -> Vec<(usize, usize)> {
         self.cells.clone()
     }
 
+    fn encode(&self, cnf: &mut Cnf) {
+        let sums = cnf.sum(&self.cells, cnf.size().house_sum());
+        for (total, &literal) in sums.iter().enumerate() {
+            if let Some(literal) = literal
+                && total != usize::from(self.total)
+            {
+                cnf.add_clause([-literal]);
+            }
+        }
+    }
+
+    fn seen_cells(&self, _size: GridSize, row: usize, col: usize) -> Vec<(usize, usize)> {
+        if !self.cells.contains(&(row, col)) {
+            return Vec::new();
+        }
+        self.cells
+            .iter()
+            .copied()
+            .filter(|&cell| cell != (row, col))
+            .collect()
+    }
+
+    /// A cage as big as a row and summing to the same holds every digit, any other cage just
+    /// doesn't repeat one.
+    fn houses(&self, size: GridSize) -> Vec<Vec<(usize, usize)>> {
+        if self.is_house(size) {
+            vec![self.cells.clone()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn all_different_groups(&self, size: GridSize) -> Vec<Vec<(usize, usize)>> {
+        if self.is_house(size) {
+            Vec::new()
+        } else {
+            vec![self.cells.clone()]
+        }
+    }
+
+    /// A two-cell cage with a single combination {a, b} left: if one cell isn't a, the other is.
+    fn strong_links(&self, candidates: &CandidateGrid) -> Vec<(Candidate, Candidate)> {
+        let &[c1, c2] = &self.cells[..] else {
+            return Vec::new();
+        };
+        let (set1, set2) = (candidates.get(c1.0, c1.1), candidates.get(c2.0, c2.1));
+        let max_digit = candidates.size().max_digit();
+        let combos: Vec<(u8, u8)> = (1..=max_digit)
+            .filter_map(|a| {
+                let b = self.total.checked_sub(a)?;
+                (a < b && b <= max_digit).then_some((a, b))
+            })
+            .filter(|&(a, b)| {
+                (set1.contains(a) && set2.contains(b)) || (set1.contains(b) && set2.contains(a))
+            })
+            .collect();
+        match combos[..] {
+            [(a, b)] => vec![((c1, a), (c2, a)), ((c1, b), (c2, b))],
+            _ => Vec::new(),
+        }
+    }
+
     fn get_possibilities(&self, grid: &SudokuGrid) -> PossibilityResult {
         // 1. Gather curent values in the cage
         let mut possibilities: HashMap<(usize, usize), HashSet<u8>> = HashMap::new();
@@ -205,7 +297,9 @@ impl Variant for KillerCage {
 
         // 4. Generate all combinations of unique digits (not in used), of length empty_cells.len(),
         //    that sum to (self.total - current_sum)
-        let available: Vec<u8> = (1..=9).filter(|d| !used.contains(d)).collect();
+        let available: Vec<u8> = (1..=grid.size().max_digit())
+            .filter(|d| !used.contains(d))
+            .collect();
         let target_sum = self.total.saturating_sub(current_sum);
         let n = empty_cells.len();
 
@@ -250,6 +344,78 @@ impl Variant for KillerCage {
         // 6. Convert HashSet<u8> to Vec<u8> for output
         Ok(self.convert_hashmaps(possibilities))
     }
+
+    /// Keeps the candidates used by some way of filling the cage with distinct digits, each
+    /// from its cell's candidates, that adds up to the total.
+    fn propagate(
+        &self,
+        grid: &SudokuGrid,
+        candidates: &CandidateGrid,
+    ) -> Result<Eliminations, VariantContradiction> {
+        let domains: Vec<DigitSet> = self
+            .cells
+            .iter()
+            .map(|&cell| domain(grid, candidates, cell))
+            .collect();
+        // The digits used by the first `i` cells are a bitmask (bit `d` for digit `d`), which
+        // also fixes their sum. Only the sets some filling can reach are kept, as there are up
+        // to 2^17 of them on a 16x16 grid
+        let max_digit = grid.size().max_digit();
+        let sum_of = |used: u32| {
+            (1..=max_digit)
+                .filter(|d| used & (1 << d) != 0)
+                .map(usize::from)
+                .sum::<usize>()
+        };
+        let total = usize::from(self.total);
+
+        // Which sets of digits the first `i` cells can hold
+        let mut reachable = vec![HashSet::from([0u32])];
+        for digits in &domains {
+            let next: HashSet<u32> = reachable[reachable.len() - 1]
+                .iter()
+                .flat_map(|&used| digits.iter().map(move |d| (used, used | (1 << d))))
+                .filter(|&(used, next)| next != used && sum_of(next) <= total)
+                .map(|(_, next)| next)
+                .collect();
+            reachable.push(next);
+        }
+        // Which of those sets can be completed to the total
+        let mut completable = vec![HashSet::new(); self.cells.len() + 1];
+        completable[self.cells.len()] = reachable[self.cells.len()]
+            .iter()
+            .copied()
+            .filter(|&used| sum_of(used) == total)
+            .collect();
+        for (i, digits) in domains.iter().enumerate().rev() {
+            completable[i] = reachable[i]
+                .iter()
+                .copied()
+                .filter(|&used| {
+                    digits.iter().any(|d| {
+                        let next = used | (1 << d);
+                        next != used && completable[i + 1].contains(&next)
+                    })
+                })
+                .collect();
+        }
+
+        let supported: Vec<DigitSet> = domains
+            .iter()
+            .enumerate()
+            .map(|(i, digits)| {
+                digits
+                    .iter()
+                    .filter(|&d| {
+                        completable[i].iter().any(|&used| {
+                            used & (1 << d) == 0 && completable[i + 1].contains(&(used | (1 << d)))
+                        })
+                    })
+                    .collect()
+            })
+            .collect();
+        unsupported(grid, candidates, &self.cells, &supported, "KillerCage")
+    }
 }
 
 impl std::fmt::Display for KillerCage {
@@ -294,16 +460,26 @@ mod tests {
             for (idx, (cells, sum, expected_possible_values)) in tests.iter().enumerate() {
                 let cage = KillerCage::new(cells.clone(), *sum);
                 assert_eq!(
-                    cage.possible_values,
+                    cage.possible_values(9),
                     *expected_possible_values,
                     "Test {} failed. Expected possible values: {:?}. Got: {:?}",
                     idx + 1,
                     expected_possible_values,
-                    cage.possible_values
+                    cage.possible_values(9)
                 );
             }
         }
 
+        #[test]
+        fn test_possible_values_by_grid_size() {
+            let cage = KillerCage::new(vec![(0, 0), (0, 1)], 7);
+            assert_eq!(cage.possible_values(4), HashSet::from([3, 4]));
+            assert_eq!(cage.possible_values(6), HashSet::from([1, 2, 3, 4, 5, 6]));
+            let cage = KillerCage::new(vec![(0, 0), (0, 1)], 30);
+            assert_eq!(cage.possible_values(16), HashSet::from([14, 16]));
+            assert!(cage.possible_values(9).is_empty());
+        }
+
         #[test]
         fn test_possible_values_sum_15_three_cells() {
             let cage = KillerCage::new(vec![(0, 0), (0, 1), (0, 2)], 15);
@@ -324,12 +500,12 @@ mod tests {
                 })
                 .copied()
                 .collect();
-            assert_eq!(cage.possible_values, expected);
+            assert_eq!(cage.possible_values(9), expected);
         }
     }
 
     mod is_valid {
-        use crate::{KillerCage, SudokuGrid, variant::Variant};
+        use crate::{GridSize, KillerCage, SudokuGrid, variant::Variant};
 
         #[test]
         fn test_value_not_in_possible_values() {
@@ -338,6 +514,15 @@ mod tests {
             assert!(!cage.is_valid(&grid, 0, 0, 5));
         }
 
+        #[test]
+        fn test_values_above_nine() {
+            let cage = KillerCage::new(vec![(0, 0), (0, 1)], 31);
+            let grid = SudokuGrid::with_size(GridSize::for_side(16).unwrap());
+            assert!(cage.is_valid(&grid, 0, 0, 16));
+            assert!(!cage.is_valid(&grid, 0, 0, 14));
+            assert!(!cage.is_valid(&SudokuGrid::empty(), 0, 0, 9));
+        }
+
         #[test]
         fn test_repeated_value_in_cage() {
             let mut grid = SudokuGrid::empty();
@@ -401,13 +586,35 @@ mod tests {
     }
 
     mod constrained_cells {
-        use crate::{KillerCage, variant::Variant};
+        use crate::{GridSize, KillerCage, variant::Variant};
 
         #[test]
         fn test_constrained_cells() {
             let cage = KillerCage::new(vec![(1, 2), (3, 4)], 10);
             let expected = vec![(1, 2), (3, 4)];
-            assert_eq!(cage.constrained_cells(), expected);
+            assert_eq!(cage.constrained_cells(GridSize::CLASSIC), expected);
+        }
+    }
+
+    mod houses {
+        use crate::{GridSize, KillerCage, variant::Variant};
+
+        #[test]
+        fn full_cage_is_a_house() {
+            let cells: Vec<(usize, usize)> = (0..9).map(|i| (i / 3, 3 + i % 3)).collect();
+            let cage = KillerCage::new(cells.clone(), 45);
+            assert_eq!(cage.houses(GridSize::CLASSIC), vec![cells]);
+            assert!(cage.all_different_groups(GridSize::CLASSIC).is_empty());
+        }
+
+        #[test]
+        fn small_cage_is_a_group() {
+            let cage = KillerCage::new(vec![(1, 2), (3, 4)], 10);
+            assert!(cage.houses(GridSize::CLASSIC).is_empty());
+            assert_eq!(
+                cage.all_different_groups(GridSize::CLASSIC),
+                vec![vec![(1, 2), (3, 4)]]
+            );
         }
     }
 
@@ -507,4 +714,55 @@ mod tests {
             assert!(result.get(&(0, 2)).unwrap().is_empty());
         }
     }
+
+    mod propagate {
+        use super::KillerCage;
+        use crate::candidates::{CandidateGrid, DigitSet};
+        use crate::{GridSize, SudokuGrid, variant::Variant};
+
+        #[test]
+        fn narrows_with_partner_candidates() {
+            let grid = SudokuGrid::empty();
+            let cage = KillerCage::new(vec![(0, 0), (0, 1)], 10);
+            let mut candidates = CandidateGrid::new();
+            candidates.set(0, 0, DigitSet::from_digits(&[1, 2]));
+            candidates.set(0, 1, GridSize::CLASSIC.digits());
+
+            let mut eliminations = cage.propagate(&grid, &candidates).unwrap();
+            eliminations.sort_unstable();
+
+            let expected: Vec<_> = (1..=7).map(|d| ((0, 1), d)).collect();
+            assert_eq!(eliminations, expected);
+        }
+
+        #[test]
+        fn respects_placed_digits() {
+            let mut grid = SudokuGrid::empty();
+            grid.set_cell(0, 0, 4);
+            let cage = KillerCage::new(vec![(0, 0), (0, 1), (0, 2)], 12);
+            let mut candidates = CandidateGrid::new();
+            candidates.set(0, 1, DigitSet::from_digits(&[1, 4, 5, 6]));
+            candidates.set(0, 2, GridSize::CLASSIC.digits());
+
+            let eliminations = cage.propagate(&grid, &candidates).unwrap();
+
+            // r1c2 can't repeat the 4, and 1, 5 or 6 leave 7, 3 or 2 for r1c3
+            assert!(eliminations.contains(&((0, 1), 4)));
+            let kept: Vec<u8> = (1..=9)
+                .filter(|&d| !eliminations.contains(&((0, 2), d)))
+                .collect();
+            assert_eq!(kept, vec![2, 3, 7]);
+        }
+
+        #[test]
+        fn no_combination_is_a_contradiction() {
+            let grid = SudokuGrid::empty();
+            let cage = KillerCage::new(vec![(0, 0), (0, 1)], 3);
+            let mut candidates = CandidateGrid::new();
+            candidates.set(0, 0, DigitSet::from_digits(&[3, 4]));
+            candidates.set(0, 1, DigitSet::from_digits(&[3, 4]));
+
+            assert!(cage.propagate(&grid, &candidates).is_err());
+        }
+    }
 }
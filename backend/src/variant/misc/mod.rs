@@ -1,7 +1,9 @@
+mod cell_sum;
 mod killer;
 mod quadruple_circles;
 mod shaded;
 
+pub use cell_sum::CellSum;
 pub use killer::KillerCage;
 pub use quadruple_circles::QuadrupleCircle;
 pub use shaded::Shaded;
@@ -1,12 +1,15 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    SudokuGrid, SudokuVariant,
+    GridSize, SudokuGrid, SudokuVariant,
+    cnf::Cnf,
     file_parser::parse_positions,
+    solver::{cell_list, cell_name},
     variant::{
-        ALL_POSSIBILITIES, Variant,
+        Breach, Variant,
         error::{PossibilityResult, VariantContradiction},
     },
 };
@@ -115,10 +118,99 @@ impl Variant for QuadrupleCircle {
         self.required.iter().all(|&d| values.contains(&d))
     }
 
-    fn constrained_cells(&self) -> Vec<(usize, usize)> {
+    fn violations(&self, grid: &crate::SudokuGrid) -> Vec<Breach> {
+        let values: Vec<u8> = self
+            .cells
+            .iter()
+            .map(|&(r, c)| grid.get_cell(r, c))
+            .collect();
+        let at = cell_name(self.cells[0]);
+        if self.is_anti {
+            let holding: Vec<(usize, usize)> = self
+                .cells
+                .iter()
+                .copied()
+                .filter(|&(r, c)| self.required.contains(&grid.get_cell(r, c)))
+                .collect();
+            if holding.is_empty() {
+                return Vec::new();
+            }
+            let message = format!(
+                "Anti-quadruple circle at {at} holds a ruled out digit at {}",
+                cell_list(&holding)
+            );
+            return vec![(holding, message)];
+        }
+        if values.contains(&0) {
+            return Vec::new();
+        }
+        let mut missing = Vec::new();
+        for digit in self.required.iter().copied().unique() {
+            let required = self.required.iter().filter(|&&d| d == digit).count();
+            let held = values.iter().filter(|&&v| v == digit).count();
+            missing.extend(std::iter::repeat_n(digit, required.saturating_sub(held)));
+        }
+        if missing.is_empty() {
+            return Vec::new();
+        }
+        let missing = missing
+            .iter()
+            .map(u8::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        vec![(
+            self.cells.clone(),
+            format!("Quadruple circle at {at} is missing {missing}"),
+        )]
+    }
+
+    fn constrained_cells(&self, _size: GridSize) -> Vec<(usize, usize)> {
         self.cells.clone()
     }
 
+    /// A digit required `k` times can only be missing from `4 - k` of the cells, so every
+    /// `5 - k` of them hold it at least once.
+    fn encode(&self, cnf: &mut Cnf) {
+        for digit in self.required.iter().copied().unique() {
+            if self.is_anti {
+                for &cell in &self.cells {
+                    cnf.add_clause([-cnf.cell(cell, digit)]);
+                }
+                continue;
+            }
+            let times = self.required.iter().filter(|&&d| d == digit).count();
+            let size = (self.cells.len() + 1).saturating_sub(times);
+            for cells in self.cells.iter().combinations(size) {
+                let clause = cells
+                    .into_iter()
+                    .map(|&cell| cnf.cell(cell, digit))
+                    .collect_vec();
+                cnf.add_clause(clause);
+            }
+        }
+    }
+
+    /// Four different required digits fill the four cells, so none of them can repeat.
+    fn seen_cells(&self, size: GridSize, row: usize, col: usize) -> Vec<(usize, usize)> {
+        if self.all_different_groups(size).is_empty() || !self.cells.contains(&(row, col)) {
+            return Vec::new();
+        }
+        self.cells
+            .iter()
+            .copied()
+            .filter(|&cell| cell != (row, col))
+            .collect()
+    }
+
+    fn all_different_groups(&self, _size: GridSize) -> Vec<Vec<(usize, usize)>> {
+        let distinct: HashSet<u8> = self.required.iter().copied().collect();
+        if !self.is_anti && distinct.len() == 4 && self.cells.len() == 4 {
+            vec![self.cells.clone()]
+        } else {
+            Vec::new()
+        }
+    }
+
     fn get_possibilities(&self, grid: &SudokuGrid) -> PossibilityResult {
         // Map of all existing cell values surrounding the quadratic circle
         let cell_values: HashMap<(usize, usize), u8> = self
@@ -128,8 +220,8 @@ impl Variant for QuadrupleCircle {
             .collect();
 
         let known_digits_in_circle = cell_values
-            .iter()
-            .filter_map(|(_, v)| if v == &0 { None } else { Some(*v) })
+            .values()
+            .filter_map(|v| if v == &0 { None } else { Some(*v) })
             .collect::<Vec<_>>();
         let empty_cell_count = cell_values.iter().filter(|&(_, &v)| v == 0).count();
 
@@ -158,10 +250,10 @@ impl Variant for QuadrupleCircle {
         if !self.is_anti {
             // If there is no space to fit the required values, return early
             if empty_cell_count < missing_required_digits.len() {
-                return Err(VariantContradiction::Inconsistent {
+                Err(VariantContradiction::Inconsistent {
                     variant: "QuadrupleCircle",
                     reason: String::from("Not enough empty cells to fill required digits"),
-                });
+                })
             }
             // If there is only just space to fit the required values, return these
             else if empty_cell_count == missing_required_digits.len() {
@@ -169,7 +261,7 @@ impl Variant for QuadrupleCircle {
             }
             // If there is more than enough space, then the cells can be any value
             else {
-                insert_possibilities(ALL_POSSIBILITIES.to_vec())
+                insert_possibilities((1..=grid.size().max_digit()).collect())
             }
         } else {
             // Anti-Quadruple
@@ -187,10 +279,8 @@ impl Variant for QuadrupleCircle {
             }
             // Return a set of all values not including the required values
             insert_possibilities(
-                ALL_POSSIBILITIES
-                    .iter()
-                    .filter(|&v| !self.required.contains(v))
-                    .copied()
+                (1..=grid.size().max_digit())
+                    .filter(|v| !self.required.contains(v))
                     .collect::<Vec<_>>(),
             )
         }
@@ -332,3 +422,34 @@ mod get_possibilities {
         assert_eq!(result.get(&(2, 2)).unwrap(), &expected);
     }
 }
+
+#[cfg(test)]
+mod all_different_groups {
+    use super::*;
+
+    const CELLS: [(usize, usize); 4] = [(2, 2), (2, 3), (3, 2), (3, 3)];
+
+    #[test]
+    fn four_distinct_digits() {
+        let circle = QuadrupleCircle::new(CELLS.to_vec(), vec![1, 2, 3, 4], false);
+        assert_eq!(
+            circle.all_different_groups(GridSize::CLASSIC),
+            vec![CELLS.to_vec()]
+        );
+        assert_eq!(
+            circle.seen_cells(GridSize::CLASSIC, 2, 2),
+            vec![(2, 3), (3, 2), (3, 3)]
+        );
+    }
+
+    #[test]
+    fn repeated_or_missing_digits() {
+        for required in [vec![1, 1, 2, 3], vec![1, 2, 3]] {
+            let circle = QuadrupleCircle::new(CELLS.to_vec(), required, false);
+            assert!(circle.all_different_groups(GridSize::CLASSIC).is_empty());
+            assert!(circle.seen_cells(GridSize::CLASSIC, 2, 2).is_empty());
+        }
+        let anti = QuadrupleCircle::new(CELLS.to_vec(), vec![1, 2, 3, 4], true);
+        assert!(anti.all_different_groups(GridSize::CLASSIC).is_empty());
+    }
+}
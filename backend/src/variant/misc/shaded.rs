@@ -3,9 +3,11 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    SudokuGrid, SudokuVariant,
+    GridSize, SudokuGrid, SudokuVariant,
+    cnf::Cnf,
     file_parser::parse_positions,
-    variant::{Variant, VariantContradiction, error::PossibilityResult},
+    solver::cell_name,
+    variant::{Breach, Variant, VariantContradiction, error::PossibilityResult},
 };
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -34,35 +36,60 @@ impl Shaded {
 }
 
 impl Variant for Shaded {
-    fn is_valid(&self, _grid: &crate::SudokuGrid, row: usize, col: usize, value: u8) -> bool {
+    fn is_valid(&self, grid: &crate::SudokuGrid, row: usize, col: usize, value: u8) -> bool {
         if self.cell != (row, col) {
             return true;
         }
-        self.shape.digit_range().contains(&value)
+        self.shape.digit_range(grid.size()).contains(&value)
     }
 
     fn validate_solution(&self, grid: &crate::SudokuGrid) -> bool {
         self.shape
-            .digit_range()
+            .digit_range(grid.size())
             .contains(&grid.get_cell(self.cell.0, self.cell.1))
     }
 
-    fn constrained_cells(&self) -> Vec<(usize, usize)> {
+    fn violations(&self, grid: &crate::SudokuGrid) -> Vec<Breach> {
+        let value = grid.get_cell(self.cell.0, self.cell.1);
+        if value == 0 || self.shape.digit_range(grid.size()).contains(&value) {
+            return Vec::new();
+        }
+        let expected = match self.shape {
+            Shape::Circle => "an odd digit",
+            Shape::Square => "an even digit",
+        };
+        let message = format!(
+            "{} at {} holds {value}, expected {expected}",
+            self.shape,
+            cell_name(self.cell)
+        );
+        vec![(vec![self.cell], message)]
+    }
+
+    fn constrained_cells(&self, _size: GridSize) -> Vec<(usize, usize)> {
         vec![self.cell]
     }
 
+    fn encode(&self, cnf: &mut Cnf) {
+        let allowed = self.shape.digit_range(cnf.size());
+        for digit in (1..=cnf.size().max_digit()).filter(|d| !allowed.contains(d)) {
+            cnf.add_clause([-cnf.cell(self.cell, digit)]);
+        }
+    }
+
     fn get_possibilities(&self, grid: &SudokuGrid) -> PossibilityResult {
         let mut possibilities = HashMap::new();
         let value = grid.get_cell(self.cell.0, self.cell.1);
+        let digit_range = self.shape.digit_range(grid.size());
         if value == 0 {
-            possibilities.insert(self.cell, self.shape.digit_range());
-        } else if self.shape.digit_range().contains(&value) {
+            possibilities.insert(self.cell, digit_range);
+        } else if digit_range.contains(&value) {
             possibilities.insert(self.cell, vec![value]);
         } else {
             return Err(VariantContradiction::NoPossibilities {
                 cell: self.cell,
                 variant: "ShadedCell",
-                reason: format!("Cell must contain one of: {:?}", self.shape.digit_range()),
+                reason: format!("Cell must contain one of: {digit_range:?}"),
             });
         }
         Ok(possibilities)
@@ -95,11 +122,15 @@ impl Shape {
         }
     }
 
-    fn digit_range(&self) -> Vec<u8> {
-        match self {
-            Shape::Circle => vec![1, 3, 5, 7, 9],
-            Shape::Square => vec![2, 4, 6, 8],
-        }
+    /// The odd digits of a grid of the given `size` for a circle, the even ones for a square.
+    fn digit_range(&self, size: GridSize) -> Vec<u8> {
+        let remainder = match self {
+            Shape::Circle => 1,
+            Shape::Square => 0,
+        };
+        (1..=size.max_digit())
+            .filter(|d| d % 2 == remainder)
+            .collect()
     }
 }
 
@@ -8,7 +8,7 @@ pub use chess::King;
 pub use chess::Knight;
 pub use dot::KropkiDot;
 pub use dot::XVDot;
-pub use error::{PossibilityResult, VariantContradiction};
+pub use error::{PossibilityMap, PossibilityResult, VariantContradiction};
 pub use line::Arrow;
 pub use line::Diagonal;
 pub use line::Entropic;
@@ -17,21 +17,151 @@ pub use line::Nabner;
 pub use line::RegionSum;
 pub use line::Renban;
 pub use line::Thermometer;
+pub use misc::CellSum;
 pub use misc::KillerCage;
 pub use misc::QuadrupleCircle;
 pub use misc::Shaded;
 
-use crate::SudokuGrid;
+use crate::candidates::{Candidate, CandidateGrid, DigitSet};
+use crate::cnf::Cnf;
+use crate::solver::cell_list;
+use crate::{GridSize, SudokuGrid};
+
+/// Candidates a variant rules out, as returned by `Variant::propagate`.
+pub type Eliminations = Vec<Candidate>;
+
+/// The cells involved in a broken rule and what's wrong with them, as returned by
+/// `Variant::violations`.
+pub type Breach = (Vec<(usize, usize)>, String);
 
 pub trait Variant {
     /// Determines if the variant is valid, given the current state of the `grid`, assuming a proposed `value` is placed in the cell in (`row`, `col`).
     fn is_valid(&self, grid: &SudokuGrid, row: usize, col: usize, value: u8) -> bool;
-    /// Returns the list of cells affected by this variant.
-    fn constrained_cells(&self) -> Vec<(usize, usize)>;
+    /// Returns the list of cells affected by this variant on a grid of the given `size`.
+    fn constrained_cells(&self, size: GridSize) -> Vec<(usize, usize)>;
     /// Determines if the variant is valid for the proposed final grid.
     fn validate_solution(&self, grid: &SudokuGrid) -> bool;
+    /// Describes each way the digits placed in `grid` break the variant. Unlike `validate_solution`, empty cells are not a breach: parts of the rule are only checked once their cells are filled.
+    fn violations(&self, grid: &SudokuGrid) -> Vec<Breach>;
     /// Return all possible values (according to the variant's constraint rules) for all cells affected by the variant.
     fn get_possibilities(&self, grid: &SudokuGrid) -> PossibilityResult;
+    /// Returns the current candidates of the empty cells affected by the variant that it rules out, given what every cell can still hold.
+    /// Unless overridden, this only looks at the placed digits, through `get_possibilities`.
+    fn propagate(
+        &self,
+        grid: &SudokuGrid,
+        candidates: &CandidateGrid,
+    ) -> Result<Eliminations, VariantContradiction> {
+        Ok(eliminations_outside(
+            grid,
+            candidates,
+            self.get_possibilities(grid)?,
+        ))
+    }
+    /// Adds the variant's rules to `cnf` as clauses. Rules already given by `seen_cells` and `houses` are encoded for every variant, so variants made of nothing else needn't add anything.
+    fn encode(&self, _cnf: &mut Cnf) {}
+    /// Returns the cells that may not hold the same digit as (`row`, `col`) because of this variant, on top of its row, column and box.
+    fn seen_cells(&self, _size: GridSize, _row: usize, _col: usize) -> Vec<(usize, usize)> {
+        Vec::new()
+    }
+    /// Returns groups of cells that hold each digit of a grid of the given `size` exactly once because of this variant, like an extra row or box.
+    fn houses(&self, _size: GridSize) -> Vec<Vec<(usize, usize)>> {
+        Vec::new()
+    }
+    /// Returns groups of cells whose digits must all differ because of this variant, but which needn't hold every digit of a grid of the given `size`.
+    fn all_different_groups(&self, _size: GridSize) -> Vec<Vec<(usize, usize)>> {
+        Vec::new()
+    }
+    /// Returns pairs of current candidates that can't both be true because of this variant (weak links).
+    fn weak_links(&self, _candidates: &CandidateGrid) -> Vec<(Candidate, Candidate)> {
+        Vec::new()
+    }
+    /// Returns pairs of candidates of which at least one must be true because of this variant (strong links).
+    fn strong_links(&self, _candidates: &CandidateGrid) -> Vec<(Candidate, Candidate)> {
+        Vec::new()
+    }
+}
+
+/// The candidates of empty cells that aren't among the cells' `possibilities`.
+fn eliminations_outside(
+    grid: &SudokuGrid,
+    candidates: &CandidateGrid,
+    possibilities: PossibilityMap,
+) -> Eliminations {
+    let mut eliminations = Vec::new();
+    for ((row, col), values) in possibilities {
+        if grid.get_cell(row, col) == 0 {
+            let ruled_out = candidates.get(row, col) - DigitSet::from_digits(&values);
+            eliminations.extend(ruled_out.iter().map(|digit| ((row, col), digit)));
+        }
+    }
+    eliminations
+}
+
+/// A breach for each digit placed more than once among `cells`, reading e.g. "Row 3 repeats 5 at
+/// r3c1 and r3c6" for `what` = "Row 3".
+pub(crate) fn repeats(grid: &SudokuGrid, cells: &[(usize, usize)], what: &str) -> Vec<Breach> {
+    (1..=grid.size().max_digit())
+        .filter_map(|digit| {
+            let holding: Vec<(usize, usize)> = cells
+                .iter()
+                .copied()
+                .filter(|&(row, col)| grid.get_cell(row, col) == digit)
+                .collect();
+            let message = format!("{what} repeats {digit} at {}", cell_list(&holding));
+            (holding.len() > 1).then_some((holding, message))
+        })
+        .collect()
 }
 
-pub const ALL_POSSIBILITIES: [u8; 9] = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+/// The digits `cell` can hold: its placed digit, or else its candidates.
+fn domain(grid: &SudokuGrid, candidates: &CandidateGrid, (row, col): (usize, usize)) -> DigitSet {
+    match grid.get_cell(row, col) {
+        0 => candidates.get(row, col),
+        value => DigitSet::single(value),
+    }
+}
+
+/// The candidates of the empty `cells` missing from their `supported` digits, i.e. those no
+/// arrangement of the variant's cells uses. Fails if a cell has no supported digit at all.
+fn unsupported(
+    grid: &SudokuGrid,
+    candidates: &CandidateGrid,
+    cells: &[(usize, usize)],
+    supported: &[DigitSet],
+    variant: &'static str,
+) -> Result<Eliminations, VariantContradiction> {
+    let mut eliminations = Vec::new();
+    for (&(row, col), &digits) in cells.iter().zip(supported) {
+        if digits.is_empty() {
+            return Err(VariantContradiction::NoPossibilities {
+                cell: (row, col),
+                variant,
+                reason: String::from("No candidate fits the variant"),
+            });
+        }
+        if grid.get_cell(row, col) == 0 {
+            let ruled_out = candidates.get(row, col) - digits;
+            eliminations.extend(ruled_out.iter().map(|digit| ((row, col), digit)));
+        }
+    }
+    Ok(eliminations)
+}
+
+/// Weak links between every pair of candidates of cells `a` and `b` that `allowed` rejects.
+fn pair_weak_links(
+    candidates: &CandidateGrid,
+    a: (usize, usize),
+    b: (usize, usize),
+    allowed: impl Fn(u8, u8) -> bool,
+) -> Vec<(Candidate, Candidate)> {
+    let mut links = Vec::new();
+    for d1 in candidates.get(a.0, a.1) {
+        for d2 in candidates.get(b.0, b.1) {
+            if !allowed(d1, d2) {
+                links.push(((a, d1), (b, d2)));
+            }
+        }
+    }
+    links
+}